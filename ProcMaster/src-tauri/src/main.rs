@@ -3,26 +3,47 @@
 use serde::Serialize;
 use std::thread;
 use std::time::Duration;
-use std::path::Path;
-use std::fs::File;
-use std::io::{self, BufRead};
-use tauri::command;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{command, Manager, RunEvent, State};
 use procfs::process::all_processes;
 use procfs::{ticks_per_second,Uptime};
 use sysinfo::{System, SystemExt, RefreshKind};
 use users::get_user_by_uid;
-use std::io::Write;
-use nix::sys::signal::{kill, Signal};
-use nix::unistd::Pid;
-use std::process::Command;
-use std::process::Stdio;
 
+#[path = "../../../src/cpuUsage.rs"]
+mod cpu_usage;
+#[path = "../../../src/ctrl.rs"]
+mod ctrl;
+#[path = "../../../src/cgroup.rs"]
+mod cgroup;
+#[path = "../../../src/config.rs"]
+mod config;
+#[path = "../../../src/audit.rs"]
+mod audit;
+#[path = "../../../src/suspended.rs"]
+mod suspended;
+#[path = "../../../src/schedule.rs"]
+mod schedule;
+#[path = "../../../src/limiter.rs"]
+mod limiter;
+#[path = "../../../src/helper.rs"]
+mod helper;
+#[path = "../../../src/launch.rs"]
+mod launch;
+#[path = "../../../src/supervisor.rs"]
+mod supervisor;
+#[path = "../../../src/syscalls.rs"]
+mod syscalls;
+#[path = "../../../src/trace.rs"]
+mod trace;
+#[path = "../../../src/sampler.rs"]
+mod sampler;
+#[path = "../../../src/zombies.rs"]
+mod zombies;
+#[path = "../../../src/hung.rs"]
+mod hung;
 
-#[derive(Serialize, Clone, Default, Debug)]
-pub struct CpuUsage {
-    cpu_usage: f64,
-    core_number: i32,
-}
 
 #[derive(Serialize, Clone, Default, Debug)]
 pub struct MemoryUsage {
@@ -34,57 +55,13 @@ pub struct MemoryUsage {
     pub total_swap: f64,
 }
 
-pub fn read_cpu_stat() -> io::Result<Vec<Vec<u64>>> {
-    let path = Path::new("/proc/stat");
-    let file = File::open(&path)?;
-    let reader = io::BufReader::new(file);
-
-    let mut cpu_stats = Vec::new();
-
-    for line in reader.lines() {
-        let line = line?;
-        if line.starts_with("cpu") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            let values: Vec<u64> = parts[1..]
-                .iter()
-                .map(|v| v.parse().unwrap_or(0))
-                .collect();
-            cpu_stats.push(values);
-        } else {
-            break;
-        }
-    }
-    Ok(cpu_stats)
-}
-
-pub fn calculate_cpu_usage(prev: &[u64], curr: &[u64]) -> f64 {
-    let prev_idle = prev[3] + prev[4];
-    let curr_idle = curr[3] + curr[4];
-
-    let prev_total: u64 = prev.iter().sum();
-    let curr_total: u64 = curr.iter().sum();
-
-    let total_diff = curr_total - prev_total;
-    let idle_diff = curr_idle - prev_idle;
-
-    100.0 * (total_diff - idle_diff) as f64 / total_diff as f64
-}
+/// Kept current by [`cpu_usage::start_background_update_cpu`], so this
+/// returns at once.
+type Cpu = Arc<Mutex<cpu_usage::CpuUsages>>;
 
 #[command]
-fn cpu_resultt() -> Vec<CpuUsage> {
-    let mut cpu_usages = Vec::new();
-    let prev_cpu_stats = read_cpu_stat().unwrap();
-    thread::sleep(Duration::from_secs(1));
-    let curr_cpu_stats = read_cpu_stat().unwrap();
-
-    for (i, (prev, curr)) in prev_cpu_stats.iter().zip(curr_cpu_stats.iter()).enumerate().skip(1) {
-        let cpu_usage = calculate_cpu_usage(prev, curr);
-        cpu_usages.push(CpuUsage {
-            cpu_usage,
-            core_number: i as i32 - 1, // Adjust core number to start from 0 for the first core
-        });
-    }
-    cpu_usages
+fn cpu_resultt(cpu: State<'_, Cpu>) -> cpu_usage::CpuUsages {
+    cpu.lock().unwrap().clone()
 }
 
 #[command]
@@ -126,6 +103,11 @@ pub struct ProcessInfo {
     ppid: i32,
     state: String,
     threads: i64,
+    io_priority: String,
+    oom_score: u32,
+    oom_score_adj: i32,
+    frozen: bool,
+    suspended_by_procmaster: bool,
 }
 
 fn seconds_to_hhmmss(seconds: f64) -> String {
@@ -147,6 +129,9 @@ pub fn get_processes_info() -> Vec<ProcessInfo> {
             let system_uptime = Uptime::new().unwrap().uptime; 
             // let total_cpu_time: f64 = stat().unwrap().cpu().cpu_time() as f64 / ticks_per_second;
             let total_memory = system.total_memory();
+            let cgroups = cgroup::CgroupFs::system();
+            let mut frozen_cgroups: HashMap<String, bool> = HashMap::new();
+            let suspended = suspended::load();
 
             for process in processes {
                 if let Ok(proc) = process {
@@ -195,6 +180,17 @@ pub fn get_processes_info() -> Vec<ProcessInfo> {
                         let ppid = stat.ppid; 
                         let state = stat.state.to_string(); 
                         let threads = stat.num_threads; 
+                        let io_priority = ctrl::get_io_priority(pid).map(|p| p.to_string()).unwrap_or_default();
+                        let oom_score = proc.oom_score().unwrap_or(0);
+                        let oom_score_adj = ctrl::get_oom_score_adj(pid).unwrap_or(0);
+                        let frozen = match cgroups.cgroup_of(pid) {
+                            Ok(cgroup) => *frozen_cgroups
+                                .entry(cgroup)
+                                .or_insert_with_key(|cgroup| cgroups.is_frozen(cgroup)),
+                            Err(_) => false,
+                        };
+                        let suspended_by_procmaster = suspended.iter()
+                            .any(|entry| entry.pid == pid && entry.starttime == stat.starttime);
 
                         let time = seconds_to_hhmmss(proc_usage_time);
 
@@ -213,6 +209,11 @@ pub fn get_processes_info() -> Vec<ProcessInfo> {
                             ppid,
                             state,
                             threads,
+                            io_priority,
+                            oom_score,
+                            oom_score_adj,
+                            frozen,
+                            suspended_by_procmaster,
                         });
                 }
             }
@@ -242,6 +243,13 @@ pub struct Process {
     pub ppid: i32,
     pub state: String,
     pub threads: i64,
+    pub io_priority: String,
+    pub oom_score: u32,
+    pub oom_score_adj: i32,
+    pub frozen: bool,
+    pub suspended_by_procmaster: bool,
+    /// Started from ProcMaster and still running.
+    pub launched: bool,
 }
 
 impl From<&ProcessInfo> for Process {
@@ -261,86 +269,534 @@ impl From<&ProcessInfo> for Process {
             ppid: info.ppid,
             state: info.state.clone(),
             threads: info.threads,
+            io_priority: info.io_priority.clone(),
+            oom_score: info.oom_score,
+            oom_score_adj: info.oom_score_adj,
+            frozen: info.frozen,
+            suspended_by_procmaster: info.suspended_by_procmaster,
+            launched: false,
         }
     }
 }
 
 #[command]
-fn get_processess() -> Vec<Process> {
+fn get_processess(launcher: State<'_, Launcher>) -> Vec<Process> {
+    let launcher = launcher.lock().unwrap();
     let processes_info = get_processes_info();
-    processes_info.iter().map(Process::from).collect()
+    processes_info.iter()
+        .map(Process::from)
+        .map(|process| Process { launched: launcher.is_launched(process.pid), ..process })
+        .collect()
+}
+
+/// Whether `pid` is protected and, if so, whether the guard asks or refuses.
+#[command]
+fn check_protection(pid: i32) -> ctrl::Protection {
+    ctrl::Guard::load().check(pid)
+}
+
+/// Which signals `pid` has pending, blocks, ignores and catches.
+#[command]
+fn signal_disposition(pid: i32) -> Result<ctrl::SignalDisposition, String> {
+    ctrl::SignalDisposition::read(pid)
+}
+
+/// Why sending `signal` (e.g. `SIGTERM`) to `pid` would do nothing yet, if it would not.
+#[command]
+fn signal_warning(pid: i32, signal: String) -> Result<Option<String>, String> {
+    let signal = signal.parse::<nix::sys::signal::Signal>().map_err(|_| format!("Unknown signal {}", signal))?;
+    Ok(ctrl::SignalDisposition::read(pid)?.warning(signal))
+}
+
+#[command]
+fn kill_process(pid: i32, bypass: Option<ctrl::Bypass>) -> Result<(), String> {
+    ctrl::kill_process(pid, bypass.unwrap_or_default())
 }
 
 #[command]
-fn kill_process(pid: i32) -> Result<(), String> {
-    send_signal(pid, Signal::SIGKILL)
+fn terminate_process(pid: i32, bypass: Option<ctrl::Bypass>) -> Result<(), String> {
+    ctrl::terminate_process(pid, bypass.unwrap_or_default())
 }
 
 #[command]
-fn terminate_process(pid: i32) -> Result<(), String> {
-    send_signal(pid, Signal::SIGTERM)
+fn suspend_process(pid: i32, bypass: Option<ctrl::Bypass>) -> Result<(), String> {
+    ctrl::suspend_process(pid, bypass.unwrap_or_default())
 }
 
 #[command]
-fn suspend_process(pid: i32) -> Result<(), String> {
-    send_signal(pid, Signal::SIGSTOP)
+fn freeze_process(pid: i32, bypass: Option<ctrl::Bypass>) -> Result<(), String> {
+    ctrl::freeze_process(pid, bypass.unwrap_or_default())
 }
 
+/// Thaws or SIGCONTs, whichever matches how the process was suspended.
 #[command]
 fn resume_process(pid: i32) -> Result<(), String> {
-    send_signal(pid, Signal::SIGCONT)
+    ctrl::resume_process(pid)
 }
 
+/// Processes ProcMaster stopped or froze that are still suspended.
 #[command]
-fn change_priority(pid: i32, priority: i32, password: String) -> Result<bool, String> {
-    if priority < -20 || priority > 19 {
-        return Err("Invalid priority value".to_string());
-    }
+fn suspended_processes() -> Vec<suspended::SuspendedProcess> {
+    suspended::load()
+}
 
-    let mut child = Command::new("sudo")
-        .arg("-S") // Read password from stdin
-        .arg("renice")
-        .arg(format!("{}", priority))
-        .arg("-p")
-        .arg(format!("{}", pid))
-        .stdin(Stdio::piped())
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
-
-    // Write the password to stdin
-    if let Some(stdin) = child.stdin.as_mut() {
-        stdin
-            .write_all(format!("{}\n", password).as_bytes())
-            .map_err(|e| format!("Failed to write to stdin: {}", e))?;
-    }
+/// Resumes everything ProcMaster suspended, ending the CPU limiters first, and
+/// returns the failures as "PID: error".
+#[command]
+fn resume_all_suspended(limiters: State<'_, Limiters>) -> Vec<String> {
+    limiters.lock().unwrap().remove_all();
+    ctrl::resume_suspended()
+        .into_iter()
+        .filter_map(|(process, result)| result.err().map(|err| format!("{}: {}", process.pid, err)))
+        .collect()
+}
+
+/// `warn` or `resume`: what the window should do on close while processes are still suspended.
+#[command]
+fn suspend_on_quit() -> config::OnQuit {
+    config::current().suspend.on_quit
+}
+
+/// Renices a process (`which` = "process"), process group ("pgrp") or user ("user").
+#[command]
+fn change_priority(pid: i32, priority: i32, which: Option<String>) -> Result<(), String> {
+    let target = match which.as_deref().unwrap_or("process") {
+        "process" => ctrl::PriorityTarget::Process(pid),
+        "pgrp" => ctrl::PriorityTarget::ProcessGroup(pid),
+        "user" => ctrl::PriorityTarget::User(pid as u32),
+        other => return Err(format!("Unknown priority target {}", other)),
+    };
+    ctrl::set_priority(target, priority).map_err(|err| err.to_string())
+}
+
+/// Tells the UI in advance whether lowering a nice value to `priority` can
+/// succeed, directly or through procmaster-helper.
+#[command]
+fn can_lower_priority(priority: i32) -> bool {
+    ctrl::may_lower_nice(priority) || helper::available()
+}
+
+#[command]
+fn get_affinity(pid: i32) -> Result<Vec<usize>, String> {
+    ctrl::get_affinity(pid).map_err(|err| err.to_string())
+}
+
+#[command]
+fn set_affinity(pid: i32, cpus: Vec<usize>, all_threads: bool) -> Result<(), String> {
+    ctrl::set_affinity(pid, &cpus, all_threads).map_err(|err| err.to_string())
+}
+
+#[command]
+fn get_io_priority(pid: i32) -> Result<ctrl::IoPriority, String> {
+    ctrl::get_io_priority(pid).map_err(|err| err.to_string())
+}
+
+/// `priority` is `rt/0`..`rt/7`, `be/0`..`be/7`, `idle` or `none`.
+#[command]
+fn set_io_priority(pid: i32, priority: String) -> Result<(), String> {
+    let priority: ctrl::IoPriority = priority.parse()?;
+    ctrl::set_io_priority(pid, priority).map_err(|err| err.to_string())
+}
+
+#[derive(Serialize)]
+struct SchedulerInfo {
+    policy: ctrl::SchedPolicy,
+    priority: i32,
+    /// Priority range of `policy`, for validating the input before applying.
+    min_priority: i32,
+    max_priority: i32,
+}
+
+#[command]
+fn get_scheduler(pid: i32) -> Result<SchedulerInfo, String> {
+    let (policy, priority) = ctrl::get_scheduler(pid).map_err(|err| err.to_string())?;
+    let (min_priority, max_priority) = policy.priority_range().map_err(|err| err.to_string())?;
+    Ok(SchedulerInfo { policy, priority, min_priority, max_priority })
+}
+
+#[command]
+fn sched_priority_range(policy: String) -> Result<(i32, i32), String> {
+    let policy: ctrl::SchedPolicy = policy.parse()?;
+    policy.priority_range().map_err(|err| err.to_string())
+}
+
+/// The webview must confirm with the user before sending a real-time policy.
+#[command]
+fn set_scheduler(pid: i32, policy: String, priority: i32, all_threads: bool) -> Result<(), String> {
+    let policy: ctrl::SchedPolicy = policy.parse()?;
+    ctrl::set_scheduler(pid, policy, priority, all_threads).map_err(|err| err.to_string())
+}
+
+#[command]
+fn set_oom_score_adj(pid: i32, value: i32) -> Result<(), String> {
+    ctrl::set_oom_score_adj(pid, value).map_err(|err| err.to_string())
+}
+
+#[command]
+fn cgroup_info(pid: i32) -> Result<cgroup::CgroupInfo, String> {
+    let cgroups = cgroup::CgroupFs::system();
+    let path = cgroups.cgroup_of(pid).map_err(|err| err.to_string())?;
+    Ok(cgroups.info(&path))
+}
+
+/// Sets `limit` (e.g. "memory.max") on the cgroup `pid` currently lives in.
+#[command]
+fn cgroup_set_limit(pid: i32, limit: String, value: String) -> Result<(), String> {
+    let limit: cgroup::CgroupLimit = limit.parse()?;
+    let path = cgroup::CgroupFs::system().cgroup_of(pid).map_err(|err| err.to_string())?;
+    ctrl::set_cgroup_limit(pid, &path, limit, &value).map_err(|err| err.to_string())
+}
+
+/// Moves `pid` into `cgroup`, creating the cgroup first if needed.
+#[command]
+fn cgroup_move(pid: i32, cgroup: String) -> Result<(), String> {
+    ctrl::move_to_cgroup(pid, &cgroup).map_err(|err| err.to_string())
+}
+
+/// The last `limit` control actions from the audit log, newest first.
+#[command]
+fn audit_log(limit: usize) -> Vec<audit::AuditEntry> {
+    audit::recent(limit)
+}
 
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to read output: {}", e))?;
+/// Lists the processes a killall would hit, without signalling any. Pass the
+/// result to `killall_previewed` once the user has confirmed it.
+#[command]
+fn killall(
+    pattern: String,
+    mode: String,
+    field: String,
+    user: Option<String>,
+    min_age: Option<String>,
+    state: Option<char>,
+    signal: String,
+) -> Result<Vec<ctrl::KillallMatch>, String> {
+    let options = ctrl::KillallOptions {
+        matcher: ctrl::Matcher::parse(&mode, &pattern)?,
+        field: field.parse()?,
+        user: user.as_deref().map(ctrl::resolve_user).transpose()?,
+        min_age: min_age.as_deref().map(ctrl::parse_age).transpose()?,
+        state,
+        signal: ctrl::parse_signal(&signal)?,
+        dry_run: true,
+        bypass: ctrl::Bypass::None,
+    };
+    ctrl::killall(&options)
+}
 
-    if output.status.success() {
-        Ok(true)
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(stderr.into_owned())
+/// Signals exactly the processes a `killall` preview returned, skipping any
+/// whose PID now belongs to a different process.
+#[command]
+fn killall_previewed(matches: Vec<ctrl::KillallMatch>, signal: String, bypass: Option<ctrl::Bypass>) -> Result<Vec<ctrl::KillallMatch>, String> {
+    Ok(ctrl::killall_previewed(&matches, ctrl::parse_signal(&signal)?, bypass.unwrap_or_default()))
+}
+
+type Schedule = Arc<Mutex<schedule::ActionQueue>>;
+
+/// Queues an action on `pid` from a spec such as "cont in 10m" or "term at 02:00".
+#[command]
+fn schedule_action(schedule: State<'_, Schedule>, pid: i32, spec: String, bypass: Option<ctrl::Bypass>) -> Result<u64, String> {
+    let spec: schedule::ScheduleSpec = spec.parse()?;
+    schedule.lock().unwrap().schedule(pid, spec, bypass.unwrap_or_default())
+}
+
+#[command]
+fn scheduled_actions(schedule: State<'_, Schedule>) -> Vec<schedule::ScheduledAction> {
+    schedule.lock().unwrap().pending().to_vec()
+}
+
+/// The last scheduled actions that fired, most recent first, each with the
+/// error it failed with, if any.
+#[command]
+fn fired_actions(schedule: State<'_, Schedule>) -> Vec<(schedule::ScheduledAction, Option<String>)> {
+    schedule.lock().unwrap().fired().iter().map(|(action, result)| (action.clone(), result.clone().err())).collect()
+}
+
+#[command]
+fn cancel_scheduled(schedule: State<'_, Schedule>, id: u64) -> bool {
+    schedule.lock().unwrap().cancel(id).is_some()
+}
+
+type Limiters = Arc<Mutex<limiter::Limiters>>;
+
+/// Holds `pid` (and its children with `children`) under `percent` of one CPU by
+/// stopping and continuing it, for hosts without cgroup delegation.
+#[command]
+fn cpu_limit(limiters: State<'_, Limiters>, pid: i32, percent: f64, children: bool, bypass: Option<ctrl::Bypass>) -> Result<u64, String> {
+    limiters.lock().unwrap().start(pid, percent, children, bypass.unwrap_or_default())
+}
+
+#[command]
+fn cpu_limiters(limiters: State<'_, Limiters>) -> Vec<limiter::LimiterInfo> {
+    limiters.lock().unwrap().list()
+}
+
+/// Removes a limiter; its processes are continued first.
+#[command]
+fn remove_cpu_limit(limiters: State<'_, Limiters>, id: u64) -> bool {
+    limiters.lock().unwrap().remove(id)
+}
+
+type Launcher = Arc<Mutex<launch::Launcher>>;
+
+#[derive(Serialize)]
+struct LaunchedProcess {
+    pid: i32,
+    command: String,
+    started: i64,
+    status: String,
+    running: bool,
+}
+
+/// Starts a command from a run spec such as "-n 10 -c 0-1 ./bench --fast" and
+/// returns its PID.
+#[command]
+fn launch_process(launcher: State<'_, Launcher>, spec: String) -> Result<i32, String> {
+    let spec: launch::LaunchSpec = spec.parse()?;
+    launcher.lock().unwrap().launch(&spec)
+}
+
+#[command]
+fn launched_processes(launcher: State<'_, Launcher>) -> Vec<LaunchedProcess> {
+    launcher.lock().unwrap().list().iter()
+        .map(|launched| LaunchedProcess {
+            pid: launched.pid,
+            command: launched.command.clone(),
+            started: launched.started,
+            status: launched.status(),
+            running: launched.is_running(),
+        })
+        .collect()
+}
+
+/// Captured stdout and stderr of a launched process, oldest line first.
+#[command]
+fn launched_output(launcher: State<'_, Launcher>, pid: i32) -> Vec<launch::OutputLine> {
+    launcher.lock().unwrap().get(pid).map(launch::Launched::output).unwrap_or_default()
+}
+
+#[command]
+fn clear_launched(launcher: State<'_, Launcher>) {
+    launcher.lock().unwrap().clear_finished();
+}
+
+type Supervisor = Arc<Mutex<supervisor::Supervisor>>;
+
+#[command]
+fn services(supervisor: State<'_, Supervisor>) -> Vec<supervisor::ServiceStatus> {
+    supervisor.lock().unwrap().status()
+}
+
+/// Adds a service from the dialog form, e.g.
+/// "name=worker restart=always backoff=1s,5s -- -n 10 ./worker", and saves it
+/// to the config file.
+#[command]
+fn add_service(supervisor: State<'_, Supervisor>, spec: String) -> Result<(), String> {
+    supervisor.lock().unwrap().add(spec.parse()?)
+}
+
+#[command]
+fn remove_service(supervisor: State<'_, Supervisor>, name: String) -> Result<(), String> {
+    supervisor.lock().unwrap().remove(&name)
+}
+
+#[command]
+fn start_service(supervisor: State<'_, Supervisor>, name: String) -> Result<(), String> {
+    supervisor.lock().unwrap().start(&name)
+}
+
+#[command]
+fn stop_service(supervisor: State<'_, Supervisor>, name: String) -> Result<(), String> {
+    supervisor.lock().unwrap().stop(&name)
+}
+
+#[command]
+fn restart_service(supervisor: State<'_, Supervisor>, name: String) -> Result<(), String> {
+    supervisor.lock().unwrap().restart(&name)
+}
+
+/// The syscall trace, if one is attached; replacing or clearing it detaches.
+type Trace = Mutex<Option<trace::Tracer>>;
+
+#[derive(Serialize)]
+struct TraceView {
+    pid: i32,
+    command: String,
+    threads: Vec<i32>,
+    ended: Option<String>,
+    log: Vec<trace::TraceLine>,
+    in_syscall: Vec<trace::InSyscall>,
+    summary: Vec<trace::SyscallSummary>,
+}
+
+/// Traces the syscalls of `pid`, or of just one thread when given a TID,
+/// detaching from whatever was traced before.
+#[command]
+fn trace_attach(trace: State<'_, Trace>, launcher: State<'_, Launcher>, pid: i32) -> Result<(), String> {
+    // Its ptrace stops would be reported to the launcher's wait instead of the tracer.
+    if launcher.lock().unwrap().is_launched(pid) {
+        return Err(format!("{} was started by ProcMaster and cannot be traced from it, use strace", pid));
     }
+    let mut trace = trace.lock().unwrap();
+    *trace = None;
+    *trace = Some(trace::Tracer::attach(pid)?);
+    Ok(())
 }
 
+#[command]
+fn trace_detach(trace: State<'_, Trace>) {
+    *trace.lock().unwrap() = None;
+}
+
+#[command]
+fn trace_view(trace: State<'_, Trace>) -> Option<TraceView> {
+    let trace = trace.lock().unwrap();
+    let tracer = trace.as_ref()?;
+    Some(TraceView {
+        pid: tracer.pid,
+        command: tracer.command.clone(),
+        threads: tracer.threads(),
+        ended: tracer.ended(),
+        log: tracer.log(),
+        in_syscall: tracer.in_syscall(),
+        summary: tracer.summary(),
+    })
+}
+
+/// The wait-site sampler, if one runs; replacing or clearing it stops it.
+type Sampler = Mutex<Option<sampler::Sampler>>;
 
-fn send_signal(pid: i32, signal: Signal) -> Result<(), String> {
-    let pid = Pid::from_raw(pid);
-    match kill(pid, signal) {
-        Ok(_) => Ok(()),
-        Err(err) => Err(format!("Failed to send signal to process {}: {}", pid, err)),
+#[derive(Serialize)]
+struct SampleView {
+    pid: i32,
+    command: String,
+    interval_ms: u128,
+    rounds: u64,
+    has_stacks: bool,
+    has_syscalls: bool,
+    ended: Option<String>,
+    sites: Vec<sampler::WaitSite>,
+}
+
+/// Starts sampling where the threads of `pid` wait, replacing any previous sampler.
+#[command]
+fn sample_start(sampler: State<'_, Sampler>, pid: i32) -> Result<(), String> {
+    let mut sampler = sampler.lock().unwrap();
+    *sampler = None;
+    *sampler = Some(sampler::Sampler::start(pid, sampler::DEFAULT_INTERVAL)?);
+    Ok(())
+}
+
+#[command]
+fn sample_stop(sampler: State<'_, Sampler>) {
+    *sampler.lock().unwrap() = None;
+}
+
+#[command]
+fn sample_reset(sampler: State<'_, Sampler>) {
+    if let Some(sampler) = sampler.lock().unwrap().as_ref() {
+        sampler.reset();
     }
 }
 
+#[command]
+fn sample_view(sampler: State<'_, Sampler>) -> Option<SampleView> {
+    let sampler = sampler.lock().unwrap();
+    let sampler = sampler.as_ref()?;
+    Some(SampleView {
+        pid: sampler.pid,
+        command: sampler.command.clone(),
+        interval_ms: sampler.interval.as_millis(),
+        rounds: sampler.rounds(),
+        has_stacks: sampler.has_stacks(),
+        has_syscalls: sampler.has_syscalls(),
+        ended: sampler.ended(),
+        sites: sampler.histogram(),
+    })
+}
+
+/// Updated every second from `main`, so zombie ages add up.
+type Zombies = Arc<Mutex<zombies::Zombies>>;
+
+#[command]
+fn zombie_report(zombies: State<'_, Zombies>) -> zombies::ZombieReport {
+    zombies.lock().unwrap().report().clone()
+}
+
+/// Sends SIGCHLD to a zombie parent so it reaps them; terminating it goes
+/// through `terminate_process`.
+#[command]
+fn nudge_zombie_parent(pid: i32) -> Result<(), String> {
+    zombies::nudge_parent(pid)
+}
+
+/// Updated every second from `main`, like [`Zombies`].
+type HungTasks = Arc<Mutex<hung::HungTasks>>;
+
+/// Threads in D state for longer than `hung_tasks.threshold`, longest first.
+#[command]
+fn hung_tasks(hung: State<'_, HungTasks>) -> Vec<hung::HungTask> {
+    hung.lock().unwrap().hung().to_vec()
+}
+
 fn main() 
 {
+    // Freezing and thawing only clean up around their own target, so
+    // leftovers from a crash elsewhere in the hierarchy go here, once.
+    cgroup::CgroupFs::system().remove_stale_freeze_cgroups();
+
+    let cpu = Cpu::default();
+    cpu_usage::start_background_update_cpu(Arc::clone(&cpu));
+
+    let schedule = Schedule::default();
+    let runner = Arc::clone(&schedule);
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(1));
+        // The outcomes stay in the queue for `fired_actions`.
+        runner.lock().unwrap().run_due();
+    });
+
+    let launcher = Launcher::default();
+    let supervisor: Supervisor = Arc::new(Mutex::new(supervisor::Supervisor::load().unwrap_or_else(|err| {
+        eprintln!("Supervisor disabled: {}", err);
+        supervisor::Supervisor::default()
+    })));
+    let (ticker, ticker_launcher) = (Arc::clone(&supervisor), Arc::clone(&launcher));
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_millis(500));
+        ticker.lock().unwrap().tick(&mut ticker_launcher.lock().unwrap());
+    });
+
+    let zombies = Zombies::default();
+    let hung: HungTasks = Arc::new(Mutex::new(hung::HungTasks::load().unwrap_or_else(|err| {
+        eprintln!("Using the default hung task threshold: {}", err);
+        hung::HungTasks::default()
+    })));
+    let (scanner, hung_scanner) = (Arc::clone(&zombies), Arc::clone(&hung));
+    thread::spawn(move || loop {
+        scanner.lock().unwrap().update();
+        hung_scanner.lock().unwrap().update();
+        thread::sleep(Duration::from_secs(1));
+    });
+
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![cpu_resultt, get_processess, Mem_Usage, kill_process, terminate_process, suspend_process, resume_process, change_priority])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .manage(cpu)
+        .manage(schedule)
+        .manage(Limiters::default())
+        .manage(launcher)
+        .manage(supervisor)
+        .manage(Trace::default())
+        .manage(Sampler::default())
+        .manage(zombies)
+        .manage(hung)
+        .invoke_handler(tauri::generate_handler![cpu_resultt, get_processess, Mem_Usage, check_protection, signal_disposition, signal_warning, kill_process, terminate_process, suspend_process, freeze_process, resume_process, change_priority, can_lower_priority, get_affinity, set_affinity, get_io_priority, set_io_priority, get_scheduler, sched_priority_range, set_scheduler, set_oom_score_adj, cgroup_info, cgroup_set_limit, cgroup_move, killall, killall_previewed, audit_log, suspended_processes, resume_all_suspended, suspend_on_quit, schedule_action, scheduled_actions, fired_actions, cancel_scheduled, cpu_limit, cpu_limiters, remove_cpu_limit, launch_process, launched_processes, launched_output, clear_launched, services, add_service, remove_service, start_service, stop_service, restart_service, trace_attach, trace_detach, trace_view, sample_start, sample_stop, sample_reset, sample_view, zombie_report, nudge_zombie_parent, hung_tasks])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Never leave a process stopped mid duty cycle behind.
+            if let RunEvent::Exit = event {
+                app.state::<Limiters>().lock().unwrap().remove_all();
+                *app.state::<Trace>().lock().unwrap() = None;
+            }
+        });
 }
\ No newline at end of file
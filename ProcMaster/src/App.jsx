@@ -1,5 +1,6 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
 import "./App.css";
 import Box from '@mui/material/Box';
 import Tab from '@mui/material/Tab';
@@ -9,8 +10,20 @@ import TabPanel from '@mui/lab/TabPanel';
 import TableRowsIcon from '@mui/icons-material/TableRows';
 import MemoryIcon from '@mui/icons-material/Memory';
 import SpeedIcon from '@mui/icons-material/Speed';
+import HistoryIcon from '@mui/icons-material/History';
+import PlayArrowIcon from '@mui/icons-material/PlayArrow';
+import AutorenewIcon from '@mui/icons-material/Autorenew';
+import ManageSearchIcon from '@mui/icons-material/ManageSearch';
+import HourglassBottomIcon from '@mui/icons-material/HourglassBottom';
+import PersonOffIcon from '@mui/icons-material/PersonOff';
 import SysInfo from "./sysinfo"; 
 import ProcessTable from "./processtable";
+import ActionsPanel from "./actions";
+import LaunchedPanel from "./launched";
+import ServicesPanel from "./services";
+import TracePanel from "./trace";
+import SamplerPanel from "./sampler";
+import ZombiesPanel from "./zombies";
 function App() {
   const [greetMsg, setGreetMsg] = useState("");
   const [name, setName] = useState("");
@@ -33,6 +46,23 @@ function App() {
 
   const [value, setValue] = useState('1');
 
+  // Processes ProcMaster stopped stay stopped after it exits, so closing the
+  // window resumes them or asks first, per `suspend.on_quit` in the config.
+  useEffect(() => {
+    const unlisten = getCurrentWindow().onCloseRequested(async () => {
+      const suspended = await invoke("suspended_processes");
+      if (suspended.length === 0) return;
+      const onQuit = await invoke("suspend_on_quit");
+      const list = suspended.map((p) => `${p.pid} ${p.command}`).join("\n");
+      if (onQuit === "resume" || window.confirm(`These processes ProcMaster suspended are still stopped:\n${list}\n\nResume them before closing?`)) {
+        await invoke("resume_all_suspended");
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const handleValueChange = (event, newValue) => {
     setValue(newValue);
   };
@@ -63,6 +93,54 @@ function App() {
           sx={{ flexGrow: 1, color: '#fff', '&.Mui-selected': {
             color: '#6573ed'}}}
         />
+        <Tab 
+          label="Actions" 
+          value="3" 
+          icon={<HistoryIcon />} 
+          iconPosition="start" 
+          sx={{ flexGrow: 1, color: '#fff', '&.Mui-selected': {
+            color: '#6573ed'}}}
+        />
+        <Tab 
+          label="Launched" 
+          value="4" 
+          icon={<PlayArrowIcon />} 
+          iconPosition="start" 
+          sx={{ flexGrow: 1, color: '#fff', '&.Mui-selected': {
+            color: '#6573ed'}}}
+        />
+        <Tab 
+          label="Services" 
+          value="5" 
+          icon={<AutorenewIcon />} 
+          iconPosition="start" 
+          sx={{ flexGrow: 1, color: '#fff', '&.Mui-selected': {
+            color: '#6573ed'}}}
+        />
+        <Tab 
+          label="Trace" 
+          value="6" 
+          icon={<ManageSearchIcon />} 
+          iconPosition="start" 
+          sx={{ flexGrow: 1, color: '#fff', '&.Mui-selected': {
+            color: '#6573ed'}}}
+        />
+        <Tab 
+          label="Sampler" 
+          value="7" 
+          icon={<HourglassBottomIcon />} 
+          iconPosition="start" 
+          sx={{ flexGrow: 1, color: '#fff', '&.Mui-selected': {
+            color: '#6573ed'}}}
+        />
+        <Tab 
+          label="Zombies" 
+          value="8" 
+          icon={<PersonOffIcon />} 
+          iconPosition="start" 
+          sx={{ flexGrow: 1, color: '#fff', '&.Mui-selected': {
+            color: '#6573ed'}}}
+        />
       </TabList>
     </Box>
     <TabPanel value="1" style={{ padding: 0, margin: 0 }}>
@@ -71,6 +149,24 @@ function App() {
     <TabPanel value="2" style={{ padding: 0, margin: 0 }}>
       <SysInfo />
     </TabPanel>
+    <TabPanel value="3" style={{ padding: 0, margin: 0 }}>
+      <ActionsPanel />
+    </TabPanel>
+    <TabPanel value="4" style={{ padding: 0, margin: 0 }}>
+      <LaunchedPanel />
+    </TabPanel>
+    <TabPanel value="5" style={{ padding: 0, margin: 0 }}>
+      <ServicesPanel />
+    </TabPanel>
+    <TabPanel value="6" style={{ padding: 0, margin: 0 }}>
+      <TracePanel />
+    </TabPanel>
+    <TabPanel value="7" style={{ padding: 0, margin: 0 }}>
+      <SamplerPanel />
+    </TabPanel>
+    <TabPanel value="8" style={{ padding: 0, margin: 0 }}>
+      <ZombiesPanel />
+    </TabPanel>
   </TabContext>
 </main>
   );
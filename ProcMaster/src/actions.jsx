@@ -0,0 +1,152 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./processtable.css";
+
+// Active CPU limiters, pending and recently fired scheduled actions, then the history of control
+// actions from the audit log, newest first.
+const ActionsPanel = () => {
+  const [entries, setEntries] = useState([]);
+  const [scheduled, setScheduled] = useState([]);
+  const [fired, setFired] = useState([]);
+  const [limiters, setLimiters] = useState([]);
+
+  useEffect(() => {
+    const fetchEntries = async () => {
+      try {
+        setEntries(await invoke("audit_log", { limit: 500 }));
+        setScheduled(await invoke("scheduled_actions"));
+        setFired(await invoke("fired_actions"));
+        setLimiters(await invoke("cpu_limiters"));
+      } catch (error) {
+        console.error("Error fetching audit log:", error);
+      }
+    };
+    fetchEntries();
+    const interval = setInterval(fetchEntries, 2000);
+    return () => clearInterval(interval);
+  }, []);
+
+  const cancelScheduled = async (id) => {
+    await invoke("cancel_scheduled", { id });
+    setScheduled((actions) => actions.filter((action) => action.id !== id));
+  };
+
+  const removeLimiter = async (id) => {
+    await invoke("remove_cpu_limit", { id });
+    setLimiters((active) => active.filter((limiter) => limiter.id !== id));
+  };
+
+  const formatAction = (action) =>
+    typeof action === "string" ? action.toLowerCase() : `renice ${action.Renice}`;
+
+  return (
+    <div className="process-table-container">
+      {limiters.length > 0 && (
+        <table className="process-table">
+          <thead>
+            <tr>
+              <th>PID</th>
+              <th>Command</th>
+              <th>CPU limit</th>
+              <th>Measured</th>
+              <th>Children</th>
+              <th></th>
+            </tr>
+          </thead>
+          <tbody>
+            {limiters.map((limiter) => (
+              <tr key={limiter.id}>
+                <td>{limiter.pid}</td>
+                <td>{limiter.command}</td>
+                <td>{limiter.target}%</td>
+                <td>{limiter.usage.toFixed(1)}%</td>
+                <td>{limiter.children ? `yes (${limiter.processes})` : "no"}</td>
+                <td>
+                  <button onClick={() => removeLimiter(limiter.id)}>Remove</button>
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+      {scheduled.length > 0 && (
+        <table className="process-table">
+          <thead>
+            <tr>
+              <th>Due</th>
+              <th>PID</th>
+              <th>Command</th>
+              <th>Scheduled action</th>
+              <th></th>
+            </tr>
+          </thead>
+          <tbody>
+            {scheduled.map((action) => (
+              <tr key={action.id}>
+                <td>{new Date(action.due * 1000).toLocaleTimeString()}</td>
+                <td>{action.pid}</td>
+                <td>{action.command}</td>
+                <td>{formatAction(action.action)}</td>
+                <td>
+                  <button onClick={() => cancelScheduled(action.id)}>Cancel</button>
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+      {fired.length > 0 && (
+        <table className="process-table">
+          <thead>
+            <tr>
+              <th>Fired</th>
+              <th>PID</th>
+              <th>Command</th>
+              <th>Scheduled action</th>
+              <th>Result</th>
+            </tr>
+          </thead>
+          <tbody>
+            {fired.map(([action, error]) => (
+              <tr key={action.id}>
+                <td>{new Date(action.due * 1000).toLocaleTimeString()}</td>
+                <td>{action.pid}</td>
+                <td>{action.command}</td>
+                <td>{formatAction(action.action)}</td>
+                <td style={{ color: error ? "red" : "green" }}>{error ?? "ok"}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+      <table className="process-table">
+        <thead>
+          <tr>
+            <th>Time</th>
+            <th>User</th>
+            <th>PID</th>
+            <th>Command</th>
+            <th>Operation</th>
+            <th>Arguments</th>
+            <th>Result</th>
+          </tr>
+        </thead>
+        <tbody>
+          {entries.map((entry, index) => (
+            <tr key={index}>
+              <td>{entry.timestamp}</td>
+              <td>{entry.user}</td>
+              <td>{entry.pid ?? "-"}</td>
+              <td>{entry.command ?? ""}</td>
+              <td>{entry.operation}</td>
+              <td>{JSON.stringify(entry.args)}</td>
+              <td style={{ color: entry.ok ? "green" : "red" }}>{entry.ok ? "ok" : entry.error}</td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </div>
+  );
+};
+
+export default ActionsPanel;
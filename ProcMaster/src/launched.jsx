@@ -0,0 +1,93 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./processtable.css";
+
+// Commands started from ProcMaster, and the captured output of the selected one.
+const LaunchedPanel = () => {
+  const [spec, setSpec] = useState("");
+  const [error, setError] = useState(null);
+  const [launched, setLaunched] = useState([]);
+  const [selected, setSelected] = useState(null);
+  const [output, setOutput] = useState([]);
+
+  useEffect(() => {
+    const fetchLaunched = async () => {
+      try {
+        setLaunched(await invoke("launched_processes"));
+        if (selected !== null) {
+          setOutput(await invoke("launched_output", { pid: selected }));
+        }
+      } catch (error) {
+        console.error("Error fetching launched processes:", error);
+      }
+    };
+    fetchLaunched();
+    const interval = setInterval(fetchLaunched, 1000);
+    return () => clearInterval(interval);
+  }, [selected]);
+
+  const run = async (event) => {
+    event.preventDefault();
+    try {
+      const pid = await invoke("launch_process", { spec });
+      setSpec("");
+      setError(null);
+      setSelected(pid);
+    } catch (error) {
+      setError(String(error));
+    }
+  };
+
+  const clearFinished = async () => {
+    await invoke("clear_launched");
+    setLaunched((entries) => entries.filter((entry) => entry.running));
+  };
+
+  return (
+    <div className="process-table-container">
+      <form className="launch-form" onSubmit={run}>
+        <input
+          value={spec}
+          onChange={(e) => setSpec(e.target.value)}
+          placeholder="-d DIR -e KEY=VALUE -n NICE -i be/7 -c 0-3 -l nofile=1024 -g CGROUP command..."
+        />
+        <button type="submit">Run</button>
+        <button type="button" onClick={clearFinished}>Clear finished</button>
+      </form>
+      {error && <div className="launch-error">{error}</div>}
+      <table className="process-table">
+        <thead>
+          <tr>
+            <th>PID</th>
+            <th>Started</th>
+            <th>Status</th>
+            <th>Command</th>
+          </tr>
+        </thead>
+        <tbody>
+          {launched.map((entry) => (
+            <tr
+              key={entry.pid}
+              onClick={() => setSelected(entry.pid)}
+              className={entry.pid === selected ? "selected" : undefined}
+            >
+              <td>{entry.pid}</td>
+              <td>{new Date(entry.started * 1000).toLocaleTimeString()}</td>
+              <td style={{ color: entry.running ? "green" : "gray" }}>{entry.status}</td>
+              <td>{entry.command}</td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+      {selected !== null && (
+        <pre className="launch-output">
+          {output.map((line, index) => (
+            <div key={index} style={line.stderr ? { color: "red" } : undefined}>{line.text}</div>
+          ))}
+        </pre>
+      )}
+    </div>
+  );
+};
+
+export default LaunchedPanel;
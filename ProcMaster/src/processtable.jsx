@@ -12,7 +12,8 @@ const ProcessTable = () => {
   const [showPriorityDialog, setShowPriorityDialog] = useState(false);
   const [selectedPriority, setSelectedPriority] = useState(0);
   const [errorMessage, setErrorMessage] = useState('');
-  const [password, setPassword] = useState('');
+  const [canLower, setCanLower] = useState(true);
+  const [hungTasks, setHungTasks] = useState([]);
 
 
   useEffect(() => {
@@ -28,6 +29,7 @@ const ProcessTable = () => {
         }
         
         setProcesses(updatedProcesses); // Update processes with sorting applied
+        setHungTasks(await invoke("hung_tasks"));
         setLoading(false);
       } catch (error) {
         console.error("Error fetching processes:", error);
@@ -54,21 +56,99 @@ const ProcessTable = () => {
     });
   };
 
+  const SIGNALS = { kill: "SIGKILL", terminate: "SIGTERM", suspend: "SIGSTOP" };
+
+  // Asks before a signal and returns the bypass to send, or null if the user
+  // backed out. Protected processes need a second confirmation ("confirm"
+  // mode) or an explicit force ("refuse" mode). A signal the process ignores
+  // or blocks is always confirmed, with the reason.
+  const confirmSignal = async (pid, action) => {
+    const process = processes.find((p) => p.pid === pid);
+    let label = `${action} PID ${pid} (${process ? process.command : "?"}, user ${process ? process.user : "?"})`;
+    const warning = SIGNALS[action]
+      ? await invoke("signal_warning", { pid, signal: SIGNALS[action] }).catch(() => null)
+      : null;
+    if (warning) {
+      label = `${label}\n\n${warning}`;
+    }
+    const protection = await invoke("check_protection", { pid });
+    if (protection.Confirm) {
+      if (!window.confirm(`${label}?\n\nProtected: ${protection.Confirm}`)) return null;
+      return window.confirm(`Really ${label}? It is protected.`) ? "confirmed" : null;
+    }
+    if (protection.Refuse) {
+      return window.confirm(`Refused: ${label} is protected (${protection.Refuse}).\n\nForce it anyway?`) ? "forced" : null;
+    }
+    if (action === "kill" || action === "terminate" || warning) {
+      return window.confirm(`${label}?`) ? "none" : null;
+    }
+    return "none";
+  };
+
   const handleAction = async (pid, action) => {
     try {
+      if (["kill", "terminate", "suspend", "freeze"].includes(action)) {
+        const bypass = await confirmSignal(pid, action);
+        if (bypass === null) return;
+        await invoke(`${action}_process`, { pid, bypass });
+        if (action === "kill" || action === "terminate") {
+          // Remove the killed process from the processes state
+          setProcesses((prevProcesses) => prevProcesses.filter((process) => process.pid !== pid));
+        }
+        return;
+      }
       switch (action) {
-        case "kill":
-          await invoke("kill_process", { pid });
+        case "resume":
+          await invoke("resume_process", { pid });
           break;
-        case "terminate":
-          await invoke("terminate_process", { pid });
+        case "schedule": {
+          const spec = window.prompt(
+            'Schedule an action, e.g. "cont in 10m", "term at 02:00" or "renice 0 in 1h"'
+          );
+          if (!spec) break;
+          // The action fires unattended, so a protected target is confirmed now.
+          let bypass = "none";
+          if (/^(term|terminate|kill|stop|suspend|freeze)\b/.test(spec.trim())) {
+            const protection = await invoke("check_protection", { pid });
+            if (protection.Confirm) {
+              if (!window.confirm(`PID ${pid} is protected (${protection.Confirm}). Schedule "${spec}" anyway?`)) break;
+              bypass = "confirmed";
+            } else if (protection.Refuse) {
+              if (!window.confirm(`Refused: PID ${pid} is protected (${protection.Refuse}).\n\nForce "${spec}" anyway?`)) break;
+              bypass = "forced";
+            }
+          }
+          await invoke("schedule_action", { pid, spec, bypass }).catch((error) => window.alert(error));
           break;
-        case "suspend":
-          await invoke("suspend_process", { pid });
+        }
+        case "limit": {
+          const percent = window.prompt("Limit CPU to what % of one CPU?");
+          if (percent) {
+            const children = window.confirm("Include its child processes?");
+            await invoke("cpu_limit", { pid, percent: parseFloat(percent), children })
+              .catch((error) => window.alert(error));
+          }
           break;
-        case "resume":
-          await invoke("resume_process", { pid });
+        }
+        case "trace":
+          await invoke("trace_attach", { pid })
+            .then(() => window.alert(`Tracing ${pid}, see the Trace tab`))
+            .catch((error) => window.alert(error));
+          break;
+        case "sample":
+          await invoke("sample_start", { pid })
+            .then(() => window.alert(`Sampling ${pid}, see the Sampler tab`))
+            .catch((error) => window.alert(error));
+          break;
+        case "signals": {
+          const list = (signals) => (signals.length ? signals.join(" ") : "none");
+          await invoke("signal_disposition", { pid })
+            .then((d) => window.alert(
+              `Pending: ${list(d.pending)}\nBlocked: ${list(d.blocked)}\nIgnored: ${list(d.ignored)}\nCaught: ${list(d.caught)}`
+            ))
+            .catch((error) => window.alert(error));
           break;
+        }
         case "priority":
           setSelectedPriority(selectedProcess.nice || 0); // Initialize with current nice value
           setErrorMessage('');
@@ -78,11 +158,6 @@ const ProcessTable = () => {
           break;
       }
 
-      if (action === "kill" || action === "terminate") 
-      {
-        // Remove the killed process from the processes state
-        setProcesses((prevProcesses) => prevProcesses.filter((process) => process.pid !== pid));
-      }
     } catch (error) {
       console.error("Error performing action on process:", error);
     }
@@ -131,35 +206,107 @@ const ProcessTable = () => {
     };
   }, [contextMenu]);
 
+  useEffect(() => {
+    if (!showPriorityDialog || !selectedProcess) return;
+    if (selectedPriority >= selectedProcess.nice) {
+      setCanLower(true);
+      return;
+    }
+    invoke("can_lower_priority", { priority: selectedPriority })
+      .then(setCanLower)
+      .catch(() => setCanLower(false));
+  }, [showPriorityDialog, selectedPriority, selectedProcess]);
+
   const handleChangePriority = async () => {
     try {
-      const result = await invoke("change_priority", {
+      await invoke("change_priority", {
         pid: selectedProcess.pid,
         priority: selectedPriority,
-        password,
       });
-      if (result) {
-        setShowPriorityDialog(false);
-      } else {
-        setErrorMessage("Failed to change priority. Incorrect password or insufficient permissions.");
-      }
+      setShowPriorityDialog(false);
     } catch (error) {
       console.error("Error changing priority:", error);
-      setErrorMessage("An error occurred while changing priority.");
+      setErrorMessage(`Failed to change priority: ${error}`);
     }
   };
   
 
+  const handleResumeAll = async () => {
+    try {
+      const failures = await invoke("resume_all_suspended");
+      if (failures.length > 0) {
+        window.alert(`Failed to resume:\n${failures.join("\n")}`);
+      }
+    } catch (error) {
+      console.error("Error resuming suspended processes:", error);
+    }
+  };
+
+  // Previews what a killall pattern matches, then signals exactly those
+  // processes once confirmed. Protected matches are skipped unless forced.
+  const handleKillall = async () => {
+    const pattern = window.prompt("Killall: glob matched against the full command line, e.g. python worker*");
+    if (!pattern) return;
+    const signal = window.prompt("Signal to send", "TERM");
+    if (!signal) return;
+    try {
+      const matches = await invoke("killall", { pattern, mode: "glob", field: "cmdline", signal });
+      if (matches.length === 0) {
+        window.alert(`Nothing matches ${pattern}`);
+        return;
+      }
+      const list = matches
+        .map((m) => `${m.pid} ${m.user} ${m.command}` +
+          (m.protected ? ` [protected: ${m.protected}]` : "") +
+          (m.signal_warning ? ` [${m.signal_warning}]` : ""))
+        .join("\n");
+      if (!window.confirm(`Send ${signal} to ${matches.length} processes?\n\n${list}`)) return;
+      const protectedCount = matches.filter((m) => m.protected).length;
+      const bypass = protectedCount > 0 &&
+        window.confirm(`${protectedCount} of them are protected. Force those too?`) ? "forced" : "none";
+      const results = await invoke("killall_previewed", { matches, signal, bypass });
+      const failures = results.filter((m) => m.error).map((m) => `${m.pid}: ${m.error}`);
+      if (failures.length > 0) {
+        window.alert(`Sent ${signal} to ${results.length - failures.length} of ${results.length} processes:\n${failures.join("\n")}`);
+      }
+    } catch (error) {
+      window.alert(error);
+    }
+  };
+
   if (loading) {
     return <div>Loading processes...</div>;
   }
 
+  const describeHung = (task) =>
+    `${task.pid === task.tid ? task.pid : `${task.pid}/${task.tid}`} (${task.command}) in D for ${task.seconds}s` +
+    (task.wchan ? ` at ${task.wchan}` : "") +
+    (task.syscall ? ` (${task.syscall})` : "") +
+    (task.blocked_on ? `, ${task.blocked_on}` : "");
+  const isHung = (pid) => hungTasks.some((task) => task.pid === pid);
+
   return (
     <div className="process-table-container">
+      {hungTasks.length > 0 && (
+        <div className="hung-banner">
+          {hungTasks.map((task) => (
+            <div key={task.tid}>Hung: {describeHung(task)}</div>
+          ))}
+        </div>
+      )}
+      <div>
+        <button onClick={handleKillall}>Killall…</button>
+      </div>
+      {processes.some((process) => process.suspended_by_procmaster) && (
+        <div className="suspended-banner">
+          Processes marked * were suspended by ProcMaster.{" "}
+          <button onClick={handleResumeAll}>Resume all I suspended</button>
+        </div>
+      )}
       <table className="process-table">
         <thead>
           <tr>
-            {["pid", "user", "command", "v_memory", "rss_memory", "shared_memory", "memory_usage", "cpu_usage", "time", "priority", "nice", "ppid", "state", "threads"].map((header) => (
+            {["pid", "user", "command", "v_memory", "rss_memory", "shared_memory", "memory_usage", "cpu_usage", "time", "priority", "nice", "ppid", "state", "threads", "io_priority", "oom_score", "oom_score_adj"].map((header) => (
               <th key={header} onClick={() => handleSort(header)}>
                 {header.charAt(0).toUpperCase() + header.slice(1).replace(/_/g, " ")}
                 {sortConfig.key === header && (sortConfig.direction === "asc" ? " ↑" : " ↓")}
@@ -175,7 +322,10 @@ const ProcessTable = () => {
             >
               <td>{process.pid}</td>
               <td>{process.user}</td>
-              <td>{process.command}</td>
+              <td style={process.launched ? { color: "darkcyan" } : undefined}>
+                {process.launched && "▸"}
+                {process.command}
+              </td>
               <td>{process.v_memory?.toFixed(2)}</td>
               <td>{process.rss_memory?.toFixed(2)}</td>
               <td>{process.shared_memory?.toFixed(2)}</td>
@@ -185,8 +335,23 @@ const ProcessTable = () => {
               <td>{process.priority}</td>
               <td>{process.nice}</td>
               <td>{process.ppid}</td>
-              <td>{process.state}</td>
+              <td
+                style={
+                  process.suspended_by_procmaster
+                    ? { color: "orange" }
+                    : isHung(process.pid) || process.state === "Z" ? { color: "red" } : undefined
+                }
+              >
+                {process.frozen
+                  ? `${process.state} frozen`
+                  : process.state === "T" ? "T stopped" : process.state === "Z" ? "Z zombie" : process.state}
+                {isHung(process.pid) && " hung"}
+                {process.suspended_by_procmaster && "*"}
+              </td>
               <td>{process.threads}</td>
+              <td>{process.io_priority}</td>
+              <td>{process.oom_score}</td>
+              <td>{process.oom_score_adj}</td>
             </tr>
           ))}
         </tbody>
@@ -202,8 +367,14 @@ const ProcessTable = () => {
             <li onClick={() => handleAction(selectedProcess.pid, "kill")}>Kill</li>
             <li onClick={() => handleAction(selectedProcess.pid, "terminate")}>Terminate</li>
             <li onClick={() => handleAction(selectedProcess.pid, "suspend")}>Suspend</li>
+            <li onClick={() => handleAction(selectedProcess.pid, "freeze")}>Freeze</li>
             <li onClick={() => handleAction(selectedProcess.pid, "resume")}>Resume</li>
             <li onClick={() => handleAction(selectedProcess.pid, "priority")}>Change Priority</li>
+            <li onClick={() => handleAction(selectedProcess.pid, "schedule")}>Schedule…</li>
+            <li onClick={() => handleAction(selectedProcess.pid, "limit")}>Limit CPU…</li>
+            <li onClick={() => handleAction(selectedProcess.pid, "trace")}>Trace syscalls</li>
+            <li onClick={() => handleAction(selectedProcess.pid, "sample")}>Where is it waiting?</li>
+            <li onClick={() => handleAction(selectedProcess.pid, "signals")}>Signals…</li>
           </ul>
         </div>
       )}
@@ -225,14 +396,11 @@ const ProcessTable = () => {
                 onChange={(e) => setSelectedPriority(Number(e.target.value))}
               />
             </div>
-            <div className="input-group">
-              <label>Password:</label>
-              <input
-                type="password"
-                value={password}
-                onChange={(e) => setPassword(e.target.value)}
-              />
-            </div>
+            {!canLower && (
+              <p className="error-message">
+                Lowering the nice value needs CAP_SYS_NICE, which ProcMaster does not have.
+              </p>
+            )}
             {errorMessage && <p className="error-message">{errorMessage}</p>}
             <div className="dialog-buttons">
               <button onClick={handleChangePriority}>Change Priority</button>
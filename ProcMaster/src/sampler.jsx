@@ -0,0 +1,97 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./processtable.css";
+
+// Where the threads of the sampled process were seen waiting, most often first.
+const SamplerPanel = () => {
+  const [pid, setPid] = useState("");
+  const [error, setError] = useState(null);
+  const [view, setView] = useState(null);
+  const [selected, setSelected] = useState(0);
+
+  useEffect(() => {
+    const fetchSamples = async () => {
+      try {
+        setView(await invoke("sample_view"));
+      } catch (error) {
+        console.error("Error fetching samples:", error);
+      }
+    };
+    fetchSamples();
+    const interval = setInterval(fetchSamples, 1000);
+    return () => clearInterval(interval);
+  }, []);
+
+  const start = async (event) => {
+    event.preventDefault();
+    try {
+      await invoke("sample_start", { pid: parseInt(pid, 10) });
+      setError(null);
+      setSelected(0);
+    } catch (error) {
+      setError(String(error));
+    }
+  };
+
+  const stop = async () => {
+    await invoke("sample_stop");
+    setView(null);
+  };
+
+  const site = view ? view.sites[selected] : null;
+  const stateColor = (state) => (state === "D" ? "red" : state === "R" ? "green" : undefined);
+
+  return (
+    <div className="process-table-container">
+      <form className="launch-form" onSubmit={start}>
+        <input value={pid} onChange={(e) => setPid(e.target.value)} placeholder="PID" />
+        <button type="submit">Sample</button>
+        <button type="button" onClick={() => invoke("sample_reset")} disabled={!view}>Reset</button>
+        <button type="button" onClick={stop} disabled={!view}>Stop</button>
+      </form>
+      {error && <div className="launch-error">{error}</div>}
+      {view && (
+        <>
+          <h3>
+            Where {view.pid} ({view.command}) waits: {view.rounds} samples every {view.interval_ms}ms
+            {view.ended && ` | ${view.ended}`}
+          </h3>
+          <table className="process-table">
+            <thead>
+              <tr>
+                <th>Samples</th>
+                <th>Share</th>
+                <th>Threads</th>
+                <th>State</th>
+                <th>Wait channel</th>
+                <th>Syscall</th>
+                <th>Innermost frame</th>
+              </tr>
+            </thead>
+            <tbody>
+              {view.sites.map((site, index) => (
+                <tr key={index} onClick={() => setSelected(index)} className={index === selected ? "selected" : undefined}>
+                  <td>{site.samples}</td>
+                  <td>{site.percent.toFixed(1)}%</td>
+                  <td>{site.threads}</td>
+                  <td style={{ color: stateColor(site.state) }}>{site.state}</td>
+                  <td>{site.wchan || "-"}</td>
+                  <td>{site.syscall ?? "-"}</td>
+                  <td>{site.stack[0] ?? ""}</td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+          <pre className="launch-output">
+            {site && site.stack.length > 0
+              ? site.stack.join("\n")
+              : !view.has_stacks && "Kernel stacks can only be read as root"}
+            {!view.has_syscalls && "\nCurrent syscalls could not be read: not permitted for this process"}
+          </pre>
+        </>
+      )}
+    </div>
+  );
+};
+
+export default SamplerPanel;
@@ -0,0 +1,97 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./processtable.css";
+
+// Supervised services: their state, restart count and health, with controls.
+const ServicesPanel = () => {
+  const [spec, setSpec] = useState("");
+  const [error, setError] = useState(null);
+  const [services, setServices] = useState([]);
+
+  const fetchServices = async () => {
+    try {
+      setServices(await invoke("services"));
+    } catch (error) {
+      console.error("Error fetching services:", error);
+    }
+  };
+
+  useEffect(() => {
+    fetchServices();
+    const interval = setInterval(fetchServices, 1000);
+    return () => clearInterval(interval);
+  }, []);
+
+  const add = async (event) => {
+    event.preventDefault();
+    try {
+      await invoke("add_service", { spec });
+      setSpec("");
+      setError(null);
+      fetchServices();
+    } catch (error) {
+      setError(String(error));
+    }
+  };
+
+  const control = async (command, name) => {
+    try {
+      await invoke(command, { name });
+      setError(null);
+      fetchServices();
+    } catch (error) {
+      setError(String(error));
+    }
+  };
+
+  const stateColor = (state) => (state === "running" ? "green" : state === "stopped" ? "gray" : "orange");
+
+  return (
+    <div className="process-table-container">
+      <form className="launch-form" onSubmit={add}>
+        <input
+          value={spec}
+          onChange={(e) => setSpec(e.target.value)}
+          placeholder='name=worker restart=always max_restarts=5 backoff=1s,5s,30s health_check="curl -sf localhost:8080" -- ./worker'
+        />
+        <button type="submit">Add</button>
+      </form>
+      {error && <div className="launch-error">{error}</div>}
+      <table className="process-table">
+        <thead>
+          <tr>
+            <th>Name</th>
+            <th>Status</th>
+            <th>PID</th>
+            <th>Restarts</th>
+            <th>Last exit</th>
+            <th>Health</th>
+            <th>Command</th>
+            <th></th>
+          </tr>
+        </thead>
+        <tbody>
+          {services.map((service) => (
+            <tr key={service.name}>
+              <td>{service.name}</td>
+              <td style={{ color: stateColor(service.state) }}>{service.state}</td>
+              <td>{service.pid ?? ""}</td>
+              <td>{service.restarts}</td>
+              <td>{service.last_exit ?? ""}</td>
+              <td style={service.health?.startsWith("failing") ? { color: "red" } : undefined}>{service.health ?? "-"}</td>
+              <td>{service.run}</td>
+              <td>
+                <button onClick={() => control("start_service", service.name)}>Start</button>
+                <button onClick={() => control("stop_service", service.name)}>Stop</button>
+                <button onClick={() => control("restart_service", service.name)}>Restart</button>
+                <button onClick={() => control("remove_service", service.name)}>Delete</button>
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </div>
+  );
+};
+
+export default ServicesPanel;
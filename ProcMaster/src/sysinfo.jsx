@@ -19,6 +19,7 @@ import {
 
 const SysInfo = () => {
   const [cpuUsages, setCpuUsages] = useState([]);
+  const [cpuTotal, setCpuTotal] = useState(null);
   const [memoryUsage, setMemoryUsage] = useState(null);
 
   useEffect(() => {
@@ -42,7 +43,8 @@ const SysInfo = () => {
       console.log("Fetching CPU usage data...");
       const data = await invoke("cpu_resultt");
       console.log("Fetched CPU usage data:", data); // Debugging log
-      setCpuUsages(data);
+      setCpuUsages(data.cores);
+      setCpuTotal(data.total);
     } catch (error) {
       console.error("Error fetching CPU usage data:", error);
     }
@@ -82,7 +84,7 @@ const SysInfo = () => {
 
   return (
     <div>
-      <h1>CPU Usage</h1>
+      <h1>CPU Usage{cpuTotal !== null && cpuUsages.length > 0 && ` (${cpuTotal.toFixed(1)}% overall)`}</h1>
       <div>
         {cpuUsages.length === 0 ? (
           <p>Loading...</p>
@@ -0,0 +1,104 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./processtable.css";
+
+// Live syscall log of the traced process or thread, with per-syscall totals.
+const TracePanel = () => {
+  const [pid, setPid] = useState("");
+  const [error, setError] = useState(null);
+  const [view, setView] = useState(null);
+  const [thread, setThread] = useState("");
+
+  useEffect(() => {
+    const fetchTrace = async () => {
+      try {
+        setView(await invoke("trace_view"));
+      } catch (error) {
+        console.error("Error fetching trace:", error);
+      }
+    };
+    fetchTrace();
+    const interval = setInterval(fetchTrace, 500);
+    return () => clearInterval(interval);
+  }, []);
+
+  const attach = async (event) => {
+    event.preventDefault();
+    try {
+      await invoke("trace_attach", { pid: parseInt(pid, 10) });
+      setError(null);
+      setThread("");
+    } catch (error) {
+      setError(String(error));
+    }
+  };
+
+  const detach = async () => {
+    await invoke("trace_detach");
+    setView(null);
+  };
+
+  const log = view ? view.log.filter((line) => thread === "" || line.tid === parseInt(thread, 10)) : [];
+  const total = view ? view.summary.reduce((sum, syscall) => sum + syscall.seconds, 0) : 0;
+
+  return (
+    <div className="process-table-container">
+      <form className="launch-form" onSubmit={attach}>
+        <input value={pid} onChange={(e) => setPid(e.target.value)} placeholder="PID, or TID for one thread" />
+        <button type="submit">Trace</button>
+        <button type="button" onClick={detach} disabled={!view}>Detach</button>
+      </form>
+      {error && <div className="launch-error">{error}</div>}
+      {view && (
+        <>
+          <h3>
+            Syscalls of {view.pid} ({view.command}), {view.threads.length} threads{" "}
+            <select value={thread} onChange={(e) => setThread(e.target.value)}>
+              <option value="">all threads</option>
+              {view.threads.map((tid) => (
+                <option key={tid} value={tid}>{tid}</option>
+              ))}
+            </select>
+            {view.ended && ` | ${view.ended}`}
+          </h3>
+          {view.in_syscall.map((call) => (
+            <div key={call.tid} style={call.seconds >= 1 ? { color: "orange" } : undefined}>
+              [{call.tid}] {call.seconds.toFixed(1)}s in {call.call}
+            </div>
+          ))}
+          <pre className="launch-output">
+            {log.slice(-300).map((line, index) => (
+              <div key={index} style={line.error ? { color: "red" } : undefined}>
+                {line.time.toFixed(3).padStart(8)} [{line.tid}] {line.text}
+              </div>
+            ))}
+          </pre>
+          <table className="process-table">
+            <thead>
+              <tr>
+                <th>Syscall</th>
+                <th>Calls</th>
+                <th>Errors</th>
+                <th>Time (s)</th>
+                <th>Time</th>
+              </tr>
+            </thead>
+            <tbody>
+              {view.summary.map((syscall) => (
+                <tr key={syscall.name}>
+                  <td>{syscall.name}</td>
+                  <td>{syscall.calls}</td>
+                  <td style={syscall.errors > 0 ? { color: "red" } : undefined}>{syscall.errors}</td>
+                  <td>{syscall.seconds.toFixed(3)}</td>
+                  <td>{total > 0 ? ((syscall.seconds / total) * 100).toFixed(1) : "0.0"}%</td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </>
+      )}
+    </div>
+  );
+};
+
+export default TracePanel;
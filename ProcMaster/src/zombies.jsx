@@ -0,0 +1,131 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./processtable.css";
+
+const formatSeconds = (seconds) =>
+  seconds < 60
+    ? `${seconds}s`
+    : seconds < 3600
+      ? `${Math.floor(seconds / 60)}m ${seconds % 60}s`
+      : `${Math.floor(seconds / 3600)}h ${Math.floor((seconds % 3600) / 60)}m`;
+
+const age = (zombie) => `${zombie.at_least ? ">= " : ""}${formatSeconds(zombie.seconds)}`;
+
+// Zombies grouped by the parent that should reap them, and orphans adopted by
+// init or a subreaper.
+const ZombiesPanel = () => {
+  const [report, setReport] = useState({ parents: [], orphans: [] });
+  const [error, setError] = useState(null);
+
+  useEffect(() => {
+    const fetchReport = async () => {
+      try {
+        setReport(await invoke("zombie_report"));
+      } catch (error) {
+        console.error("Error fetching zombies:", error);
+      }
+    };
+    fetchReport();
+    const interval = setInterval(fetchReport, 1000);
+    return () => clearInterval(interval);
+  }, []);
+
+  const nudge = async (parent) => {
+    try {
+      await invoke("nudge_zombie_parent", { pid: parent.pid });
+      setError(null);
+    } catch (error) {
+      setError(String(error));
+    }
+  };
+
+  // Parents are often protected (the session's shell, sshd), so those take a
+  // second confirmation or an explicit force, as in the process table.
+  const terminate = async (parent) => {
+    const label = `Terminate ${parent.pid} (${parent.command}) so its ${parent.zombies.length} zombies are reaped`;
+    let bypass = "none";
+    try {
+      const protection = await invoke("check_protection", { pid: parent.pid });
+      if (protection.Confirm) {
+        if (!window.confirm(`${label}?\n\nProtected: ${protection.Confirm}`)) return;
+        if (!window.confirm(`Really terminate ${parent.pid}? It is protected.`)) return;
+        bypass = "confirmed";
+      } else if (protection.Refuse) {
+        if (!window.confirm(`Refused: ${parent.pid} is protected (${protection.Refuse}).\n\nForce it anyway?`)) return;
+        bypass = "forced";
+      } else if (!window.confirm(`${label}?`)) {
+        return;
+      }
+      await invoke("terminate_process", { pid: parent.pid, bypass });
+      setError(null);
+    } catch (error) {
+      setError(String(error));
+    }
+  };
+
+  return (
+    <div className="process-table-container">
+      {error && <div className="launch-error">{error}</div>}
+      <h3>{report.parents.length === 0 ? "No zombies" : "Zombies by parent"}</h3>
+      <table className="process-table">
+        <thead>
+          <tr>
+            <th>PID</th>
+            <th>Command</th>
+            <th>State</th>
+            <th>Zombie for</th>
+            <th>Actions</th>
+          </tr>
+        </thead>
+        <tbody>
+          {report.parents.map((parent) => (
+            <React.Fragment key={parent.pid}>
+              <tr>
+                <td><b>{parent.pid}</b></td>
+                <td><b>{parent.command}</b> ({parent.zombies.length} zombies)</td>
+                <td>{parent.state}</td>
+                <td>{parent.zombies.length > 0 && age(parent.zombies[0])}</td>
+                <td>
+                  <button onClick={() => nudge(parent)}>SIGCHLD</button>
+                  <button onClick={() => terminate(parent)} disabled={parent.pid === 1}>Terminate parent</button>
+                </td>
+              </tr>
+              {parent.zombies.map((zombie) => (
+                <tr key={zombie.pid}>
+                  <td style={{ paddingLeft: "2em" }}>{zombie.pid}</td>
+                  <td style={{ paddingLeft: "2em" }}>{zombie.command}</td>
+                  <td style={{ color: "red" }}>Z</td>
+                  <td>{age(zombie)}</td>
+                  <td></td>
+                </tr>
+              ))}
+            </React.Fragment>
+          ))}
+        </tbody>
+      </table>
+      <h3>{report.orphans.length} orphans reparented to init or a subreaper</h3>
+      <table className="process-table">
+        <thead>
+          <tr>
+            <th>PID</th>
+            <th>Command</th>
+            <th>Adopted by</th>
+            <th>Session</th>
+          </tr>
+        </thead>
+        <tbody>
+          {report.orphans.map((orphan) => (
+            <tr key={orphan.pid}>
+              <td>{orphan.pid}</td>
+              <td>{orphan.command}</td>
+              <td>{orphan.reaper} ({orphan.reaper_command})</td>
+              <td>{orphan.session}</td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </div>
+  );
+};
+
+export default ZombiesPanel;
@@ -0,0 +1,111 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use nix::unistd::getuid;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use users::get_user_by_uid;
+
+use crate::config;
+use crate::ctrl::CtrlError;
+
+/// One line of the audit log: who did what to which process, and how it went.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// RFC 3339, UTC.
+    pub timestamp: String,
+    pub user: String,
+    pub uid: u32,
+    /// `None` for actions on a process group or user as a whole.
+    pub pid: Option<i32>,
+    /// Start time of the target in clock ticks after boot. Together with the PID
+    /// it identifies the process even after the PID has been reused.
+    pub starttime: Option<u64>,
+    pub command: Option<String>,
+    pub operation: String,
+    pub args: Value,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub errno: Option<i32>,
+}
+
+/// Errors that can be written to the audit log.
+pub trait AuditError: std::fmt::Display {
+    fn errno(&self) -> Option<i32> {
+        None
+    }
+}
+
+impl AuditError for String {}
+
+impl AuditError for CtrlError {
+    fn errno(&self) -> Option<i32> {
+        match self {
+            CtrlError::InvalidArgument(_) => None,
+            CtrlError::NotPermitted => Some(libc::EPERM),
+            CtrlError::AccessDenied => Some(libc::EACCES),
+            CtrlError::NoSuchProcess => Some(libc::ESRCH),
+            CtrlError::PolicyDenied => None,
+            CtrlError::Os(errno) => Some(*errno as i32),
+        }
+    }
+}
+
+/// Runs `action` and logs it as `operation` on `pid`. The target is looked up
+/// before `action` runs, since after a kill there is nothing left to look at.
+pub fn audited<E: AuditError>(
+    operation: &str,
+    pid: Option<i32>,
+    args: Value,
+    action: impl FnOnce() -> Result<(), E>,
+) -> Result<(), E> {
+    let stat = pid.and_then(|pid| procfs::process::Process::new(pid).and_then(|proc| proc.stat()).ok());
+    let result = action();
+    let uid = getuid().as_raw();
+    record(&AuditEntry {
+        timestamp: OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default(),
+        user: get_user_by_uid(uid)
+            .map(|user| user.name().to_string_lossy().into_owned())
+            .unwrap_or_else(|| uid.to_string()),
+        uid,
+        pid,
+        starttime: stat.as_ref().map(|stat| stat.starttime),
+        command: stat.map(|stat| stat.comm),
+        operation: operation.to_string(),
+        args,
+        ok: result.is_ok(),
+        error: result.as_ref().err().map(|err| err.to_string()),
+        errno: result.as_ref().err().and_then(|err| err.errno()),
+    });
+    result
+}
+
+/// Appends `entry` to the configured log. Logging is best effort: a control
+/// action is never failed because its log line could not be written.
+fn record(entry: &AuditEntry) {
+    let audit = &config::current().audit;
+    if !audit.enabled {
+        return;
+    }
+    let path = audit.path();
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let Ok(line) = serde_json::to_string(entry) else { return };
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+/// The last `limit` entries of the log, newest first. Lines that do not parse are skipped.
+pub fn recent(limit: usize) -> Vec<AuditEntry> {
+    let path = config::current().audit.path();
+    let Ok(text) = fs::read_to_string(path) else { return Vec::new() };
+    text.lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
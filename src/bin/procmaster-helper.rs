@@ -0,0 +1,468 @@
+//! Privileged helper for ProcMaster. Run it as root (e.g. from a systemd unit);
+//! it listens on a Unix socket and performs the operations the TUI and the GUI
+//! lack the privilege for, within what its policy file allows the caller.
+//!
+//! ```text
+//! procmaster-helper [--socket PATH] [--policy PATH]
+//! ```
+//!
+//! Each connection sends one [`HelperRequest`] as a line of JSON and gets one
+//! [`HelperResponse`] back. The caller is identified with SO_PEERCRED, never by
+//! anything in the request.
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use nix::errno::Errno;
+use nix::sys::signal::Signal;
+use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
+use glob::MatchOptions;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use users::{get_user_by_uid, get_user_groups};
+
+// Shared with the TUI; the helper only needs part of each.
+#[allow(dead_code)]
+#[path = "../ctrl.rs"]
+mod ctrl;
+#[allow(dead_code)]
+#[path = "../cgroup.rs"]
+mod cgroup;
+#[allow(dead_code)]
+#[path = "../config.rs"]
+mod config;
+#[allow(dead_code)]
+#[path = "../audit.rs"]
+mod audit;
+#[allow(dead_code)]
+#[path = "../suspended.rs"]
+mod suspended;
+#[allow(dead_code)]
+#[path = "../helper.rs"]
+mod helper;
+
+use audit::{audited, AuditError};
+use cgroup::{CgroupFs, CgroupLimit};
+use ctrl::{Bypass, CtrlError, Guard, IoPriority, PriorityTarget, SchedPolicy};
+use helper::{HelperOp, HelperRequest, HelperResponse, PROTOCOL_VERSION, SERVING};
+
+const DEFAULT_POLICY: &str = "/etc/procmaster/helper.toml";
+
+/// The helper's policy file. Requests are refused unless a rule allows them, so
+/// a missing file allows nothing.
+///
+/// ```toml
+/// [[rules]]
+/// groups = ["wheel"]
+/// operations = ["renice", "ioprio", "signal"]
+/// targets = "own"
+/// min_nice = -5
+///
+/// [[rules]]
+/// users = ["audio"]
+/// operations = ["scheduler", "oom_score_adj"]
+/// max_rt_priority = 50
+/// min_oom_score_adj = -500
+///
+/// [[rules]]
+/// users = ["ops"]
+/// targets = "any"
+/// cgroups = ["procmaster/*"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Policy {
+    rules: Vec<PolicyRule>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Targets {
+    /// Only processes running as the caller's uid.
+    #[default]
+    Own,
+    Any,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PolicyRule {
+    /// The rule applies to these users and to members of these groups. With
+    /// both empty it applies to everyone.
+    users: Vec<String>,
+    groups: Vec<String>,
+    /// Operation names as in [`HelperOp::name`]. Empty allows all of them.
+    operations: Vec<String>,
+    targets: Targets,
+    /// Lowest nice value the caller may set.
+    min_nice: i32,
+    /// Highest SCHED_FIFO/SCHED_RR priority the caller may set. The default,
+    /// 0, allows no realtime scheduling, like an unprivileged RLIMIT_RTPRIO.
+    max_rt_priority: i32,
+    /// Lowest oom_score_adj the caller may set. The default, 0, keeps callers
+    /// from shielding processes from the OOM killer.
+    min_oom_score_adj: i32,
+    /// Glob patterns for the cgroups the caller may change or move processes
+    /// into. Empty allows no cgroup operations.
+    cgroups: Vec<String>,
+    /// Whether forced signals may override the helper's protected processes.
+    allow_force: bool,
+}
+
+impl HelperOp {
+    /// The name policy files use for this operation, e.g. `renice`.
+    fn name(&self) -> &'static str {
+        match self {
+            HelperOp::Renice { .. } => "renice",
+            HelperOp::Signal { .. } => "signal",
+            HelperOp::Ioprio { .. } => "ioprio",
+            HelperOp::Scheduler { .. } => "scheduler",
+            HelperOp::OomScoreAdj { .. } => "oom_score_adj",
+            HelperOp::CgroupLimit { .. } => "cgroup_limit",
+            HelperOp::CgroupMove { .. } => "cgroup_move",
+            HelperOp::Freeze { .. } => "freeze",
+            HelperOp::Thaw { .. } => "thaw",
+        }
+    }
+}
+
+impl HelperResponse {
+    fn result(result: Result<(), CtrlError>) -> Self {
+        match result {
+            Ok(()) => HelperResponse { ok: true, error: None, errno: None },
+            Err(err) => HelperResponse {
+                ok: false,
+                error: Some(err.to_string()),
+                // An error without an errno still has to read as a failed
+                // operation, not as a refusal.
+                errno: Some(AuditError::errno(&err).unwrap_or(libc::EINVAL)),
+            },
+        }
+    }
+
+    fn refused(reason: String) -> Self {
+        HelperResponse { ok: false, error: Some(reason), errno: None }
+    }
+}
+
+/// Who is on the other end of the socket, from SO_PEERCRED.
+struct Peer {
+    pid: i32,
+    uid: u32,
+    user: String,
+    groups: Vec<String>,
+}
+
+impl Peer {
+    fn of(stream: &UnixStream) -> Result<Self, String> {
+        let credentials = getsockopt(stream, PeerCredentials).map_err(|err| format!("SO_PEERCRED failed: {}", err))?;
+        let user = get_user_by_uid(credentials.uid()).ok_or_else(|| format!("Unknown uid {}", credentials.uid()))?;
+        let groups = get_user_groups(user.name(), credentials.gid())
+            .unwrap_or_default()
+            .iter()
+            .map(|group| group.name().to_string_lossy().into_owned())
+            .collect();
+        Ok(Peer {
+            pid: credentials.pid(),
+            uid: credentials.uid(),
+            user: user.name().to_string_lossy().into_owned(),
+            groups,
+        })
+    }
+}
+
+impl Policy {
+    /// Loads the policy, refusing a file that someone other than root could
+    /// have written, since it grants root's privileges.
+    fn load(path: &Path) -> Result<Self, String> {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Policy::default()),
+            Err(err) => return Err(format!("Failed to read policy {}: {}", path.display(), err)),
+        };
+        if metadata.uid() != 0 || metadata.mode() & 0o022 != 0 {
+            return Err(format!("Policy {} must be owned by root and not group or world writable", path.display()));
+        }
+        let text = fs::read_to_string(path).map_err(|err| format!("Failed to read policy {}: {}", path.display(), err))?;
+        toml::from_str(&text).map_err(|err| format!("Invalid policy {}: {}", path.display(), err))
+    }
+
+    /// Checks `op` against the first rule that covers the caller and the
+    /// operation.
+    fn check(&self, peer: &Peer, op: &HelperOp) -> Result<(), String> {
+        let rule = self.rules.iter()
+            .find(|rule| {
+                let applies = (rule.users.is_empty() && rule.groups.is_empty())
+                    || rule.users.contains(&peer.user)
+                    || rule.groups.iter().any(|group| peer.groups.contains(group));
+                applies && (rule.operations.is_empty() || rule.operations.iter().any(|name| name == op.name()))
+            })
+            .ok_or_else(|| format!("No policy rule allows {} for {}", op.name(), peer.user))?;
+
+        if rule.targets == Targets::Own {
+            let owned = |pid: i32| procfs::process::Process::new(pid).and_then(|proc| proc.uid()).is_ok_and(|uid| uid == peer.uid);
+            let own = match op {
+                HelperOp::Renice { target, who, .. } => match target.as_str() {
+                    "process" => owned(*who as i32),
+                    "user" => *who == peer.uid,
+                    // A process group may mix users, so only `targets = "any"` covers it.
+                    _ => false,
+                },
+                // A limit applies to everything in the cgroup and below it,
+                // so all of that has to be the caller's too. Processes that
+                // exit meanwhile are no longer affected.
+                HelperOp::CgroupLimit { pid, cgroup, .. } => {
+                    let foreign = |pid: i32| procfs::process::Process::new(pid).and_then(|proc| proc.uid()).is_ok_and(|uid| uid != peer.uid);
+                    owned(*pid) && cgroup_pids(cgroup).is_ok_and(|pids| !pids.into_iter().any(foreign))
+                }
+                HelperOp::Signal { pid, .. }
+                | HelperOp::Ioprio { pid, .. }
+                | HelperOp::Scheduler { pid, .. }
+                | HelperOp::OomScoreAdj { pid, .. }
+                | HelperOp::CgroupMove { pid, .. }
+                | HelperOp::Freeze { pid }
+                | HelperOp::Thaw { pid } => owned(*pid),
+            };
+            if !own {
+                return Err(format!("{} may only {} their own processes", peer.user, op.name()));
+            }
+        }
+        match op {
+            HelperOp::Renice { nice, .. } if *nice < rule.min_nice => {
+                Err(format!("Nice values below {} are not allowed", rule.min_nice))
+            }
+            HelperOp::Signal { bypass: Bypass::Forced, .. } if !rule.allow_force => {
+                Err("Forcing past protected processes is not allowed".to_string())
+            }
+            HelperOp::Scheduler { policy, priority, .. } => {
+                let policy: SchedPolicy = policy.parse()?;
+                if policy.is_realtime() && *priority > rule.max_rt_priority {
+                    Err(match rule.max_rt_priority {
+                        0 => "Realtime scheduling is not allowed".to_string(),
+                        max => format!("Realtime priorities above {} are not allowed", max),
+                    })
+                } else {
+                    Ok(())
+                }
+            }
+            HelperOp::OomScoreAdj { value, .. } if *value < rule.min_oom_score_adj => {
+                Err(format!("oom_score_adj values below {} are not allowed", rule.min_oom_score_adj))
+            }
+            HelperOp::CgroupLimit { cgroup, .. } | HelperOp::CgroupMove { cgroup, .. } => {
+                // `*` must not match across `/`, and `..` must not climb out
+                // of a matching prefix.
+                let relative = cgroup.trim_start_matches('/');
+                let options = MatchOptions { require_literal_separator: true, ..MatchOptions::new() };
+                let allowed = Path::new(relative).components().all(|part| matches!(part, Component::Normal(_)))
+                    && rule.cgroups.iter()
+                        .filter_map(|pattern| glob::Pattern::new(pattern).ok())
+                        .any(|pattern| pattern.matches_with(relative, options));
+                if allowed { Ok(()) } else { Err(format!("Cgroup {} is not allowed", cgroup)) }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// PIDs of every process in `cgroup` and in the cgroups below it.
+fn cgroup_pids(cgroup: &str) -> Result<Vec<i32>, CtrlError> {
+    let mut pids = Vec::new();
+    let mut dirs = vec![CgroupFs::system().path(cgroup)?];
+    while let Some(dir) = dirs.pop() {
+        let procs = fs::read_to_string(dir.join("cgroup.procs"))?;
+        pids.extend(procs.lines().filter_map(|line| line.trim().parse::<i32>().ok()));
+        for entry in fs::read_dir(&dir)?.flatten() {
+            if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                dirs.push(entry.path());
+            }
+        }
+    }
+    Ok(pids)
+}
+
+/// Fails unless the request's PID, if it has one, still belongs to the process
+/// that started at the request's start time.
+fn check_target(request: &HelperRequest) -> Result<(), CtrlError> {
+    let Some(pid) = request.op.pid() else { return Ok(()) };
+    let starttime = request.starttime.ok_or(CtrlError::InvalidArgument("the request has no start time for its target"))?;
+    let stat = procfs::process::Process::new(pid).and_then(|proc| proc.stat()).map_err(|_| CtrlError::NoSuchProcess)?;
+    if stat.starttime == starttime { Ok(()) } else { Err(CtrlError::NoSuchProcess) }
+}
+
+/// Sends `signal` through a pidfd. The start time is checked only once the
+/// pidfd is open, so the signal reaches that process even if the PID is reused
+/// a moment later.
+fn pidfd_signal(request: &HelperRequest, pid: i32, signal: Signal) -> Result<(), CtrlError> {
+    // SAFETY: plain syscalls; the returned descriptor is owned below.
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    if fd < 0 {
+        return Err(Errno::last().into());
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+    check_target(request)?;
+    let sent = unsafe {
+        libc::syscall(libc::SYS_pidfd_send_signal, fd.as_raw_fd(), signal as i32, std::ptr::null::<libc::siginfo_t>(), 0)
+    };
+    if sent < 0 { Err(Errno::last().into()) } else { Ok(()) }
+}
+
+/// Performs the request's operation through `ctrl`, which audits it like any
+/// other control action. The target is checked once more right before, since
+/// the policy check took a while.
+fn execute(request: &HelperRequest) -> Result<(), Result<CtrlError, String>> {
+    let invalid = |err: String| Err(err);
+    let op = &request.op;
+    if !matches!(op, HelperOp::Signal { .. }) {
+        check_target(request).map_err(Ok)?;
+    }
+    match op {
+        HelperOp::Renice { target, who, nice } => {
+            let target = match target.as_str() {
+                "process" => PriorityTarget::Process(*who as i32),
+                "pgrp" => PriorityTarget::ProcessGroup(*who as i32),
+                "user" => PriorityTarget::User(*who),
+                other => return Err(invalid(format!("Unknown renice target {}", other))),
+            };
+            ctrl::set_priority(target, *nice).map_err(Ok)
+        }
+        HelperOp::Signal { pid, signal, bypass } => {
+            let signal = Signal::try_from(*signal).map_err(|_| invalid(format!("Unknown signal {}", signal)))?;
+            // Like `ctrl::send_signal`, SIGCHLD is harmless enough to skip the guard.
+            if signal != Signal::SIGCHLD {
+                Guard::load().allow(*pid, *bypass).map_err(Err)?;
+            }
+            audited("signal", Some(*pid), json!({ "signal": signal.as_str(), "bypass": bypass }), || {
+                pidfd_signal(request, *pid, signal)
+            })
+            .map_err(Ok)
+        }
+        HelperOp::Ioprio { pid, priority } => {
+            let priority: IoPriority = priority.parse().map_err(Err)?;
+            ctrl::set_io_priority(*pid, priority).map_err(Ok)
+        }
+        HelperOp::Scheduler { pid, policy, priority, all_threads } => {
+            let policy: SchedPolicy = policy.parse().map_err(Err)?;
+            ctrl::set_scheduler(*pid, policy, *priority, *all_threads).map_err(Ok)
+        }
+        HelperOp::OomScoreAdj { pid, value } => ctrl::set_oom_score_adj(*pid, *value).map_err(Ok),
+        HelperOp::CgroupLimit { pid, cgroup, limit, value } => {
+            let limit: CgroupLimit = limit.parse().map_err(Err)?;
+            ctrl::set_cgroup_limit(*pid, cgroup, limit, value).map_err(Ok)
+        }
+        HelperOp::CgroupMove { pid, cgroup } => ctrl::move_to_cgroup(*pid, cgroup).map_err(Ok),
+        HelperOp::Freeze { pid } => {
+            audited("freeze", Some(*pid), json!({}), || CgroupFs::system().freeze_pid(*pid)).map_err(Ok)
+        }
+        HelperOp::Thaw { pid } => {
+            audited("thaw", Some(*pid), json!({}), || CgroupFs::system().thaw_pid(*pid)).map_err(Ok)
+        }
+    }
+}
+
+/// Decodes one request line, checking the protocol version before the
+/// operation so a newer client gets a clear answer.
+fn parse_request(line: &str) -> Result<HelperRequest, String> {
+    let value: Value = serde_json::from_str(line).map_err(|err| format!("Invalid request: {}", err))?;
+    match value.get("version").and_then(Value::as_u64) {
+        Some(version) if version == u64::from(PROTOCOL_VERSION) => {}
+        Some(version) => {
+            return Err(format!("Unsupported protocol version {}, this helper speaks {}", version, PROTOCOL_VERSION));
+        }
+        None => return Err("Request has no protocol version".to_string()),
+    }
+    serde_json::from_value(value).map_err(|err| format!("Invalid request: {}", err))
+}
+
+fn serve(stream: UnixStream, policy_path: &Path) -> Result<(), String> {
+    let peer = Peer::of(&stream)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5))).map_err(|err| err.to_string())?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).map_err(|err| err.to_string())?;
+    // Like the policy, the config is read again for every request, as the
+    // helper outlives any edit to it. A malformed file keeps the last good one.
+    let _ = config::reload();
+
+    let (pid, args, response) = match parse_request(&line) {
+        Err(err) => (None, json!({ "peer_uid": peer.uid, "peer_pid": peer.pid }), HelperResponse::refused(err)),
+        Ok(request) => {
+            let pid = request.op.pid();
+            // Ownership is judged by PID, so the PID has to be the caller's
+            // process before the policy is even looked at.
+            let response = match check_target(&request) {
+                Err(err) => HelperResponse::result(Err(err)),
+                Ok(()) => match Policy::load(policy_path).and_then(|policy| policy.check(&peer, &request.op)) {
+                    Err(reason) => HelperResponse::refused(reason),
+                    Ok(()) => match execute(&request) {
+                        Ok(()) => HelperResponse::result(Ok(())),
+                        Err(Ok(err)) => HelperResponse::result(Err(err)),
+                        Err(Err(reason)) => HelperResponse::refused(reason),
+                    },
+                },
+            };
+            let args = json!({ "request": request.op, "starttime": request.starttime, "peer_user": peer.user, "peer_uid": peer.uid, "peer_pid": peer.pid });
+            (pid, args, response)
+        }
+    };
+    // One entry per request says who asked for what; the operation itself is
+    // logged separately by `ctrl` when it runs.
+    let _ = audited("helper", pid, args, || match &response.error {
+        Some(err) if !response.ok => Err(err.clone()),
+        _ => Ok(()),
+    });
+
+    let mut answer = serde_json::to_string(&response).map_err(|err| err.to_string())?;
+    answer.push('\n');
+    (&stream).write_all(answer.as_bytes()).map_err(|err| err.to_string())
+}
+
+fn main() {
+    let mut socket = config::HelperConfig::default().socket_path();
+    let mut policy = PathBuf::from(DEFAULT_POLICY);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--socket", Some(path)) => socket = PathBuf::from(path),
+            ("--policy", Some(path)) => policy = PathBuf::from(path),
+            _ => {
+                eprintln!("Usage: procmaster-helper [--socket PATH] [--policy PATH]");
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Err(err) = Policy::load(&policy) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    SERVING.store(true, Ordering::Relaxed);
+
+    if let Some(dir) = socket.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    // A socket left behind by a previous run would make bind fail.
+    let _ = fs::remove_file(&socket);
+    let listener = match UnixListener::bind(&socket) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("Failed to listen on {}: {}", socket.display(), err);
+            std::process::exit(1);
+        }
+    };
+    // Anyone may connect; the policy decides what each caller gets.
+    let _ = fs::set_permissions(&socket, fs::Permissions::from_mode(0o666));
+    println!("Listening on {} with policy {}", socket.display(), policy.display());
+
+    for stream in listener.incoming().flatten() {
+        let policy = policy.clone();
+        thread::spawn(move || {
+            if let Err(err) = serve(stream, &policy) {
+                eprintln!("{}", err);
+            }
+        });
+    }
+}
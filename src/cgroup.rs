@@ -0,0 +1,560 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::ctrl::CtrlError;
+
+/// Resource control files ProcMaster can set on a cgroup v2 directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum CgroupLimit {
+    CpuMax,
+    CpuWeight,
+    MemoryMax,
+    MemoryHigh,
+    IoMax,
+    PidsMax,
+}
+
+impl CgroupLimit {
+    pub const ALL: [CgroupLimit; 6] = [
+        CgroupLimit::CpuMax,
+        CgroupLimit::CpuWeight,
+        CgroupLimit::MemoryMax,
+        CgroupLimit::MemoryHigh,
+        CgroupLimit::IoMax,
+        CgroupLimit::PidsMax,
+    ];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            CgroupLimit::CpuMax => "cpu.max",
+            CgroupLimit::CpuWeight => "cpu.weight",
+            CgroupLimit::MemoryMax => "memory.max",
+            CgroupLimit::MemoryHigh => "memory.high",
+            CgroupLimit::IoMax => "io.max",
+            CgroupLimit::PidsMax => "pids.max",
+        }
+    }
+
+    /// A short example of what the file accepts, for prompts.
+    pub fn hint(self) -> &'static str {
+        match self {
+            CgroupLimit::CpuMax => "\"50000 100000\" = half a CPU, or \"max\"",
+            CgroupLimit::CpuWeight => "1 to 10000, default 100",
+            CgroupLimit::MemoryMax | CgroupLimit::MemoryHigh => "bytes with K/M/G suffix, or \"max\"",
+            CgroupLimit::IoMax => "\"8:0 rbps=1048576 wbps=max riops=max wiops=max\"",
+            CgroupLimit::PidsMax => "number of tasks, or \"max\"",
+        }
+    }
+
+    /// Catches obvious typos before they reach the kernel, which only answers EINVAL.
+    pub fn validate(self, value: &str) -> Result<(), CtrlError> {
+        let value = value.trim();
+        let valid = match self {
+            CgroupLimit::CpuMax => {
+                let mut parts = value.split_whitespace();
+                let quota_ok = parts.next().is_some_and(|quota| quota == "max" || quota.parse::<u64>().is_ok());
+                let period_ok = parts.next().is_none_or(|period| period.parse::<u64>().is_ok());
+                quota_ok && period_ok && parts.next().is_none()
+            }
+            CgroupLimit::CpuWeight => value.parse::<u32>().is_ok_and(|weight| (1..=10000).contains(&weight)),
+            CgroupLimit::MemoryMax | CgroupLimit::MemoryHigh => value == "max" || parse_bytes(value).is_some(),
+            CgroupLimit::IoMax => {
+                let mut parts = value.split_whitespace();
+                let device_ok = parts.next().is_some_and(|device| {
+                    device.split_once(':').is_some_and(|(major, minor)| major.parse::<u32>().is_ok() && minor.parse::<u32>().is_ok())
+                });
+                device_ok && parts.all(|part| {
+                    matches!(part.split_once('='), Some(("rbps" | "wbps" | "riops" | "wiops", limit)) if limit == "max" || limit.parse::<u64>().is_ok())
+                })
+            }
+            CgroupLimit::PidsMax => value == "max" || value.parse::<u64>().is_ok(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(CtrlError::InvalidArgument(match self {
+                CgroupLimit::CpuMax => "cpu.max takes \"<quota|max> [period]\"",
+                CgroupLimit::CpuWeight => "cpu.weight takes a number from 1 to 10000",
+                CgroupLimit::MemoryMax => "memory.max takes bytes (K/M/G suffix allowed) or max",
+                CgroupLimit::MemoryHigh => "memory.high takes bytes (K/M/G suffix allowed) or max",
+                CgroupLimit::IoMax => "io.max takes \"MAJ:MIN key=value...\" with rbps, wbps, riops, wiops",
+                CgroupLimit::PidsMax => "pids.max takes a number or max",
+            }))
+        }
+    }
+}
+
+impl fmt::Display for CgroupLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file_name())
+    }
+}
+
+impl FromStr for CgroupLimit {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        CgroupLimit::ALL
+            .into_iter()
+            .find(|limit| limit.file_name() == text)
+            .ok_or_else(|| format!("Unknown cgroup limit {}", text))
+    }
+}
+
+/// Parses `512`, `64K`, `256M` or `2G` into bytes, the suffixes memory.max accepts.
+fn parse_bytes(text: &str) -> Option<u64> {
+    let (number, multiplier) = match text.chars().last()?.to_ascii_uppercase() {
+        'K' => (&text[..text.len() - 1], 1 << 10),
+        'M' => (&text[..text.len() - 1], 1 << 20),
+        'G' => (&text[..text.len() - 1], 1 << 30),
+        'T' => (&text[..text.len() - 1], 1 << 40),
+        _ => (text, 1),
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Current limits and usage of one cgroup. Values are `None` when the file is
+/// missing, usually because the controller is not enabled for that cgroup.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CgroupInfo {
+    /// Path relative to the hierarchy root, e.g. `/user.slice/user-1000.slice`.
+    pub path: String,
+    pub limits: Vec<(CgroupLimit, Option<String>)>,
+    pub memory_current: Option<u64>,
+    pub cpu_usage_usec: Option<u64>,
+    pub pids_current: Option<u64>,
+}
+
+/// A cgroup v2 hierarchy. Both roots can be pointed at a scratch directory
+/// laid out like `/sys/fs/cgroup` and `/proc`, so nothing here needs the real ones.
+pub struct CgroupFs {
+    root: PathBuf,
+    proc_root: PathBuf,
+}
+
+impl CgroupFs {
+    pub fn new(root: impl Into<PathBuf>, proc_root: impl Into<PathBuf>) -> Self {
+        CgroupFs { root: root.into(), proc_root: proc_root.into() }
+    }
+
+    pub fn system() -> Self {
+        CgroupFs::new("/sys/fs/cgroup", "/proc")
+    }
+
+    /// Absolute directory of a cgroup given relative to the hierarchy root.
+    /// The helper writes here as root, so `.` and `..` components are refused,
+    /// and so is anything a symlink resolves to outside the hierarchy.
+    pub fn path(&self, cgroup: &str) -> Result<PathBuf, CtrlError> {
+        let relative = Path::new(cgroup.trim_start_matches('/'));
+        if !relative.components().all(|part| matches!(part, Component::Normal(_))) {
+            return Err(CtrlError::InvalidArgument("cgroup paths may not contain . or .."));
+        }
+        let path = self.root.join(relative);
+        self.confine(&path)?;
+        Ok(path)
+    }
+
+    /// Fails unless `path`, with symlinks resolved, is inside the hierarchy. A
+    /// path that does not exist yet is judged by its deepest existing ancestor.
+    fn confine(&self, path: &Path) -> Result<(), CtrlError> {
+        let root = self.root.canonicalize()?;
+        let existing = path.ancestors().find(|ancestor| ancestor.symlink_metadata().is_ok()).unwrap_or(path);
+        if existing.canonicalize()?.starts_with(&root) {
+            Ok(())
+        } else {
+            Err(CtrlError::InvalidArgument("cgroup path leads outside the cgroup hierarchy"))
+        }
+    }
+
+    /// The cgroup v2 path of `pid`, from the `0::` line of `/proc/<pid>/cgroup`.
+    pub fn cgroup_of(&self, pid: i32) -> Result<String, CtrlError> {
+        let text = fs::read_to_string(self.proc_root.join(pid.to_string()).join("cgroup"))?;
+        text.lines()
+            .find_map(|line| line.strip_prefix("0::"))
+            .map(|path| path.to_string())
+            .ok_or(CtrlError::InvalidArgument("process is not in a cgroup v2 hierarchy"))
+    }
+
+    /// Creates `cgroup` (and missing parents), delegating the cpu, memory, io
+    /// and pids controllers down to it where the parents offer them.
+    pub fn create(&self, cgroup: &str) -> Result<(), CtrlError> {
+        self.path(cgroup)?;
+        let mut current = self.root.clone();
+        for part in cgroup.trim_matches('/').split('/').filter(|part| !part.is_empty()) {
+            enable_controllers(&current);
+            current.push(part);
+            self.confine(&current)?;
+            if !current.is_dir() {
+                fs::create_dir(&current)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves `pid` (with all its threads) into `cgroup` by writing `cgroup.procs`.
+    pub fn move_pid(&self, pid: i32, cgroup: &str) -> Result<(), CtrlError> {
+        fs::write(self.path(cgroup)?.join("cgroup.procs"), pid.to_string())?;
+        Ok(())
+    }
+
+    pub fn set_limit(&self, cgroup: &str, limit: CgroupLimit, value: &str) -> Result<(), CtrlError> {
+        limit.validate(value)?;
+        fs::write(self.path(cgroup)?.join(limit.file_name()), value.trim())?;
+        Ok(())
+    }
+
+    /// Everything is `None` for a path [`CgroupFs::path`] refuses.
+    pub fn info(&self, cgroup: &str) -> CgroupInfo {
+        let dir = self.path(cgroup).ok();
+        let read = |name: &str| {
+            dir.as_ref()
+                .and_then(|dir| fs::read_to_string(dir.join(name)).ok())
+                .map(|text| text.trim().to_string())
+        };
+        let usage_usec = read("cpu.stat").and_then(|stat| {
+            stat.lines()
+                .find_map(|line| line.strip_prefix("usage_usec "))
+                .and_then(|value| value.parse().ok())
+        });
+        CgroupInfo {
+            path: cgroup.to_string(),
+            limits: CgroupLimit::ALL.iter().map(|&limit| (limit, read(limit.file_name()))).collect(),
+            memory_current: read("memory.current").and_then(|value| value.parse().ok()),
+            cpu_usage_usec: usage_usec,
+            pids_current: read("pids.current").and_then(|value| value.parse().ok()),
+        }
+    }
+}
+
+/// Prefix of the single-process cgroups that [`CgroupFs::freeze_pid`] creates.
+const FREEZE_PREFIX: &str = "procmaster-frozen-";
+
+fn is_freeze_cgroup(cgroup: &str) -> bool {
+    cgroup.rsplit('/').next().is_some_and(|name| name.starts_with(FREEZE_PREFIX))
+}
+
+impl CgroupFs {
+    /// The cgroup whose own `cgroup.freeze` keeps `cgroup` frozen: `cgroup`
+    /// itself or the nearest frozen ancestor. `None` when nothing freezes it.
+    pub fn frozen_by(&self, cgroup: &str) -> Option<String> {
+        let mut current = cgroup.trim_end_matches('/');
+        loop {
+            let freeze = self.path(current).and_then(|dir| Ok(fs::read_to_string(dir.join("cgroup.freeze"))?));
+            if freeze.is_ok_and(|freeze| freeze.trim() == "1") {
+                return Some(if current.is_empty() { "/".to_string() } else { current.to_string() });
+            }
+            current = current.rsplit_once('/')?.0;
+        }
+    }
+
+    /// Whether `cgroup` is frozen, either itself or through a frozen ancestor.
+    pub fn is_frozen(&self, cgroup: &str) -> bool {
+        self.path(cgroup)
+            .and_then(|dir| Ok(fs::read_to_string(dir.join("cgroup.events"))?))
+            .is_ok_and(|events| events.lines().any(|line| line == "frozen 1"))
+    }
+
+    /// Freezes or thaws every process in `cgroup` by writing `cgroup.freeze`.
+    pub fn set_frozen(&self, cgroup: &str, frozen: bool) -> Result<(), CtrlError> {
+        fs::write(self.path(cgroup)?.join("cgroup.freeze"), if frozen { "1" } else { "0" })?;
+        Ok(())
+    }
+
+    /// Freezes `pid` alone. Writing `cgroup.freeze` on its own cgroup would
+    /// freeze every sibling too, so the process is first moved into a child
+    /// cgroup of its current one that only it lives in.
+    pub fn freeze_pid(&self, pid: i32) -> Result<(), CtrlError> {
+        let current = self.cgroup_of(pid)?;
+        self.remove_stale_freeze_cgroups_near(&current);
+        if is_freeze_cgroup(&current) {
+            return self.set_frozen(&current, true);
+        }
+        let frozen = format!("{}/{}{}", current.trim_end_matches('/'), FREEZE_PREFIX, pid);
+        let dir = self.path(&frozen)?;
+        if !dir.is_dir() {
+            fs::create_dir(&dir)?;
+        }
+        if let Err(err) = self.move_pid(pid, &frozen) {
+            let _ = fs::remove_dir(&dir);
+            return Err(err);
+        }
+        self.set_frozen(&frozen, true)
+    }
+
+    /// Undoes [`CgroupFs::freeze_pid`]: thaws the process, moves it back to its
+    /// original cgroup and removes the single-process one. If `pid` was frozen
+    /// together with its whole cgroup, that cgroup is thawed instead. A process
+    /// frozen through an ancestor is refused, since thawing its own cgroup
+    /// would change nothing; see [`CgroupFs::frozen_by`].
+    pub fn thaw_pid(&self, pid: i32) -> Result<(), CtrlError> {
+        let current = self.cgroup_of(pid)?;
+        self.remove_stale_freeze_cgroups_near(&current);
+        if self.frozen_by(&current).is_some_and(|frozen| frozen.trim_end_matches('/') != current.trim_end_matches('/')) {
+            return Err(CtrlError::InvalidArgument("the process is frozen through an ancestor cgroup, which has to be thawed instead"));
+        }
+        self.set_frozen(&current, false)?;
+        if !is_freeze_cgroup(&current) {
+            return Ok(());
+        }
+        let parent = match current.rsplit_once('/') {
+            Some(("", _)) | None => "/",
+            Some((parent, _)) => parent,
+        };
+        self.move_pid(pid, parent)?;
+        if let Ok(dir) = self.path(&current) {
+            let _ = fs::remove_dir(dir);
+        }
+        Ok(())
+    }
+
+    /// Whether `dir` is the single-process cgroup of a frozen process that
+    /// exited before it was thawed: empty, and named after a PID that is gone.
+    /// An empty one whose process is alive may be one another ProcMaster has
+    /// just created and not moved the process into yet.
+    fn is_stale_freeze_cgroup(&self, dir: &Path) -> bool {
+        let Some(pid) = dir.file_name().and_then(|name| name.to_str()).and_then(|name| name.strip_prefix(FREEZE_PREFIX)) else {
+            return false;
+        };
+        !self.proc_root.join(pid).exists()
+            && fs::read_to_string(dir.join("cgroup.procs")).is_ok_and(|procs| procs.trim().is_empty())
+    }
+
+    /// Removes stale single-process cgroups right below `cgroup` and below its
+    /// parent, which is where [`CgroupFs::freeze_pid`] leaves them for the
+    /// processes around `cgroup`. Best effort, like the full sweep.
+    pub fn remove_stale_freeze_cgroups_near(&self, cgroup: &str) {
+        let cgroup = cgroup.trim_end_matches('/');
+        let parent = cgroup.rsplit_once('/').map_or("", |(parent, _)| parent);
+        for dir in [cgroup, parent].into_iter().filter_map(|cgroup| self.path(cgroup).ok()) {
+            for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+                if entry.file_type().is_ok_and(|kind| kind.is_dir()) && self.is_stale_freeze_cgroup(&entry.path()) {
+                    let _ = fs::remove_dir(entry.path());
+                }
+            }
+        }
+    }
+
+    /// Removes the single-process cgroups of frozen processes that exited
+    /// before they were thawed, anywhere in the hierarchy. This walks all of
+    /// it, so it runs once at startup. Best effort: one that cannot be read
+    /// or removed is left alone.
+    pub fn remove_stale_freeze_cgroups(&self) {
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+                if !entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                    continue;
+                }
+                if !entry.file_name().to_string_lossy().starts_with(FREEZE_PREFIX) {
+                    dirs.push(entry.path());
+                } else if self.is_stale_freeze_cgroup(&entry.path()) {
+                    let _ = fs::remove_dir(entry.path());
+                }
+            }
+        }
+    }
+}
+
+/// Best effort: a controller the parent does not have, or cannot delegate, is skipped.
+fn enable_controllers(dir: &Path) {
+    let Ok(available) = fs::read_to_string(dir.join("cgroup.controllers")) else { return };
+    for controller in available.split_whitespace() {
+        if matches!(controller, "cpu" | "memory" | "io" | "pids") {
+            let _ = fs::write(dir.join("cgroup.subtree_control"), format!("+{}", controller));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// A scratch `/sys/fs/cgroup` and `/proc` under the temporary directory,
+    /// removed again when dropped.
+    struct Tree {
+        dir: PathBuf,
+        cgroups: CgroupFs,
+    }
+
+    impl Tree {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("procmaster-cgroup-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(dir.join("cgroup")).unwrap();
+            fs::create_dir_all(dir.join("proc")).unwrap();
+            let cgroups = CgroupFs::new(dir.join("cgroup"), dir.join("proc"));
+            Tree { dir, cgroups }
+        }
+
+        fn cgroup(&self, path: &str) -> PathBuf {
+            self.dir.join("cgroup").join(path)
+        }
+
+        fn write(&self, path: &str, text: &str) {
+            let path = self.dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+    }
+
+    impl Drop for Tree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn cgroup_of_reads_the_v2_line() {
+        let tree = Tree::new("cgroup-of");
+        tree.write("proc/42/cgroup", "1:name=systemd:/legacy\n0::/user.slice/app.scope\n");
+        tree.write("proc/43/cgroup", "1:cpu:/legacy\n");
+        assert_eq!(tree.cgroups.cgroup_of(42).unwrap(), "/user.slice/app.scope");
+        assert!(matches!(tree.cgroups.cgroup_of(43), Err(CtrlError::InvalidArgument(_))));
+        assert_eq!(tree.cgroups.cgroup_of(44), Err(CtrlError::Os(nix::errno::Errno::ENOENT)));
+    }
+
+    #[test]
+    fn create_delegates_controllers_the_parent_offers() {
+        let tree = Tree::new("create");
+        tree.write("cgroup/cgroup.controllers", "cpuset memory\n");
+        tree.cgroups.create("/procmaster/job").unwrap();
+        assert!(tree.cgroup("procmaster/job").is_dir());
+        // Each controller is written on its own, so the last one is left;
+        // cpuset is not one ProcMaster delegates.
+        assert_eq!(fs::read_to_string(tree.cgroup("cgroup.subtree_control")).unwrap(), "+memory");
+        // The new parent has no controllers file, so nothing is written there.
+        assert!(!tree.cgroup("procmaster/cgroup.subtree_control").exists());
+        // Creating it again is not an error.
+        tree.cgroups.create("procmaster/job").unwrap();
+    }
+
+    #[test]
+    fn move_pid_writes_cgroup_procs() {
+        let tree = Tree::new("move");
+        tree.cgroups.create("job").unwrap();
+        tree.cgroups.move_pid(42, "/job").unwrap();
+        assert_eq!(fs::read_to_string(tree.cgroup("job/cgroup.procs")).unwrap(), "42");
+        assert!(tree.cgroups.move_pid(42, "missing").is_err());
+    }
+
+    #[test]
+    fn set_limit_validates_before_writing() {
+        let tree = Tree::new("limit");
+        tree.cgroups.create("job").unwrap();
+        tree.cgroups.set_limit("job", CgroupLimit::MemoryMax, " 256M\n").unwrap();
+        assert_eq!(fs::read_to_string(tree.cgroup("job/memory.max")).unwrap(), "256M");
+        assert!(matches!(tree.cgroups.set_limit("job", CgroupLimit::MemoryMax, "lots"), Err(CtrlError::InvalidArgument(_))));
+        assert_eq!(fs::read_to_string(tree.cgroup("job/memory.max")).unwrap(), "256M");
+
+        let valid = [
+            (CgroupLimit::CpuMax, "50000 100000"),
+            (CgroupLimit::CpuMax, "max"),
+            (CgroupLimit::CpuWeight, "10000"),
+            (CgroupLimit::IoMax, "8:0 rbps=1048576 wiops=max"),
+            (CgroupLimit::PidsMax, "max"),
+        ];
+        for (limit, value) in valid {
+            assert_eq!(limit.validate(value), Ok(()), "{} {}", limit, value);
+        }
+        let invalid = [
+            (CgroupLimit::CpuMax, "50000 often"),
+            (CgroupLimit::CpuMax, "1 2 3"),
+            (CgroupLimit::CpuWeight, "0"),
+            (CgroupLimit::MemoryHigh, "1Q"),
+            (CgroupLimit::IoMax, "sda rbps=1"),
+            (CgroupLimit::IoMax, "8:0 speed=1"),
+            (CgroupLimit::PidsMax, "-1"),
+        ];
+        for (limit, value) in invalid {
+            assert!(limit.validate(value).is_err(), "{} {}", limit, value);
+        }
+    }
+
+    #[test]
+    fn info_reads_limits_and_usage() {
+        let tree = Tree::new("info");
+        tree.write("cgroup/job/memory.max", "max\n");
+        tree.write("cgroup/job/memory.current", "1048576\n");
+        tree.write("cgroup/job/cpu.stat", "usage_usec 5000\nuser_usec 3000\n");
+        let info = tree.cgroups.info("/job");
+        assert_eq!(info.path, "/job");
+        assert_eq!(info.memory_current, Some(1048576));
+        assert_eq!(info.cpu_usage_usec, Some(5000));
+        assert_eq!(info.pids_current, None);
+        let limit = |wanted| info.limits.iter().find(|(limit, _)| *limit == wanted).unwrap().1.clone();
+        assert_eq!(limit(CgroupLimit::MemoryMax).as_deref(), Some("max"));
+        assert_eq!(limit(CgroupLimit::CpuMax), None);
+    }
+
+    #[test]
+    fn freeze_and_thaw_use_a_single_process_cgroup() {
+        let tree = Tree::new("freeze");
+        tree.write("proc/42/cgroup", "0::/app\n");
+        tree.write("cgroup/app/cgroup.procs", "");
+        tree.cgroups.freeze_pid(42).unwrap();
+        assert_eq!(fs::read_to_string(tree.cgroup("app/procmaster-frozen-42/cgroup.procs")).unwrap(), "42");
+        assert_eq!(fs::read_to_string(tree.cgroup("app/procmaster-frozen-42/cgroup.freeze")).unwrap(), "1");
+
+        // The kernel would have moved it; the scratch tree has to be told.
+        tree.write("proc/42/cgroup", "0::/app/procmaster-frozen-42\n");
+        assert_eq!(tree.cgroups.frozen_by("/app/procmaster-frozen-42").as_deref(), Some("/app/procmaster-frozen-42"));
+        tree.cgroups.thaw_pid(42).unwrap();
+        assert_eq!(fs::read_to_string(tree.cgroup("app/procmaster-frozen-42/cgroup.freeze")).unwrap(), "0");
+        assert_eq!(fs::read_to_string(tree.cgroup("app/cgroup.procs")).unwrap(), "42");
+    }
+
+    #[test]
+    fn only_freeze_cgroups_of_exited_processes_are_stale() {
+        let tree = Tree::new("stale");
+        tree.write("proc/42/cgroup", "0::/app\n");
+        tree.write("cgroup/app/procmaster-frozen-7/cgroup.procs", "");
+        tree.write("cgroup/app/procmaster-frozen-8/cgroup.procs", "8\n");
+        tree.write("cgroup/app/procmaster-frozen-42/cgroup.procs", "");
+        tree.write("cgroup/app/worker/cgroup.procs", "");
+        // rmdir of a real cgroup ignores its interface files, so only the
+        // verdict is checked here.
+        let stale = |cgroup: &str| tree.cgroups.is_stale_freeze_cgroup(&tree.cgroup(cgroup));
+        assert!(stale("app/procmaster-frozen-7"));
+        // Still populated, or its process is alive and may be about to move in.
+        assert!(!stale("app/procmaster-frozen-8"));
+        assert!(!stale("app/procmaster-frozen-42"));
+        assert!(!stale("app/worker"));
+    }
+
+    #[test]
+    fn thaw_refuses_a_process_frozen_through_an_ancestor() {
+        let tree = Tree::new("ancestor");
+        tree.write("cgroup/batch/cgroup.freeze", "1\n");
+        tree.write("cgroup/batch/job/cgroup.freeze", "0\n");
+        tree.write("cgroup/batch/job/cgroup.events", "populated 1\nfrozen 1\n");
+        tree.write("proc/42/cgroup", "0::/batch/job\n");
+        assert!(tree.cgroups.is_frozen("/batch/job"));
+        assert_eq!(tree.cgroups.frozen_by("/batch/job").as_deref(), Some("/batch"));
+        assert!(matches!(tree.cgroups.thaw_pid(42), Err(CtrlError::InvalidArgument(_))));
+        assert_eq!(fs::read_to_string(tree.cgroup("batch/job/cgroup.freeze")).unwrap(), "0\n");
+        assert_eq!(tree.cgroups.frozen_by("/elsewhere"), None);
+    }
+
+    #[test]
+    fn paths_outside_the_hierarchy_are_refused() {
+        let tree = Tree::new("escape");
+        fs::create_dir_all(tree.dir.join("outside")).unwrap();
+        symlink(tree.dir.join("outside"), tree.cgroup("link")).unwrap();
+        for cgroup in ["..", "../outside", "procmaster/../../outside", "./job", "link", "link/job"] {
+            assert!(matches!(tree.cgroups.path(cgroup), Err(CtrlError::InvalidArgument(_))), "{}", cgroup);
+            assert!(tree.cgroups.create(cgroup).is_err(), "{}", cgroup);
+            assert!(tree.cgroups.move_pid(42, cgroup).is_err(), "{}", cgroup);
+            assert!(tree.cgroups.set_limit(cgroup, CgroupLimit::PidsMax, "10").is_err(), "{}", cgroup);
+        }
+        assert!(!tree.dir.join("outside/job").exists());
+        assert!(!tree.dir.join("outside/cgroup.procs").exists());
+        assert!(!tree.cgroup("procmaster").exists());
+        assert_eq!(tree.cgroups.info("../outside").memory_current, None);
+        assert_eq!(tree.cgroups.path("/").unwrap(), tree.cgroup(""));
+    }
+}
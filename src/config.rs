@@ -0,0 +1,344 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Settings read from `config.toml`. Every section and field is optional, so a
+/// missing file or an empty one gives the defaults below.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub protection: ProtectionConfig,
+    pub audit: AuditConfig,
+    pub suspend: SuspendConfig,
+    pub watchdog: WatchdogConfig,
+    pub helper: HelperConfig,
+    pub supervisor: SupervisorConfig,
+    pub hung_tasks: HungTaskConfig,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtectionMode {
+    /// Protected processes need a second confirmation.
+    #[default]
+    Confirm,
+    /// Protected processes are refused unless the action is forced.
+    Refuse,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ProtectionConfig {
+    pub mode: ProtectionMode,
+    /// Glob patterns matched against the command name, e.g. `"sshd"` or `"postgres*"`.
+    pub patterns: Vec<String>,
+    /// Extra PIDs to protect on top of init, ProcMaster, its parent and the session leader.
+    pub pids: Vec<i32>,
+}
+
+impl Default for ProtectionConfig {
+    fn default() -> Self {
+        ProtectionConfig {
+            mode: ProtectionMode::Confirm,
+            patterns: vec!["sshd".to_string()],
+            pids: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    /// JSON-lines file control actions are appended to. Defaults to `audit.jsonl`
+    /// next to the config file.
+    pub file: Option<PathBuf>,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        AuditConfig { enabled: true, file: None }
+    }
+}
+
+impl AuditConfig {
+    pub fn path(&self) -> PathBuf {
+        self.file.clone().unwrap_or_else(|| config_dir().join("audit.jsonl"))
+    }
+}
+
+/// What to do on quit while processes ProcMaster suspended are still stopped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnQuit {
+    /// Ask whether to resume them first.
+    #[default]
+    Warn,
+    /// Resume them without asking.
+    Resume,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SuspendConfig {
+    pub on_quit: OnQuit,
+}
+
+/// Rules the watchdog checks against every process snapshot.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    /// Only log what the rules would do. Overrides every rule's own setting.
+    pub dry_run: bool,
+    pub rules: Vec<RuleConfig>,
+}
+
+/// One `[[watchdog.rules]]` entry, e.g.
+///
+/// ```toml
+/// [[watchdog.rules]]
+/// name = "runaway worker"
+/// when = "rss > 4GiB for 60s and command matches 'worker*'"
+/// action = "signal TERM"
+/// cooldown = "10m"
+/// rate_limit = "5/1h"
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+pub struct RuleConfig {
+    pub name: String,
+    /// Comparisons joined by `and`, each optionally followed by `for DURATION`,
+    /// see `watchdog::Condition`. `cpu` is the lifetime average the Processes
+    /// tab shows, not current usage.
+    pub when: String,
+    /// How long all the comparisons must hold together before the rule fires.
+    /// Defaults to at once.
+    #[serde(rename = "for")]
+    pub hold: Option<String>,
+    /// `signal SIG`, `renice N`, `ioprio CLASS/LEVEL`, `cgroup FILE VALUE`, `exec COMMAND` or `alert`.
+    pub action: String,
+    /// Minimum time between two firings on the same process. Defaults to 5m.
+    pub cooldown: Option<String>,
+    /// At most `COUNT/PERIOD` firings of this rule overall, e.g. `5/1h`.
+    pub rate_limit: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// How to reach `procmaster-helper`, which performs operations ProcMaster lacks
+/// the privilege for. What the helper allows is set in its own policy file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct HelperConfig {
+    /// Fall back to the helper when an operation fails with EPERM or EACCES.
+    pub enabled: bool,
+    /// Defaults to `/run/procmaster/helper.sock`.
+    pub socket: Option<PathBuf>,
+}
+
+impl Default for HelperConfig {
+    fn default() -> Self {
+        HelperConfig { enabled: true, socket: None }
+    }
+}
+
+impl HelperConfig {
+    pub fn socket_path(&self) -> PathBuf {
+        self.socket.clone().unwrap_or_else(|| PathBuf::from("/run/procmaster/helper.sock"))
+    }
+}
+
+/// When a thread stuck in uninterruptible sleep (D state) is reported as hung.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HungTaskConfig {
+    /// How long a thread has to stay in D state, e.g. `"2m"`. Defaults to 30s.
+    pub threshold: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SupervisorConfig {
+    pub services: Vec<ServiceConfig>,
+}
+
+/// When a supervised command is started again after it exits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    Always,
+    /// Only after a non-zero exit or a kill.
+    #[default]
+    OnFailure,
+    Never,
+}
+
+/// One `[[supervisor.services]]` entry, e.g.
+///
+/// ```toml
+/// [[supervisor.services]]
+/// name = "worker"
+/// run = "-d ~/src/app -n 10 ./worker --queue jobs"
+/// restart = "always"
+/// backoff = ["1s", "5s", "30s"]
+/// max_restarts = 10
+/// health_check = "curl -sf localhost:8080/health"
+/// health_interval = "30s"
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServiceConfig {
+    pub name: String,
+    /// A run spec as the launch dialog takes it, options first, then the command.
+    pub run: String,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    /// Delays before each restart; the last one repeats. Defaults to 1s, 5s, 30s.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backoff: Vec<String>,
+    /// Give up after this many restarts. Unlimited if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_restarts: Option<u32>,
+    /// Run with `sh -c` every `health_interval`; after three failures in a row
+    /// the service is terminated, and restarted as `restart` says.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<String>,
+    /// Defaults to 30s.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health_interval: Option<String>,
+    /// Start when ProcMaster starts. Defaults to true.
+    #[serde(default = "default_true")]
+    pub autostart: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Appends `service` to the config file as a `[[supervisor.services]]` table,
+/// leaving the rest of the file as it is.
+pub fn add_service(service: &ServiceConfig) -> Result<(), String> {
+    if load()?.supervisor.services.iter().any(|existing| existing.name == service.name) {
+        return Err(format!("A service named {} already exists", service.name));
+    }
+    let path = config_path();
+    let mut text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(format!("Failed to read config {}: {}", path.display(), err)),
+    };
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    let body = toml::to_string(service).map_err(|err| format!("Failed to write service {}: {}", service.name, err))?;
+    text.push_str(&format!("\n[[supervisor.services]]\n{}", body));
+    write_config(&text)
+}
+
+/// Removes the `[[supervisor.services]]` table named `name` from the config
+/// file. Other tables, comments and formatting are kept.
+pub fn remove_service(name: &str) -> Result<(), String> {
+    let path = config_path();
+    let text = fs::read_to_string(&path).map_err(|err| format!("Failed to read config {}: {}", path.display(), err))?;
+    let mut kept = String::new();
+    let mut found = false;
+    for section in split_tables(&text) {
+        let is_target = section.trim_start().starts_with("[[supervisor.services]]")
+            && section.split_once('\n')
+                .and_then(|(_, body)| toml::from_str::<ServiceConfig>(body).ok())
+                .is_some_and(|service| service.name == name);
+        if is_target {
+            found = true;
+        } else {
+            kept.push_str(section);
+        }
+    }
+    if !found {
+        return Err(format!("No service named {} in {}", name, path.display()));
+    }
+    write_config(&kept)
+}
+
+/// Splits a TOML document at its table headers, each piece starting with its
+/// header line (the first piece holds whatever precedes the first header).
+fn split_tables(text: &str) -> Vec<&str> {
+    let header = Regex::new(r#"^\s*\[\[?\s*[A-Za-z0-9_.\-]+\s*\]\]?\s*(#.*)?$"#).unwrap();
+    let mut starts = vec![0];
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if offset > 0 && header.is_match(line.trim_end()) {
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+    starts.push(text.len());
+    starts.windows(2).map(|range| &text[range[0]..range[1]]).collect()
+}
+
+fn write_config(text: &str) -> Result<(), String> {
+    let path = config_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| format!("Failed to create {}: {}", dir.display(), err))?;
+    }
+    // Refuse to write something that would not load again.
+    let config = toml::from_str::<Config>(text).map_err(|err| format!("Refusing to write an invalid config: {}", err))?;
+    let tmp = path.with_extension("toml.tmp");
+    fs::write(&tmp, text)
+        .and_then(|()| fs::rename(&tmp, &path))
+        .map_err(|err| format!("Failed to write config {}: {}", path.display(), err))?;
+    *CURRENT.write().unwrap() = Some(Arc::new(config));
+    Ok(())
+}
+
+/// `$PROCMASTER_CONFIG`, else `$XDG_CONFIG_HOME/procmaster/config.toml`,
+/// else `~/.config/procmaster/config.toml`.
+pub fn config_path() -> PathBuf {
+    if let Some(path) = env::var_os("PROCMASTER_CONFIG") {
+        return PathBuf::from(path);
+    }
+    config_dir().join("config.toml")
+}
+
+/// Directory ProcMaster keeps its config and state files in.
+pub fn config_dir() -> PathBuf {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    base.join("procmaster")
+}
+
+/// Loads the config file. A missing file gives the defaults; a malformed one is an error.
+pub fn load() -> Result<Config, String> {
+    let path = config_path();
+    match fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text).map_err(|err| format!("Invalid config {}: {}", path.display(), err)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(format!("Failed to read config {}: {}", path.display(), err)),
+    }
+}
+
+static CURRENT: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+
+/// The config as last loaded, read from the file on first use. The guard and
+/// the audit log consult it on every action, so it is cached rather than
+/// parsed each time; [`reload`] and writes through this module refresh it. A
+/// malformed file gives the defaults, so a typo never switches protection or
+/// auditing off.
+pub fn current() -> Arc<Config> {
+    if let Some(config) = CURRENT.read().unwrap().as_ref() {
+        return Arc::clone(config);
+    }
+    let mut current = CURRENT.write().unwrap();
+    Arc::clone(current.get_or_insert_with(|| Arc::new(load().unwrap_or_default())))
+}
+
+/// Reads the file again and makes it the [`current`] config. A malformed file
+/// is an error and leaves the cached config as it was.
+pub fn reload() -> Result<Arc<Config>, String> {
+    let config = Arc::new(load()?);
+    *CURRENT.write().unwrap() = Some(Arc::clone(&config));
+    Ok(config)
+}
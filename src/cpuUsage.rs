@@ -1,13 +1,17 @@
-use sysinfo::{System, Disk};
-use std::thread::sleep;
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::Duration;
 use std::path::Path;
 use std::fs::File;
 use std::io::{self, BufRead};
 
+use serde::Serialize;
 
 
-pub fn read_cpu_stat() -> io::Result<Vec<Vec<u64>>> {
+
+/// The `cpu` and `cpuN` lines of `/proc/stat`, each with its label. An offline
+/// CPU has no line, so the labels are what ties two readings together.
+pub fn read_cpu_stat() -> io::Result<Vec<(String, Vec<u64>)>> {
     let path = Path::new("/proc/stat");
     let file = File::open(&path)?;
     let reader = io::BufReader::new(file);
@@ -22,7 +26,7 @@ pub fn read_cpu_stat() -> io::Result<Vec<Vec<u64>>> {
                 .iter()
                 .map(|v| v.parse().unwrap_or(0))
                 .collect();
-            cpu_stats.push(values);
+            cpu_stats.push((parts[0].to_string(), values));
         }
         else {
             break;
@@ -31,36 +35,109 @@ pub fn read_cpu_stat() -> io::Result<Vec<Vec<u64>>> {
     Ok(cpu_stats)
 }
 
+/// Busy share in percent between two readings of one `/proc/stat` line. Counters
+/// that went backwards and readings with no ticks in between both give 0.
 pub fn calculate_cpu_usage(prev: &[u64], curr: &[u64]) -> f64 {
-    let prev_idle = prev[3] + prev[4];
-    let curr_idle = curr[3] + curr[4];
-
-    let prev_total: u64 = prev.iter().sum();
-    let curr_total: u64 = curr.iter().sum();
+    // idle + iowait
+    let idle = |stats: &[u64]| stats.iter().skip(3).take(2).sum::<u64>();
+    let total = |stats: &[u64]| stats.iter().sum::<u64>();
 
-    let total_diff = curr_total - prev_total;
-    let idle_diff = curr_idle - prev_idle;
+    let Some(total_diff) = total(curr).checked_sub(total(prev)).filter(|&diff| diff > 0) else { return 0.0 };
+    let Some(idle_diff) = idle(curr).checked_sub(idle(prev)) else { return 0.0 };
 
-    100.0 * (total_diff - idle_diff) as f64 / total_diff as f64
+    100.0 * total_diff.saturating_sub(idle_diff) as f64 / total_diff as f64
 }
 
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct CpuUsage {
     pub cpu_usage: f64,
     pub core_number: i32,
 }
 
-pub fn cpu_result() -> Vec<CpuUsage> {
-    let mut cpu_usages = Vec::new();
-    let mut prev_cpu_stats = read_cpu_stat().unwrap();
-    sleep(Duration::from_secs(1));
-    let curr_cpu_stats = read_cpu_stat().unwrap();
-
-    for (i, (prev, curr)) in prev_cpu_stats.iter().zip(curr_cpu_stats.iter()).enumerate().skip(1) {
-        let cpu_usage = calculate_cpu_usage(prev, curr);
-        cpu_usages.push(CpuUsage {
-            cpu_usage,
-            core_number: i as i32 - 1, // Adjust core number to start from 0 for the first core
-        });
+/// Latest CPU usage, kept current by [`start_background_update_cpu`].
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CpuUsages {
+    /// All CPUs together, from the `cpu` line.
+    pub total: f64,
+    pub cores: Vec<CpuUsage>,
+}
+
+/// Pairs the two readings by label; a CPU that just came online has no earlier
+/// reading and shows 0 until the next one.
+fn cpu_usages(prev_cpu_stats: &[(String, Vec<u64>)], curr_cpu_stats: &[(String, Vec<u64>)]) -> CpuUsages {
+    let mut usages = CpuUsages::default();
+    for (label, curr) in curr_cpu_stats {
+        let cpu_usage = prev_cpu_stats
+            .iter()
+            .find(|(prev_label, _)| prev_label == label)
+            .map_or(0.0, |(_, prev)| calculate_cpu_usage(prev, curr));
+        match label.strip_prefix("cpu").unwrap_or_default() {
+            "" => usages.total = cpu_usage,
+            number => usages.cores.push(CpuUsage {
+                cpu_usage,
+                core_number: number.parse().unwrap_or(-1),
+            }),
+        }
+    }
+    usages
+}
+
+/// Rereads `/proc/stat` every second, like the process list, and stores the
+/// usage since the previous reading, so nothing waits for a measurement.
+pub fn start_background_update_cpu(cpu_usage: Arc<Mutex<CpuUsages>>) {
+    thread::spawn(move || {
+        let mut prev_cpu_stats = read_cpu_stat().unwrap_or_default();
+        loop {
+            thread::sleep(Duration::from_secs(1));
+            let Ok(curr_cpu_stats) = read_cpu_stat() else { continue };
+            *cpu_usage.lock().unwrap() = cpu_usages(&prev_cpu_stats, &curr_cpu_stats);
+            prev_cpu_stats = curr_cpu_stats;
+        }
+    });
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// user nice system idle iowait irq softirq steal
+    fn reading(label: &str, busy: u64, idle: u64) -> (String, Vec<u64>) {
+        (label.to_string(), vec![busy, 0, 0, idle, 0, 0, 0, 0])
     }
-    cpu_usages
-}
\ No newline at end of file
+
+    #[test]
+    fn usage_is_the_busy_share_of_the_ticks_in_between() {
+        assert_eq!(calculate_cpu_usage(&reading("cpu", 100, 100).1, &reading("cpu", 175, 125).1), 75.0);
+        // iowait counts as idle.
+        assert_eq!(calculate_cpu_usage(&[0, 0, 0, 0, 0], &[10, 0, 0, 20, 10]), 25.0);
+    }
+
+    #[test]
+    fn counter_reset_gives_zero() {
+        assert_eq!(calculate_cpu_usage(&reading("cpu", 500, 500).1, &reading("cpu", 10, 10).1), 0.0);
+        // Only idle went backwards.
+        assert_eq!(calculate_cpu_usage(&reading("cpu", 100, 500).1, &reading("cpu", 700, 400).1), 0.0);
+    }
+
+    #[test]
+    fn equal_readings_give_zero() {
+        let stats = reading("cpu", 300, 700).1;
+        assert_eq!(calculate_cpu_usage(&stats, &stats), 0.0);
+    }
+
+    #[test]
+    fn readings_are_paired_by_label_when_a_core_goes_offline() {
+        let prev = [reading("cpu", 0, 0), reading("cpu0", 0, 0), reading("cpu1", 0, 0), reading("cpu2", 0, 0)];
+        // cpu1 went offline; cpu2 must still be compared with cpu2, not cpu1.
+        let curr = [reading("cpu", 150, 50), reading("cpu0", 50, 50), reading("cpu2", 100, 0)];
+        let usages = cpu_usages(&prev, &curr);
+        assert_eq!(usages.total, 75.0);
+        let cores: Vec<(i32, f64)> = usages.cores.iter().map(|core| (core.core_number, core.cpu_usage)).collect();
+        assert_eq!(cores, [(0, 50.0), (2, 100.0)]);
+
+        // It comes back online with no earlier reading to compare with.
+        let back = [reading("cpu", 150, 50), reading("cpu0", 50, 50), reading("cpu1", 30, 10), reading("cpu2", 100, 0)];
+        let usages = cpu_usages(&curr, &back);
+        assert_eq!(usages.cores[1].core_number, 1);
+        assert_eq!(usages.cores[1].cpu_usage, 0.0);
+    }
+}
@@ -1,73 +1,1105 @@
+use nix::errno::Errno;
+use nix::sched::{sched_getaffinity, sched_setaffinity, CpuSet};
+use nix::sys::resource::{getrlimit, Resource};
 use nix::sys::signal::{kill, Signal};
-use nix::unistd::{Pid};
-//use scheduler::Which::Process;
-use std::process::Command;
-use std::process::Stdio;
-use procfs::process::all_processes;
-//use scheduler::set_priority;
+use nix::unistd::{getppid, getsid, Pid};
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+use procfs::process::{all_processes, Process};
+use procfs::{ticks_per_second, Uptime};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use users::{get_user_by_name, get_user_by_uid};
+use serde_json::{json, Value};
+use crate::audit::audited;
+use crate::cgroup::{CgroupFs, CgroupLimit};
+use crate::config::{self, ProtectionConfig, ProtectionMode};
+use crate::helper::{fallback, HelperOp};
+use crate::suspended::{self, SuspendMethod, SuspendedProcess};
 
 
-pub fn kill_process(pid: i32) -> Result<(), String> {
-    send_signal(pid, Signal::SIGKILL)
+pub fn kill_process(pid: i32, bypass: Bypass) -> Result<(), String> {
+    signal_process(pid, Signal::SIGKILL, bypass)
 }
 
-pub fn terminate_process(pid: i32) -> Result<(), String> {
-    send_signal(pid, Signal::SIGTERM)
+pub fn terminate_process(pid: i32, bypass: Bypass) -> Result<(), String> {
+    signal_process(pid, Signal::SIGTERM, bypass)
 }
 
-pub fn suspend_process(pid: i32) -> Result<(), String> {
-    send_signal(pid, Signal::SIGSTOP)
+pub fn suspend_process(pid: i32, bypass: Bypass) -> Result<(), String> {
+    signal_process(pid, Signal::SIGSTOP, bypass)
 }
 
+/// Suspends `pid` with the cgroup v2 freezer. Unlike SIGSTOP this is invisible
+/// to the parent (no SIGCHLD, no `T` state), so supervisors and shells' job
+/// control are not confused by it.
+pub fn freeze_process(pid: i32, bypass: Bypass) -> Result<(), String> {
+    let args = json!({ "bypass": bypass });
+    check_guard(&Guard::load(), "freeze", pid, bypass, &args)?;
+    audited("freeze", Some(pid), args, || {
+        CgroupFs::system().freeze_pid(pid).or_else(|err| fallback(err, HelperOp::Freeze { pid }))
+    })
+        .map_err(|err| format!("Failed to freeze process {}: {}", pid, err))?;
+    suspended::add(pid, SuspendMethod::Freeze);
+    Ok(())
+}
+
+/// Resumes `pid` with whatever is holding it: thaws it if it is cgroup-frozen
+/// and sends SIGCONT if it is signal-stopped.
 pub fn resume_process(pid: i32) -> Result<(), String> {
-    send_signal(pid, Signal::SIGCONT)
+    let cgroups = CgroupFs::system();
+    if let Some(cgroup) = cgroups.cgroup_of(pid).ok().filter(|cgroup| cgroups.is_frozen(cgroup)) {
+        if let Some(frozen) = cgroups.frozen_by(&cgroup).filter(|frozen| frozen.trim_end_matches('/') != cgroup.trim_end_matches('/')) {
+            return Err(format!("Process {} is frozen because cgroup {} is; thaw that cgroup instead", pid, frozen));
+        }
+        audited("thaw", Some(pid), json!({}), || {
+            cgroups.thaw_pid(pid).or_else(|err| fallback(err, HelperOp::Thaw { pid }))
+        })
+            .map_err(|err| format!("Failed to thaw process {}: {}", pid, err))?;
+    }
+    let stopped = procfs::process::Process::new(pid)
+        .and_then(|proc| proc.stat())
+        .map_or(true, |stat| stat.state == 'T');
+    if stopped {
+        send_signal(pid, Signal::SIGCONT, Bypass::None)?;
+    }
+    suspended::remove(pid);
+    Ok(())
 }
 
-fn send_signal(pid: i32, signal: Signal) -> Result<(), String> {
-    let pid = Pid::from_raw(pid);
-    match kill(pid, signal) {
-        Ok(_) => Ok(()),
-        Err(err) => Err(format!("Failed to send signal to process {}: {}", pid, err)),
+/// Resumes every process ProcMaster suspended that is still suspended, and
+/// returns each one with how resuming it went.
+pub fn resume_suspended() -> Vec<(SuspendedProcess, Result<(), String>)> {
+    let cgroups = CgroupFs::system();
+    for cgroup in suspended::freeze_cgroups() {
+        cgroups.remove_stale_freeze_cgroups_near(&cgroup);
     }
+    suspended::load()
+        .into_iter()
+        .map(|entry| {
+            let result = resume_process(entry.pid);
+            (entry, result)
+        })
+        .collect()
 }
 
+/// Stops or continues `pid` for the CPU limiter. Unlike [`suspend_process`] and
+/// [`resume_process`] this bypasses the audit log and the suspended registry:
+/// the limiter toggles many times a second, so starting and removing it is
+/// what gets logged instead, and it registers each process once.
+pub fn set_stopped(pid: i32, stopped: bool) -> Result<(), CtrlError> {
+    kill(Pid::from_raw(pid), if stopped { Signal::SIGSTOP } else { Signal::SIGCONT })?;
+    Ok(())
+}
 
+/// Sends `signal` to `pid` after checking it against the protected-process [`Guard`].
+pub fn signal_process(pid: i32, signal: Signal, bypass: Bypass) -> Result<(), String> {
+    guarded_signal(&Guard::load(), pid, signal, bypass)
+}
 
-pub fn killall(process_name: &str) -> Result<(), String> {
-    let processes = all_processes().map_err(|err| format!("Failed to list processes: {}", err))?;
-    let mut errors = Vec::new();
-
-    for process in processes {
-        if let Ok(proc) = process {
-            if let Ok(stat) = proc.stat() {
-                if stat.comm == process_name {
-                    if let Err(err) = terminate_process(stat.pid) {
-                        errors.push(err);
+fn guarded_signal(guard: &Guard, pid: i32, signal: Signal, bypass: Bypass) -> Result<(), String> {
+    check_guard(guard, "signal", pid, bypass, &json!({ "signal": signal.as_str(), "bypass": bypass }))?;
+    send_signal(pid, signal, bypass)?;
+    if signal == Signal::SIGSTOP {
+        suspended::add(pid, SuspendMethod::Signal);
+    }
+    Ok(())
+}
+
+/// Like [`Guard::allow`], but a refusal is written to the audit log as well.
+fn check_guard(guard: &Guard, operation: &str, pid: i32, bypass: Bypass, args: &Value) -> Result<(), String> {
+    match guard.allow(pid, bypass) {
+        Ok(()) => Ok(()),
+        Err(err) => audited(operation, Some(pid), args.clone(), || Err(err)),
+    }
+}
+
+/// Sends `signal` to `pid` without asking the [`Guard`], for signals that cannot
+/// hurt, such as SIGCONT to resume or SIGCHLD to nudge a parent into reaping.
+/// Still audited.
+pub fn send_signal(pid: i32, signal: Signal, bypass: Bypass) -> Result<(), String> {
+    audited("signal", Some(pid), json!({ "signal": signal.as_str(), "bypass": bypass }), || {
+        kill(Pid::from_raw(pid), signal)
+            .map_err(CtrlError::from)
+            .or_else(|err| fallback(err, HelperOp::Signal { pid, signal: signal as i32, bypass }))
+    })
+    .map_err(|err| format!("Failed to send signal to process {}: {}", pid, err))
+}
+
+/// Parses a signal given as a number (`9`), a bare name (`KILL`) or a full name (`SIGKILL`).
+pub fn parse_signal(text: &str) -> Result<Signal, String> {
+    let text = text.trim();
+    if let Ok(number) = text.parse::<i32>() {
+        return Signal::try_from(number).map_err(|_| format!("Unknown signal number {}", number));
+    }
+    let upper = text.to_ascii_uppercase();
+    let name = if upper.starts_with("SIG") { upper } else { format!("SIG{}", upper) };
+    Signal::from_str(&name).map_err(|_| format!("Unknown signal {}", text))
+}
+
+/// What a process does with signals, from the SigPnd, ShdPnd, SigBlk, SigIgn
+/// and SigCgt masks in `/proc/PID/status`. Signals not listed anywhere get
+/// their default action.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SignalDisposition {
+    /// Pending for the main thread or for the process as a whole.
+    pub pending: Vec<String>,
+    /// Blocked by every thread. A process signal goes to any thread that does
+    /// not block it, so a signal only some threads block is still delivered.
+    pub blocked: Vec<String>,
+    pub ignored: Vec<String>,
+    /// Handled by the process itself, which may or may not exit on them.
+    pub caught: Vec<String>,
+    #[serde(skip)]
+    masks: [u64; 4],
+}
+
+impl SignalDisposition {
+    pub fn read(pid: i32) -> Result<Self, String> {
+        let process = procfs::process::Process::new(pid).map_err(|err| format!("Failed to read process {}: {}", pid, err))?;
+        let status = process.status().map_err(|err| format!("Failed to read process {}: {}", pid, err))?;
+        let blocked = process.tasks()
+            .map(|tasks| tasks.flatten().filter_map(|task| task.status().ok()).fold(u64::MAX, |mask, task| mask & task.sigblk))
+            .ok()
+            .filter(|&mask| mask != u64::MAX)
+            .unwrap_or(status.sigblk);
+        let masks = [status.sigpnd | status.shdpnd, blocked, status.sigign, status.sigcgt];
+        let [pending, blocked, ignored, caught] = masks.map(signal_names);
+        Ok(SignalDisposition { pending, blocked, ignored, caught, masks })
+    }
+
+    /// Why sending `signal` would not do what the user expects, such as
+    /// "This process ignores SIGTERM". SIGKILL and SIGSTOP cannot be blocked,
+    /// ignored or caught, so they never get a warning.
+    pub fn warning(&self, signal: Signal) -> Option<String> {
+        if matches!(signal, Signal::SIGKILL | Signal::SIGSTOP) {
+            return None;
+        }
+        let bit = 1 << (signal as i32 - 1);
+        let [pending, blocked, ignored, _] = self.masks;
+        if ignored & bit != 0 {
+            Some(format!("This process ignores {}, sending it does nothing", signal))
+        } else if blocked & bit != 0 && pending & bit != 0 {
+            Some(format!("This process blocks {} and one is already pending; another one is merged into it", signal))
+        } else if blocked & bit != 0 {
+            Some(format!("This process blocks {}; it stays pending until a thread unblocks it", signal))
+        } else {
+            None
+        }
+    }
+
+    pub fn catches(&self, signal: Signal) -> bool {
+        self.masks[3] & (1 << (signal as i32 - 1)) != 0
+    }
+}
+
+/// Names of the signals in a `/proc` signal mask, where bit N-1 is signal N.
+fn signal_names(mask: u64) -> Vec<String> {
+    (1..=64)
+        .filter(|number| mask & (1 << (number - 1)) != 0)
+        .map(|number| match Signal::try_from(number) {
+            Ok(signal) => signal.as_str().to_string(),
+            Err(_) if number >= libc::SIGRTMIN() => format!("SIGRTMIN+{}", number - libc::SIGRTMIN()),
+            Err(_) => format!("SIG{}", number),
+        })
+        .collect()
+}
+
+/// Parses an age such as `90`, `90s`, `15m`, `2h` or `1d` into seconds.
+pub fn parse_age(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => text.split_at(pos),
+        None => (text, "s"),
+    };
+    let value: u64 = number.parse().map_err(|_| format!("Invalid age {}", text))?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("Invalid age unit {} (use s, m, h, d or w)", unit)),
+    };
+    Ok(value * multiplier)
+}
+
+/// Resolves a user name or numeric uid to a uid.
+pub fn resolve_user(user: &str) -> Result<u32, String> {
+    if let Ok(uid) = user.parse::<u32>() {
+        return Ok(uid);
+    }
+    get_user_by_name(user)
+        .map(|u| u.uid())
+        .ok_or_else(|| format!("Unknown user {}", user))
+}
+
+/// The part of a process that a killall pattern is matched against.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchField {
+    /// The short command name from `/proc/<pid>/stat`.
+    #[default]
+    Comm,
+    /// The full command line, arguments joined by spaces.
+    Cmdline,
+    /// The path of the executable.
+    Exe,
+}
+
+impl FromStr for MatchField {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "comm" => Ok(MatchField::Comm),
+            "cmdline" => Ok(MatchField::Cmdline),
+            "exe" => Ok(MatchField::Exe),
+            _ => Err(format!("Unknown match field {} (use comm, cmdline or exe)", text)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Matcher {
+    Exact(String),
+    Glob(glob::Pattern),
+    Regex(Regex),
+}
+
+impl Matcher {
+    /// Builds a matcher from a mode (`exact`, `glob` or `regex`) and a pattern.
+    pub fn parse(mode: &str, pattern: &str) -> Result<Self, String> {
+        match mode {
+            "exact" => Ok(Matcher::Exact(pattern.to_string())),
+            "glob" => glob::Pattern::new(pattern)
+                .map(Matcher::Glob)
+                .map_err(|err| format!("Invalid glob {}: {}", pattern, err)),
+            "regex" => Regex::new(pattern)
+                .map(Matcher::Regex)
+                .map_err(|err| format!("Invalid regex {}: {}", pattern, err)),
+            _ => Err(format!("Unknown match mode {} (use exact, glob or regex)", mode)),
+        }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Exact(pattern) => text == pattern,
+            Matcher::Glob(pattern) => pattern.matches(text),
+            Matcher::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KillallOptions {
+    pub matcher: Matcher,
+    pub field: MatchField,
+    pub user: Option<u32>,
+    /// Only match processes that have been running for at least this many seconds.
+    pub min_age: Option<u64>,
+    /// Only match processes in this state (`R`, `S`, `D`, `T`, `Z`, ...).
+    pub state: Option<char>,
+    pub signal: Signal,
+    pub dry_run: bool,
+    /// Applied to protected matches; the rest are signalled without asking.
+    pub bypass: Bypass,
+}
+
+/// Parses a killall-style spec, e.g. `-r -f -u bob -o 1h -s KILL 'worker-\d+'`.
+///
+/// `-r` regex, `-g` glob (exact otherwise), `-f` match the full command line,
+/// `-x` match the executable path, `-u` user, `-o` minimum age, `-S` state,
+/// `-s` signal and `-n` dry run. Quote a pattern that contains spaces, e.g.
+/// `-f 'python worker.py'`.
+impl FromStr for KillallOptions {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut mode = "exact";
+        let mut field = MatchField::Comm;
+        let mut user = None;
+        let mut min_age = None;
+        let mut state = None;
+        let mut signal = Signal::SIGTERM;
+        let mut dry_run = false;
+        let mut pattern = None;
+
+        let mut tokens = split_spec(spec)?.into_iter();
+        while let Some((token, quoted)) = tokens.next() {
+            let mut value = |flag: &str| {
+                tokens.next().map(|(value, _)| value).ok_or_else(|| format!("{} needs a value", flag))
+            };
+            match token.as_str() {
+                _ if quoted => {
+                    if pattern.is_some() {
+                        return Err("Only one pattern may be given".to_string());
+                    }
+                    pattern = Some(token);
+                }
+                "-r" => mode = "regex",
+                "-g" => mode = "glob",
+                "-f" => field = MatchField::Cmdline,
+                "-x" => field = MatchField::Exe,
+                "-n" => dry_run = true,
+                "-u" => user = Some(resolve_user(&value("-u")?)?),
+                "-o" => min_age = Some(parse_age(&value("-o")?)?),
+                "-s" => signal = parse_signal(&value("-s")?)?,
+                "-S" => {
+                    let text = value("-S")?;
+                    let mut chars = text.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => state = Some(c.to_ascii_uppercase()),
+                        _ => return Err(format!("Invalid state {}", text)),
+                    }
+                }
+                _ if token.starts_with('-') && token.len() > 1 => {
+                    return Err(format!("Unknown option {}", token));
+                }
+                _ => {
+                    if pattern.is_some() {
+                        return Err("Only one pattern may be given".to_string());
                     }
+                    pattern = Some(token);
                 }
             }
         }
+
+        let pattern = pattern.ok_or_else(|| "No pattern given".to_string())?;
+        Ok(KillallOptions {
+            matcher: Matcher::parse(mode, &pattern)?,
+            field,
+            user,
+            min_age,
+            state,
+            signal,
+            dry_run,
+            bypass: Bypass::None,
+        })
     }
+}
 
-    if errors.is_empty() {
-        Ok(())
-    } else {
-        Err(errors.join(", "))
+/// Splits a killall spec on whitespace, keeping anything inside single or
+/// double quotes together. Each token comes with whether any of it was quoted,
+/// so a quoted pattern is never mistaken for an option.
+fn split_spec(spec: &str) -> Result<Vec<(String, bool)>, String> {
+    let mut tokens = Vec::new();
+    let mut token: Option<(String, bool)> = None;
+    let mut chars = spec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                let (text, quoted) = token.get_or_insert_with(Default::default);
+                *quoted = true;
+                loop {
+                    match chars.next() {
+                        Some(next) if next == c => break,
+                        Some(next) => text.push(next),
+                        None => return Err(format!("Unterminated {} quote", c)),
+                    }
+                }
+            }
+            _ if c.is_whitespace() => tokens.extend(token.take()),
+            _ => token.get_or_insert_with(Default::default).0.push(c),
+        }
+    }
+    tokens.extend(token);
+    Ok(tokens)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KillallMatch {
+    pub pid: i32,
+    /// Start time in clock ticks, so a confirmed preview can tell if the pid was reused.
+    pub starttime: u64,
+    pub user: String,
+    pub command: String,
+    pub state: char,
+    /// Why the process is protected, if it is.
+    pub protected: Option<String>,
+    /// Why the signal would have no effect yet, from [`SignalDisposition::warning`].
+    pub signal_warning: Option<String>,
+    /// Set when sending the signal failed. Always `None` on a dry run.
+    pub error: Option<String>,
+}
+
+/// Sends `options.signal` to every process matching `options`, or only lists
+/// them when `options.dry_run` is set. ProcMaster itself is never matched.
+pub fn killall(options: &KillallOptions) -> Result<Vec<KillallMatch>, String> {
+    let processes = all_processes().map_err(|err| format!("Failed to list processes: {}", err))?;
+    let ticks_per_second = ticks_per_second().map_err(|err| err.to_string())? as f64;
+    let system_uptime = Uptime::new().map_err(|err| err.to_string())?.uptime;
+    let own_pid = std::process::id() as i32;
+    let guard = Guard::load();
+    let mut matches = Vec::new();
+
+    for proc in processes.flatten() {
+        let Ok(stat) = proc.stat() else { continue };
+        if stat.pid == own_pid {
+            continue;
+        }
+        if options.state.is_some_and(|state| state != stat.state) {
+            continue;
+        }
+        let Ok(uid) = proc.uid() else { continue };
+        if options.user.is_some_and(|user| user != uid) {
+            continue;
+        }
+        if let Some(min_age) = options.min_age {
+            let age = system_uptime - stat.starttime as f64 / ticks_per_second;
+            if age < min_age as f64 {
+                continue;
+            }
+        }
+
+        let text = match options.field {
+            MatchField::Comm => stat.comm.clone(),
+            MatchField::Cmdline => proc.cmdline().map(|args| args.join(" ")).unwrap_or_default(),
+            MatchField::Exe => proc.exe().map(|path| path.to_string_lossy().into_owned()).unwrap_or_default(),
+        };
+        if !options.matcher.is_match(&text) {
+            continue;
+        }
+
+        let protected = match guard.check(stat.pid) {
+            Protection::Unprotected => None,
+            Protection::Confirm(reason) | Protection::Refuse(reason) => Some(reason),
+        };
+        let signal_warning = SignalDisposition::read(stat.pid).ok().and_then(|disposition| disposition.warning(options.signal));
+        let error = if options.dry_run {
+            None
+        } else {
+            guarded_signal(&guard, stat.pid, options.signal, options.bypass).err()
+        };
+        let user = get_user_by_uid(uid)
+            .map(|u| u.name().to_string_lossy().into_owned())
+            .unwrap_or_else(|| uid.to_string());
+        matches.push(KillallMatch {
+            pid: stat.pid,
+            starttime: stat.starttime,
+            user,
+            command: text,
+            state: stat.state,
+            protected,
+            signal_warning,
+            error,
+        });
+    }
+
+    Ok(matches)
+}
+
+/// Sends `signal` to exactly the processes of an earlier dry run, skipping any
+/// that exited or whose pid now belongs to a different process.
+pub fn killall_previewed(matches: &[KillallMatch], signal: Signal, bypass: Bypass) -> Vec<KillallMatch> {
+    let guard = Guard::load();
+    matches
+        .iter()
+        .map(|m| {
+            let unchanged = Process::new(m.pid)
+                .and_then(|proc| proc.stat())
+                .is_ok_and(|stat| stat.starttime == m.starttime);
+            let error = if unchanged {
+                guarded_signal(&guard, m.pid, signal, bypass).err()
+            } else {
+                Some(format!("Process {} exited since the preview", m.pid))
+            };
+            KillallMatch { error, ..m.clone() }
+        })
+        .collect()
+}
+
+/// How the user vouched for an action on a protected process.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bypass {
+    #[default]
+    None,
+    /// Confirmed a second time; enough in `confirm` protection mode.
+    Confirmed,
+    /// The explicit force key; overrides `refuse` mode as well.
+    Forced,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum Protection {
+    Unprotected,
+    /// Allowed with [`Bypass::Confirmed`]. Holds the reason.
+    Confirm(String),
+    /// Allowed only with [`Bypass::Forced`]. Holds the reason.
+    Refuse(String),
+}
+
+/// Decides which processes are too important to signal casually: init,
+/// ProcMaster and its parent shell, the session leader of this terminal, and
+/// whatever the `[protection]` config section adds.
+pub struct Guard {
+    mode: ProtectionMode,
+    pids: Vec<(i32, String)>,
+    patterns: Vec<glob::Pattern>,
+}
+
+impl Guard {
+    pub fn new(config: &ProtectionConfig) -> Self {
+        let mut pids = vec![
+            (1, "init".to_string()),
+            (std::process::id() as i32, "ProcMaster itself".to_string()),
+            (getppid().as_raw(), "ProcMaster's parent shell".to_string()),
+        ];
+        if let Ok(sid) = getsid(None) {
+            pids.push((sid.as_raw(), "session leader of this terminal".to_string()));
+        }
+        pids.extend(config.pids.iter().map(|&pid| (pid, "listed in the config".to_string())));
+        Guard {
+            mode: config.mode,
+            pids,
+            patterns: config.patterns.iter().filter_map(|pattern| glob::Pattern::new(pattern).ok()).collect(),
+        }
+    }
+
+    /// Builds a guard from the config file, falling back to the default protection
+    /// if the file is malformed so that a typo never switches the guard off.
+    pub fn load() -> Self {
+        Guard::new(&config::current().protection)
+    }
+
+    pub fn check(&self, pid: i32) -> Protection {
+        let reason = self.pids.iter()
+            .find(|(protected, _)| *protected == pid)
+            .map(|(_, reason)| reason.clone())
+            .or_else(|| {
+                let comm = procfs::process::Process::new(pid).and_then(|proc| proc.stat()).ok()?.comm;
+                self.patterns.iter()
+                    .find(|pattern| pattern.matches(&comm))
+                    .map(|pattern| format!("matches protected pattern {}", pattern))
+            });
+        match (reason, self.mode) {
+            (None, _) => Protection::Unprotected,
+            (Some(reason), ProtectionMode::Confirm) => Protection::Confirm(reason),
+            (Some(reason), ProtectionMode::Refuse) => Protection::Refuse(reason),
+        }
+    }
+
+    pub fn allow(&self, pid: i32, bypass: Bypass) -> Result<(), String> {
+        match (self.check(pid), bypass) {
+            (Protection::Unprotected, _) | (_, Bypass::Forced) | (Protection::Confirm(_), Bypass::Confirmed) => Ok(()),
+            (Protection::Confirm(reason), Bypass::None) => {
+                Err(format!("PID {} is protected ({}), confirm twice to proceed", pid, reason))
+            }
+            (Protection::Refuse(reason), _) => {
+                Err(format!("Refused: PID {} is protected ({}), force to override", pid, reason))
+            }
+        }
+    }
+}
+
+/// Errors from control operations that the UI may want to tell apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CtrlError {
+    /// The value is out of range for the operation.
+    InvalidArgument(&'static str),
+    /// EPERM: the target belongs to another user.
+    NotPermitted,
+    /// EACCES: the operation needs a capability this process lacks.
+    AccessDenied,
+    /// ESRCH: the target no longer exists.
+    NoSuchProcess,
+    /// `procmaster-helper` refused the request under its policy.
+    PolicyDenied,
+    Os(Errno),
+}
+
+impl fmt::Display for CtrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CtrlError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
+            CtrlError::NotPermitted => write!(f, "Operation not permitted (EPERM)"),
+            CtrlError::AccessDenied => write!(f, "Permission denied (EACCES)"),
+            CtrlError::NoSuchProcess => write!(f, "No such process (ESRCH)"),
+            CtrlError::PolicyDenied => write!(f, "Refused by the helper policy"),
+            CtrlError::Os(errno) => write!(f, "{} ({})", errno.desc(), errno),
+        }
+    }
+}
+
+impl std::error::Error for CtrlError {}
+
+impl From<Errno> for CtrlError {
+    fn from(errno: Errno) -> Self {
+        match errno {
+            Errno::EPERM => CtrlError::NotPermitted,
+            Errno::EACCES => CtrlError::AccessDenied,
+            Errno::ESRCH => CtrlError::NoSuchProcess,
+            Errno::EINVAL => CtrlError::InvalidArgument("rejected by the kernel"),
+            errno => CtrlError::Os(errno),
+        }
+    }
+}
+
+impl From<std::io::Error> for CtrlError {
+    fn from(err: std::io::Error) -> Self {
+        match err.raw_os_error() {
+            Some(raw) => Errno::from_raw(raw).into(),
+            None => CtrlError::Os(Errno::EIO),
+        }
+    }
+}
+
+impl From<procfs::ProcError> for CtrlError {
+    fn from(err: procfs::ProcError) -> Self {
+        match err {
+            procfs::ProcError::NotFound(_) => CtrlError::NoSuchProcess,
+            procfs::ProcError::PermissionDenied(_) => CtrlError::AccessDenied,
+            procfs::ProcError::Io(err, _) => CtrlError::Os(Errno::from_raw(err.raw_os_error().unwrap_or(libc::EIO))),
+            _ => CtrlError::Os(Errno::EIO),
+        }
+    }
+}
+
+/// What a nice value is applied to, mirroring `renice -p`, `-g` and `-u`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriorityTarget {
+    Process(i32),
+    ProcessGroup(i32),
+    User(u32),
+}
+
+impl PriorityTarget {
+    /// `process`, `pgrp` or `user`, the names the Tauri command takes.
+    pub fn kind(self) -> &'static str {
+        match self {
+            PriorityTarget::Process(_) => "process",
+            PriorityTarget::ProcessGroup(_) => "pgrp",
+            PriorityTarget::User(_) => "user",
+        }
+    }
+
+    fn which_who(self) -> (libc::c_int, libc::id_t) {
+        match self {
+            PriorityTarget::Process(pid) => (libc::PRIO_PROCESS as libc::c_int, pid as libc::id_t),
+            PriorityTarget::ProcessGroup(pgid) => (libc::PRIO_PGRP as libc::c_int, pgid as libc::id_t),
+            PriorityTarget::User(uid) => (libc::PRIO_USER as libc::c_int, uid as libc::id_t),
+        }
+    }
+}
+
+/// Returns the nice value of `target` (the lowest one for groups and users).
+pub fn get_priority(target: PriorityTarget) -> Result<i32, CtrlError> {
+    let (which, who) = target.which_who();
+    // -1 is a valid nice value, so errno has to be cleared and checked instead.
+    Errno::clear();
+    let nice = unsafe { libc::getpriority(which as _, who) };
+    if nice == -1 && Errno::last_raw() != 0 {
+        return Err(Errno::last().into());
     }
+    Ok(nice)
 }
-pub fn change_priority(pid: i32, priority: i32) -> bool {
-    if priority < -20 || priority > 19 {
+
+/// Sets the nice value of `target` with `setpriority(2)`.
+///
+/// Raising the nice value of your own processes always works. Lowering it needs
+/// CAP_SYS_NICE or a large enough RLIMIT_NICE and fails with
+/// [`CtrlError::AccessDenied`] otherwise, see [`may_lower_nice`].
+pub fn set_priority(target: PriorityTarget, nice: i32) -> Result<(), CtrlError> {
+    let (which, who) = target.which_who();
+    let pid = match target {
+        PriorityTarget::Process(pid) => Some(pid),
+        PriorityTarget::ProcessGroup(_) | PriorityTarget::User(_) => None,
+    };
+    audited("renice", pid, json!({ "target": target.kind(), "who": who, "nice": nice }), || {
+        if !(-20..=19).contains(&nice) {
+            return Err(CtrlError::InvalidArgument("nice must be between -20 and 19"));
+        }
+        if unsafe { libc::setpriority(which as _, who, nice) } == -1 {
+            return fallback(Errno::last().into(), HelperOp::Renice { target: target.kind().to_string(), who, nice });
+        }
+        Ok(())
+    })
+}
+
+const CAP_SYS_NICE: u32 = 23;
+const CAP_SYS_RESOURCE: u32 = 24;
+
+fn has_capability(capability: u32) -> bool {
+    let Ok(status) = procfs::process::Process::myself().and_then(|me| me.status()) else {
         return false;
+    };
+    status.capeff & (1 << capability) != 0
+}
+
+/// Whether ProcMaster holds CAP_SYS_NICE in its effective set.
+pub fn has_cap_sys_nice() -> bool {
+    has_capability(CAP_SYS_NICE)
+}
+
+/// Whether ProcMaster holds CAP_SYS_RESOURCE, which lowering `oom_score_adj` needs.
+pub fn has_cap_sys_resource() -> bool {
+    has_capability(CAP_SYS_RESOURCE)
+}
+
+/// Whether lowering a nice value down to `nice` is allowed, so the UI can say
+/// so before trying. Without CAP_SYS_NICE the floor is `20 - RLIMIT_NICE`.
+pub fn may_lower_nice(nice: i32) -> bool {
+    if has_cap_sys_nice() {
+        return true;
+    }
+    match getrlimit(Resource::RLIMIT_NICE) {
+        Ok((soft, _)) => i64::from(nice) >= 20 - soft.min(40) as i64,
+        Err(_) => false,
+    }
+}
+
+/// Number of CPUs configured on this machine, i.e. the width of an affinity mask.
+pub fn cpu_count() -> usize {
+    let count = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) };
+    if count < 1 { 1 } else { count as usize }
+}
+
+/// Returns the CPUs `pid` (a process or a thread id) may run on.
+pub fn get_affinity(pid: i32) -> Result<Vec<usize>, CtrlError> {
+    let set = sched_getaffinity(Pid::from_raw(pid))?;
+    Ok((0..cpu_count()).filter(|&cpu| set.is_set(cpu).unwrap_or(false)).collect())
+}
+
+/// Pins `pid` to `cpus`. With `all_threads` every thread in `/proc/<pid>/task`
+/// is pinned, otherwise only the thread whose id is `pid`.
+pub fn set_affinity(pid: i32, cpus: &[usize], all_threads: bool) -> Result<(), CtrlError> {
+    let args = json!({ "cpus": format_cpu_list(cpus), "all_threads": all_threads });
+    audited("affinity", Some(pid), args, || {
+        if cpus.is_empty() {
+            return Err(CtrlError::InvalidArgument("at least one CPU must be selected"));
+        }
+        let mut set = CpuSet::new();
+        for &cpu in cpus {
+            set.set(cpu).map_err(|_| CtrlError::InvalidArgument("CPU index out of range"))?;
+        }
+
+        for_each_thread(pid, all_threads, |tid| sched_setaffinity(Pid::from_raw(tid), &set))
+    })
+}
+
+/// Runs `apply` on `pid` alone, or on every thread in `/proc/<pid>/task`.
+fn for_each_thread(pid: i32, all_threads: bool, mut apply: impl FnMut(i32) -> Result<(), Errno>) -> Result<(), CtrlError> {
+    if !all_threads {
+        return Ok(apply(pid)?);
+    }
+    let tasks = procfs::process::Process::new(pid)?.tasks()?;
+    for task in tasks.flatten() {
+        match apply(task.tid) {
+            // The thread exited while we were walking the list.
+            Ok(()) | Err(Errno::ESRCH) => {}
+            Err(errno) => return Err(errno.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Formats CPUs the way `taskset -c` does, e.g. `0-3,6`.
+pub fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut iter = cpus.iter().copied().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end = iter.next().unwrap();
+        }
+        ranges.push(if start == end { start.to_string() } else { format!("{}-{}", start, end) });
+    }
+    ranges.join(",")
+}
+
+/// Parses a `taskset -c` style list such as `0-3,6` into CPU numbers.
+pub fn parse_cpu_list(text: &str) -> Result<Vec<usize>, String> {
+    let invalid = || format!("Invalid CPU list {}, expected e.g. 0-3,6", text);
+    let mut cpus = Vec::new();
+    for part in text.split(',') {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start, end),
+            None => (part, part),
+        };
+        let start: usize = start.trim().parse().map_err(|_| invalid())?;
+        let end: usize = end.trim().parse().map_err(|_| invalid())?;
+        if start > end || end >= cpu_count() {
+            return Err(format!("CPUs must be between 0 and {}", cpu_count() - 1));
+        }
+        cpus.extend(start..=end);
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(cpus)
+}
+
+/// I/O scheduling class, as used by `ionice`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum IoClass {
+    /// No class set; the kernel derives best-effort from the nice value.
+    None,
+    Realtime,
+    BestEffort,
+    Idle,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct IoPriority {
+    pub class: IoClass,
+    /// 0 (highest) to 7 (lowest). Ignored for the none and idle classes.
+    pub level: u8,
+}
+
+pub const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+
+impl IoPriority {
+    fn from_raw(raw: libc::c_int) -> Self {
+        let class = match (raw >> IOPRIO_CLASS_SHIFT) & 0x7 {
+            1 => IoClass::Realtime,
+            2 => IoClass::BestEffort,
+            3 => IoClass::Idle,
+            _ => IoClass::None,
+        };
+        // Newer kernels keep priority hints in the bits above the level.
+        IoPriority { class, level: (raw & 0x7) as u8 }
+    }
+
+    /// The value `ioprio_set(2)` takes. The kernel refuses the none class with
+    /// any level but 0, so the level only goes along for rt and be.
+    pub fn to_raw(self) -> libc::c_int {
+        let (class, level) = match self.class {
+            IoClass::None => (0, 0),
+            IoClass::Realtime => (1, self.level),
+            IoClass::BestEffort => (2, self.level),
+            IoClass::Idle => (3, 0),
+        };
+        (class << IOPRIO_CLASS_SHIFT) | libc::c_int::from(level)
+    }
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.class {
+            IoClass::None => write!(f, "none"),
+            IoClass::Realtime => write!(f, "rt/{}", self.level),
+            IoClass::BestEffort => write!(f, "be/{}", self.level),
+            IoClass::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+/// Parses `rt/0`, `be/4`, `idle` or `none`, the format [`IoPriority`] displays as.
+impl FromStr for IoPriority {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (class, level) = match text.split_once('/') {
+            Some((class, level)) => (class, Some(level)),
+            None => (text, None),
+        };
+        let class = match class {
+            "rt" | "realtime" => IoClass::Realtime,
+            "be" | "best-effort" => IoClass::BestEffort,
+            "idle" => IoClass::Idle,
+            "none" => IoClass::None,
+            _ => return Err(format!("Unknown I/O class {} (use rt, be, idle or none)", class)),
+        };
+        let level = match level {
+            Some(level) => match level.parse::<u8>() {
+                Ok(level) if level <= 7 => level,
+                _ => return Err(format!("Invalid I/O priority level {} (use 0 to 7)", level)),
+            },
+            None => 4,
+        };
+        let level = if matches!(class, IoClass::None | IoClass::Idle) { 0 } else { level };
+        Ok(IoPriority { class, level })
+    }
+}
+
+pub fn get_io_priority(pid: i32) -> Result<IoPriority, CtrlError> {
+    let raw = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, pid) };
+    if raw == -1 {
+        return Err(Errno::last().into());
+    }
+    Ok(IoPriority::from_raw(raw as libc::c_int))
+}
+
+/// Sets the I/O priority of `pid` with `ioprio_set(2)`. The realtime class
+/// needs CAP_SYS_ADMIN and fails with [`CtrlError::NotPermitted`] without it.
+pub fn set_io_priority(pid: i32, priority: IoPriority) -> Result<(), CtrlError> {
+    audited("ioprio", Some(pid), json!({ "priority": priority.to_string() }), || {
+        if priority.level > 7 {
+            return Err(CtrlError::InvalidArgument("I/O priority level must be between 0 and 7"));
+        }
+        let raw = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, pid, priority.to_raw()) };
+        if raw == -1 {
+            return fallback(Errno::last().into(), HelperOp::Ioprio { pid, priority: priority.to_string() });
+        }
+        Ok(())
+    })
+}
+
+/// Linux scheduling policies settable with `sched_setscheduler(2)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum SchedPolicy {
+    Other,
+    Batch,
+    Idle,
+    Fifo,
+    RoundRobin,
+}
+
+impl SchedPolicy {
+    pub const ALL: [SchedPolicy; 5] = [
+        SchedPolicy::Other,
+        SchedPolicy::Batch,
+        SchedPolicy::Idle,
+        SchedPolicy::Fifo,
+        SchedPolicy::RoundRobin,
+    ];
+
+    fn to_raw(self) -> libc::c_int {
+        match self {
+            SchedPolicy::Other => libc::SCHED_OTHER,
+            SchedPolicy::Batch => libc::SCHED_BATCH,
+            SchedPolicy::Idle => libc::SCHED_IDLE,
+            SchedPolicy::Fifo => libc::SCHED_FIFO,
+            SchedPolicy::RoundRobin => libc::SCHED_RR,
+        }
+    }
+
+    fn from_raw(raw: libc::c_int) -> Option<Self> {
+        // SCHED_RESET_ON_FORK is or-ed into the policy when set.
+        match raw & !libc::SCHED_RESET_ON_FORK {
+            libc::SCHED_OTHER => Some(SchedPolicy::Other),
+            libc::SCHED_BATCH => Some(SchedPolicy::Batch),
+            libc::SCHED_IDLE => Some(SchedPolicy::Idle),
+            libc::SCHED_FIFO => Some(SchedPolicy::Fifo),
+            libc::SCHED_RR => Some(SchedPolicy::RoundRobin),
+            _ => None,
+        }
+    }
+
+    pub fn is_realtime(self) -> bool {
+        matches!(self, SchedPolicy::Fifo | SchedPolicy::RoundRobin)
+    }
+
+    /// Valid static priorities for this policy: 1..=99 for real-time, 0 otherwise.
+    pub fn priority_range(self) -> Result<(i32, i32), CtrlError> {
+        let min = unsafe { libc::sched_get_priority_min(self.to_raw()) };
+        let max = unsafe { libc::sched_get_priority_max(self.to_raw()) };
+        if min == -1 || max == -1 {
+            return Err(Errno::last().into());
+        }
+        Ok((min, max))
+    }
+}
+
+impl fmt::Display for SchedPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SchedPolicy::Other => "other",
+            SchedPolicy::Batch => "batch",
+            SchedPolicy::Idle => "idle",
+            SchedPolicy::Fifo => "fifo",
+            SchedPolicy::RoundRobin => "rr",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for SchedPolicy {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        SchedPolicy::ALL
+            .into_iter()
+            .find(|policy| policy.to_string() == text.to_ascii_lowercase())
+            .ok_or_else(|| format!("Unknown scheduling policy {} (use other, batch, idle, fifo or rr)", text))
+    }
+}
+
+/// Returns the policy and static priority of `pid`.
+pub fn get_scheduler(pid: i32) -> Result<(SchedPolicy, i32), CtrlError> {
+    let raw = unsafe { libc::sched_getscheduler(pid) };
+    if raw == -1 {
+        return Err(Errno::last().into());
+    }
+    let policy = SchedPolicy::from_raw(raw).ok_or(CtrlError::Os(Errno::EINVAL))?;
+    let mut param = libc::sched_param { sched_priority: 0 };
+    if unsafe { libc::sched_getparam(pid, &mut param) } == -1 {
+        return Err(Errno::last().into());
     }
+    Ok((policy, param.sched_priority))
+}
+
+/// Switches `pid` (or all of its threads) to `policy` with `sched_setscheduler(2)`.
+///
+/// `priority` must lie in [`SchedPolicy::priority_range`]. Real-time policies need
+/// CAP_SYS_NICE or RLIMIT_RTPRIO and fail with [`CtrlError::NotPermitted`] otherwise.
+pub fn set_scheduler(pid: i32, policy: SchedPolicy, priority: i32, all_threads: bool) -> Result<(), CtrlError> {
+    let args = json!({ "policy": policy.to_string(), "priority": priority, "all_threads": all_threads });
+    audited("scheduler", Some(pid), args, || {
+        let (min, max) = policy.priority_range()?;
+        if priority < min || priority > max {
+            return Err(CtrlError::InvalidArgument(if policy.is_realtime() {
+                "real-time priority is out of range for this policy"
+            } else {
+                "non real-time policies take priority 0"
+            }));
+        }
+        let param = libc::sched_param { sched_priority: priority };
+        for_each_thread(pid, all_threads, |tid| {
+            if unsafe { libc::sched_setscheduler(tid, policy.to_raw(), &param) } == -1 {
+                return Err(Errno::last());
+            }
+            Ok(())
+        })
+        .or_else(|err| {
+            fallback(err, HelperOp::Scheduler { pid, policy: policy.to_string(), priority, all_threads })
+        })
+    })
+}
 
-    let output;
-    output = Command::new("sudo")
-        .arg("renice")
-        .arg(format!("{}", priority))
-        .arg(format!("{}", pid))
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .output()
-        .expect("Failed to change priority");         
-    output.status.success()
-}
\ No newline at end of file
+pub fn get_oom_score_adj(pid: i32) -> Result<i32, CtrlError> {
+    let text = fs::read_to_string(format!("/proc/{}/oom_score_adj", pid))?;
+    text.trim().parse().map_err(|_| CtrlError::Os(Errno::EIO))
+}
+
+/// Writes `/proc/<pid>/oom_score_adj`. -1000 exempts the process from the OOM
+/// killer, 1000 makes it the first victim. Lowering the value needs
+/// CAP_SYS_RESOURCE and fails with [`CtrlError::AccessDenied`] without it.
+pub fn set_oom_score_adj(pid: i32, value: i32) -> Result<(), CtrlError> {
+    audited("oom_score_adj", Some(pid), json!({ "value": value }), || {
+        if !(-1000..=1000).contains(&value) {
+            return Err(CtrlError::InvalidArgument("oom_score_adj must be between -1000 and 1000"));
+        }
+        fs::write(format!("/proc/{}/oom_score_adj", pid), value.to_string())
+            .map_err(CtrlError::from)
+            .or_else(|err| fallback(err, HelperOp::OomScoreAdj { pid, value }))
+    })
+}
+
+/// Sets `limit` on `cgroup`. `pid` is the process the change was made for and
+/// only identifies it in the audit log.
+pub fn set_cgroup_limit(pid: i32, cgroup: &str, limit: CgroupLimit, value: &str) -> Result<(), CtrlError> {
+    let args = json!({ "cgroup": cgroup, "limit": limit.file_name(), "value": value.trim() });
+    audited("cgroup_limit", Some(pid), args, || {
+        CgroupFs::system().set_limit(cgroup, limit, value).or_else(|err| {
+            let op = HelperOp::CgroupLimit {
+                pid,
+                cgroup: cgroup.to_string(),
+                limit: limit.file_name().to_string(),
+                value: value.trim().to_string(),
+            };
+            fallback(err, op)
+        })
+    })
+}
+
+/// Moves `pid` into `cgroup`, creating the cgroup first if needed.
+pub fn move_to_cgroup(pid: i32, cgroup: &str) -> Result<(), CtrlError> {
+    audited("cgroup_move", Some(pid), json!({ "cgroup": cgroup }), || {
+        let cgroups = CgroupFs::system();
+        cgroups.create(cgroup)
+            .and_then(|()| cgroups.move_pid(pid, cgroup))
+            .or_else(|err| fallback(err, HelperOp::CgroupMove { pid, cgroup: cgroup.to_string() }))
+    })
+}
@@ -0,0 +1,127 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use nix::errno::Errno;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::ctrl::{Bypass, CtrlError};
+
+/// Bumped whenever a request or response changes shape. The helper refuses
+/// requests with any other version.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Set by `procmaster-helper` itself, so that an operation failing inside the
+/// helper is not handed back to the helper.
+pub static SERVING: AtomicBool = AtomicBool::new(false);
+
+/// One privileged operation. Each maps onto the `ctrl` function of the same
+/// name and carries its arguments.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum HelperOp {
+    /// `target` is `process`, `pgrp` or `user`, as [`crate::ctrl::PriorityTarget::kind`].
+    Renice { target: String, who: u32, nice: i32 },
+    Signal { pid: i32, signal: i32, bypass: Bypass },
+    /// `priority` in the `be/4` form [`crate::ctrl::IoPriority`] parses.
+    Ioprio { pid: i32, priority: String },
+    /// `policy` in the form [`crate::ctrl::SchedPolicy`] parses.
+    Scheduler { pid: i32, policy: String, priority: i32, all_threads: bool },
+    OomScoreAdj { pid: i32, value: i32 },
+    CgroupLimit { pid: i32, cgroup: String, limit: String, value: String },
+    CgroupMove { pid: i32, cgroup: String },
+    Freeze { pid: i32 },
+    Thaw { pid: i32 },
+}
+
+impl HelperOp {
+    /// The process the operation acts on, if it acts on a single one.
+    pub fn pid(&self) -> Option<i32> {
+        match self {
+            HelperOp::Renice { target, who, .. } if target == "process" => Some(*who as i32),
+            HelperOp::Renice { .. } => None,
+            HelperOp::Signal { pid, .. }
+            | HelperOp::Ioprio { pid, .. }
+            | HelperOp::Scheduler { pid, .. }
+            | HelperOp::OomScoreAdj { pid, .. }
+            | HelperOp::CgroupLimit { pid, .. }
+            | HelperOp::CgroupMove { pid, .. }
+            | HelperOp::Freeze { pid }
+            | HelperOp::Thaw { pid } => Some(*pid),
+        }
+    }
+}
+
+/// One line of JSON sent to the helper.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HelperRequest {
+    pub version: u32,
+    /// Start time of [`HelperOp::pid`] in clock ticks. The helper acts only if
+    /// the PID still belongs to that process, so a reused PID is never hit.
+    pub starttime: Option<u64>,
+    #[serde(flatten)]
+    pub op: HelperOp,
+}
+
+/// One line of JSON the helper answers with.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HelperResponse {
+    pub ok: bool,
+    pub error: Option<String>,
+    /// Set when the operation itself failed, so the client can report the same
+    /// [`CtrlError`] it would have got running it directly.
+    pub errno: Option<i32>,
+}
+
+/// Sends `op` to the helper and waits for its answer.
+pub fn request(socket: &Path, op: HelperOp) -> Result<(), CtrlError> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let starttime = match op.pid() {
+        Some(pid) => Some(
+            procfs::process::Process::new(pid)
+                .and_then(|proc| proc.stat())
+                .map_err(|_| CtrlError::NoSuchProcess)?
+                .starttime,
+        ),
+        None => None,
+    };
+    let mut line = serde_json::to_string(&HelperRequest { version: PROTOCOL_VERSION, starttime, op })
+        .map_err(|_| CtrlError::Os(Errno::EINVAL))?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer)?;
+    let response: HelperResponse = serde_json::from_str(&answer).map_err(|_| CtrlError::Os(Errno::EPROTO))?;
+    match (response.ok, response.errno) {
+        (true, _) => Ok(()),
+        (false, Some(errno)) => Err(Errno::from_raw(errno).into()),
+        (false, None) => Err(CtrlError::PolicyDenied),
+    }
+}
+
+/// Whether the helper is enabled and its socket is there, i.e. whether
+/// operations ProcMaster lacks the privilege for can still be attempted.
+pub fn available() -> bool {
+    let helper = &config::current().helper;
+    helper.enabled && helper.socket_path().exists()
+}
+
+/// What `ctrl` does when an operation fails for lack of privilege: if the
+/// helper is enabled and running, it asks the helper to do it instead and
+/// returns the helper's result. Any other error is returned unchanged.
+pub fn fallback(err: CtrlError, op: HelperOp) -> Result<(), CtrlError> {
+    if !matches!(err, CtrlError::NotPermitted | CtrlError::AccessDenied) || SERVING.load(Ordering::Relaxed) {
+        return Err(err);
+    }
+    let helper = &config::current().helper;
+    let socket = helper.socket_path();
+    if !helper.enabled || !socket.exists() {
+        return Err(err);
+    }
+    request(&socket, op)
+}
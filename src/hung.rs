@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use procfs::process::{all_processes, MountInfo};
+use serde::Serialize;
+
+use crate::config::{self, HungTaskConfig};
+use crate::ctrl;
+use crate::schedule::format_seconds;
+use crate::syscalls;
+
+/// Shortest time between two scans of `/proc`.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+/// Threshold when the config does not set one.
+const DEFAULT_THRESHOLD: Duration = Duration::from_secs(30);
+/// Filesystems whose server going away leaves tasks in D state.
+const NETWORK_FILESYSTEMS: [&str; 8] = ["nfs", "nfs4", "cifs", "smb3", "ceph", "9p", "glusterfs", "fuse.sshfs"];
+
+/// A thread that has been in uninterruptible sleep for longer than the threshold.
+#[derive(Clone, Debug, Serialize)]
+pub struct HungTask {
+    pub pid: i32,
+    pub tid: i32,
+    /// Name of the thread, which is the process's for its main thread.
+    pub command: String,
+    /// How long it has been in D state without a break, as far as ProcMaster has seen.
+    pub seconds: u64,
+    /// Kernel function it sleeps in; empty if unreadable.
+    pub wchan: String,
+    pub syscall: Option<String>,
+    /// The file, device or filesystem it most likely waits for, e.g.
+    /// `fd 3 /mnt/data/db on nfs4 server:/export at /mnt/data`.
+    pub blocked_on: Option<String>,
+}
+
+impl HungTask {
+    /// One-line warning, like `1234/1240 (tar) in D for 2m 5s at nfs_wait_bit_killable (read), fd 3 ...`.
+    pub fn describe(&self) -> String {
+        let id = if self.pid == self.tid { self.pid.to_string() } else { format!("{}/{}", self.pid, self.tid) };
+        let mut text = format!("{} ({}) in D for {}", id, self.command, format_seconds(self.seconds));
+        if !self.wchan.is_empty() {
+            text.push_str(&format!(" at {}", self.wchan));
+        }
+        if let Some(syscall) = &self.syscall {
+            text.push_str(&format!(" ({})", syscall));
+        }
+        if let Some(blocked_on) = &self.blocked_on {
+            text.push_str(&format!(", {}", blocked_on));
+        }
+        text
+    }
+}
+
+/// Remembers how long each thread has been in uninterruptible sleep, so the
+/// ones stuck there, typically on a hung NFS server or a dying disk, can be
+/// told apart from the ones passing through. [`HungTasks::update`] has to be
+/// called regularly.
+pub struct HungTasks {
+    threshold: Duration,
+    last_scan: Option<Instant>,
+    /// When each thread in D state, by TID and start time, entered it.
+    since: HashMap<(i32, u64), Instant>,
+    hung: Vec<HungTask>,
+}
+
+impl Default for HungTasks {
+    fn default() -> Self {
+        HungTasks { threshold: DEFAULT_THRESHOLD, last_scan: None, since: HashMap::new(), hung: Vec::new() }
+    }
+}
+
+impl HungTasks {
+    pub fn new(config: &HungTaskConfig) -> Result<Self, String> {
+        let threshold = match &config.threshold {
+            Some(threshold) => Duration::from_secs(ctrl::parse_age(threshold).map_err(|err| format!("hung_tasks.threshold: {}", err))?),
+            None => DEFAULT_THRESHOLD,
+        };
+        Ok(HungTasks { threshold, ..HungTasks::default() })
+    }
+
+    /// Reads the threshold from the config file, which also refreshes
+    /// [`config::current`].
+    pub fn load() -> Result<Self, String> {
+        HungTasks::new(&config::reload()?.hung_tasks)
+    }
+
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Rescans every thread, unless the last scan was less than a second ago.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        if self.last_scan.is_some_and(|scan| now.duration_since(scan) < SCAN_INTERVAL) {
+            return;
+        }
+        self.last_scan = Some(now);
+        let Ok(processes) = all_processes() else { return };
+
+        let mut since = HashMap::new();
+        let mut hung = Vec::new();
+        for process in processes.flatten() {
+            let Ok(tasks) = process.tasks() else { continue };
+            for task in tasks.flatten() {
+                let Ok(stat) = task.stat() else { continue };
+                if stat.state != 'D' {
+                    continue;
+                }
+                let key = (task.tid, stat.starttime);
+                let entered = self.since.get(&key).copied().unwrap_or(now);
+                since.insert(key, entered);
+                if now.duration_since(entered) >= self.threshold {
+                    hung.push(inspect(task.pid, task.tid, stat.comm, now.duration_since(entered)));
+                }
+            }
+        }
+        self.since = since;
+        hung.sort_by(|a, b| b.seconds.cmp(&a.seconds).then(a.tid.cmp(&b.tid)));
+        self.hung = hung;
+    }
+
+    /// Threads in D state for longer than the threshold, longest first.
+    pub fn hung(&self) -> &[HungTask] {
+        &self.hung
+    }
+
+    /// The longest-hung thread of process `pid`, if any.
+    pub fn of(&self, pid: i32) -> Option<&HungTask> {
+        self.hung.iter().find(|task| task.pid == pid)
+    }
+}
+
+fn inspect(pid: i32, tid: i32, command: String, duration: Duration) -> HungTask {
+    let dir = format!("/proc/{}/task/{}", pid, tid);
+    let wchan = fs::read_to_string(format!("{}/wchan", dir))
+        .map(|wchan| wchan.trim().to_string())
+        .ok()
+        .filter(|wchan| wchan != "0")
+        .unwrap_or_default();
+    let (syscall, fd) = fs::read_to_string(format!("{}/syscall", dir)).ok().as_deref().map_or((None, None), current_syscall);
+    HungTask {
+        pid,
+        tid,
+        command,
+        seconds: duration.as_secs(),
+        wchan,
+        syscall,
+        blocked_on: blocked_on(pid, fd),
+    }
+}
+
+/// Name of the syscall in a `syscall` file (`NR ARG1 ... ARG6 SP PC`), and its
+/// first argument when that is a file descriptor.
+fn current_syscall(text: &str) -> (Option<String>, Option<i32>) {
+    let mut fields = text.split_whitespace();
+    let Some(nr) = fields.next().and_then(|nr| nr.parse::<u64>().ok()) else { return (None, None) };
+    let Some(name) = syscalls::native_name(nr) else { return (Some(format!("syscall_{}", nr)), None) };
+    let fd = syscalls::arguments(name)
+        .filter(|kinds| kinds.starts_with('f'))
+        .and_then(|_| fields.next())
+        .and_then(|arg| u64::from_str_radix(arg.trim_start_matches("0x"), 16).ok())
+        .and_then(|arg| i32::try_from(arg).ok());
+    (Some(name.to_string()), fd)
+}
+
+/// What `pid` is most likely blocked on: the file behind the fd of the current
+/// syscall, else an open file on a network filesystem, else the filesystem of
+/// its working directory.
+fn blocked_on(pid: i32, fd: Option<i32>) -> Option<String> {
+    let process = procfs::process::Process::new(pid).ok()?;
+    let mounts = process.mountinfo().unwrap_or_default();
+    if let Some(fd) = fd {
+        if let Ok(target) = fs::read_link(format!("/proc/{}/fd/{}", pid, fd)) {
+            return Some(format!("fd {} {}", fd, describe_path(&target, &mounts)));
+        }
+    }
+    let open_files = fs::read_dir(format!("/proc/{}/fd", pid)).into_iter().flatten().flatten();
+    for entry in open_files {
+        let Ok(target) = fs::read_link(entry.path()) else { continue };
+        if mount_of(&target, &mounts).is_some_and(|mount| NETWORK_FILESYSTEMS.contains(&mount.fs_type.as_str())) {
+            return Some(format!("open file {}", describe_path(&target, &mounts)));
+        }
+    }
+    let cwd = process.cwd().ok()?;
+    Some(format!("cwd {}", describe_path(&cwd, &mounts)))
+}
+
+/// `path on FSTYPE SOURCE at MOUNTPOINT`, or just the path when it is not on a
+/// mounted filesystem, like `socket:[1234]`.
+fn describe_path(path: &Path, mounts: &[MountInfo]) -> String {
+    match mount_of(path, mounts) {
+        Some(mount) => format!(
+            "{} on {} {} at {}",
+            path.display(),
+            mount.fs_type,
+            mount.mount_source.as_deref().unwrap_or("?"),
+            mount.mount_point.display()
+        ),
+        None => path.display().to_string(),
+    }
+}
+
+/// The mount `path` is on: the deepest mount point containing it, and of
+/// those the last one mounted, which hides the others.
+fn mount_of<'a>(path: &Path, mounts: &'a [MountInfo]) -> Option<&'a MountInfo> {
+    if !path.is_absolute() {
+        return None;
+    }
+    mounts.iter()
+        .filter(|mount| path.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.components().count())
+}
@@ -0,0 +1,367 @@
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Read};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use nix::sched::{sched_setaffinity, CpuSet};
+use nix::sys::resource::{setrlimit, Resource};
+use nix::sys::signal::Signal;
+use nix::unistd::Pid;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::audit::audited;
+use crate::cgroup::CgroupFs;
+use crate::ctrl::{parse_cpu_list, IoPriority, IOPRIO_WHO_PROCESS};
+use crate::schedule::now;
+
+/// How many lines of output are kept per launched process.
+const OUTPUT_LINES: usize = 2000;
+
+/// Resource limits `-l` accepts, by their `prlimit` names.
+const RLIMITS: [(&str, Resource); 8] = [
+    ("as", Resource::RLIMIT_AS),
+    ("core", Resource::RLIMIT_CORE),
+    ("cpu", Resource::RLIMIT_CPU),
+    ("fsize", Resource::RLIMIT_FSIZE),
+    ("memlock", Resource::RLIMIT_MEMLOCK),
+    ("nofile", Resource::RLIMIT_NOFILE),
+    ("nproc", Resource::RLIMIT_NPROC),
+    ("stack", Resource::RLIMIT_STACK),
+];
+
+/// One resource limit to set on a launched process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rlimit {
+    pub name: &'static str,
+    resource: Resource,
+    pub soft: u64,
+    pub hard: u64,
+}
+
+/// Parses `nofile=1024` (soft and hard) or `nofile=1024:4096`; `unlimited`
+/// stands for no limit.
+impl FromStr for Rlimit {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (name, value) = text.split_once('=').ok_or_else(|| format!("Invalid limit {}, expected NAME=SOFT[:HARD]", text))?;
+        let (name, resource) = RLIMITS.iter()
+            .find(|(known, _)| *known == name)
+            .copied()
+            .ok_or_else(|| format!("Unknown limit {} (use {})", name, RLIMITS.map(|(name, _)| name).join(", ")))?;
+        let number = |text: &str| match text {
+            "unlimited" | "infinity" => Ok(libc::RLIM_INFINITY),
+            _ => text.parse().map_err(|_| format!("Invalid limit value {}", text)),
+        };
+        let (soft, hard) = match value.split_once(':') {
+            Some((soft, hard)) => (number(soft)?, number(hard)?),
+            None => (number(value)?, number(value)?),
+        };
+        if soft > hard {
+            return Err(format!("Soft limit of {} is above its hard limit", name));
+        }
+        Ok(Rlimit { name, resource, soft, hard })
+    }
+}
+
+/// What to run and the settings it starts with. Everything is applied in the
+/// child between fork and exec, so the command never runs without them.
+#[derive(Clone, Debug, Default)]
+pub struct LaunchSpec {
+    /// Run with `sh -c`, so it may use quoting, pipes and redirects.
+    pub command: String,
+    pub cwd: Option<PathBuf>,
+    /// Set on top of ProcMaster's own environment.
+    pub env: Vec<(String, String)>,
+    pub nice: Option<i32>,
+    pub io_priority: Option<IoPriority>,
+    pub cpus: Option<Vec<usize>>,
+    pub rlimits: Vec<Rlimit>,
+    /// Created if missing; the process starts inside it.
+    pub cgroup: Option<String>,
+}
+
+/// Parses a run spec, e.g. `-d /tmp -e RUST_LOG=debug -n 10 -c 0-1 ./bench --fast`.
+///
+/// `-d` working directory (a leading `~/` is `$HOME`), `-e` environment variable (repeatable), `-n` nice,
+/// `-i` I/O priority, `-c` CPU list, `-l` resource limit (repeatable) and `-g`
+/// cgroup. Everything after the options (or after `--`) is the command.
+impl FromStr for LaunchSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut launch = LaunchSpec::default();
+        let mut rest = spec.trim();
+        while rest.starts_with('-') {
+            let (flag, after) = split_word(rest);
+            if flag == "--" {
+                rest = after;
+                break;
+            }
+            let (value, after) = split_word(after);
+            if value.is_empty() {
+                return Err(format!("{} needs a value", flag));
+            }
+            match flag {
+                "-d" => launch.cwd = Some(expand_home(value)),
+                "-e" => {
+                    let (key, value) = value.split_once('=').ok_or_else(|| format!("Invalid variable {}, expected KEY=VALUE", value))?;
+                    launch.env.push((key.to_string(), value.to_string()));
+                }
+                "-n" => match value.parse::<i32>() {
+                    Ok(nice) if (-20..=19).contains(&nice) => launch.nice = Some(nice),
+                    _ => return Err(format!("Invalid nice value {} (use -20 to 19)", value)),
+                },
+                "-i" => launch.io_priority = Some(value.parse()?),
+                "-c" => launch.cpus = Some(parse_cpu_list(value)?),
+                "-l" => launch.rlimits.push(value.parse()?),
+                "-g" => launch.cgroup = Some(value.to_string()),
+                _ => return Err(format!("Unknown option {}", flag)),
+            }
+            rest = after;
+        }
+        if rest.is_empty() {
+            return Err("No command given".to_string());
+        }
+        launch.command = rest.to_string();
+        Ok(launch)
+    }
+}
+
+/// Replaces a leading `~` with `$HOME`, as a shell would; there is no shell
+/// between the spec and the working directory.
+fn expand_home(path: &str) -> PathBuf {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    match (path.strip_prefix('~'), home) {
+        (Some(""), Some(home)) => home,
+        (Some(rest), Some(home)) if rest.starts_with('/') => home.join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Splits off the first whitespace-separated word.
+fn split_word(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    match text.find(char::is_whitespace) {
+        Some(end) => (&text[..end], text[end..].trim_start()),
+        None => (text, ""),
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OutputLine {
+    pub stderr: bool,
+    pub text: String,
+}
+
+/// A process ProcMaster started, with its captured output.
+pub struct Launched {
+    pub pid: i32,
+    pub command: String,
+    /// Unix time in seconds.
+    pub started: i64,
+    output: Arc<Mutex<VecDeque<OutputLine>>>,
+    status: Arc<Mutex<Option<ExitStatus>>>,
+}
+
+impl Launched {
+    /// The last [`OUTPUT_LINES`] lines of stdout and stderr, oldest first.
+    pub fn output(&self) -> Vec<OutputLine> {
+        self.output.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status.lock().unwrap().is_none()
+    }
+
+    /// How it ended, once it has.
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        *self.status.lock().unwrap()
+    }
+
+    /// `running`, `exited 0` or `killed by SIGTERM`.
+    pub fn status(&self) -> String {
+        self.exit_status().map_or("running".to_string(), describe_exit)
+    }
+}
+
+/// `exited 0` or `killed by SIGTERM`.
+pub fn describe_exit(status: ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("exited {}", code),
+        (None, Some(signal)) => match Signal::try_from(signal) {
+            Ok(signal) => format!("killed by {}", signal),
+            Err(_) => format!("killed by signal {}", signal),
+        },
+        (None, None) => "exited".to_string(),
+    }
+}
+
+/// The processes started from ProcMaster. ProcMaster does not stop them when
+/// it exits, but their stdout and stderr are pipes it reads, so one still
+/// running gets SIGPIPE (or EPIPE) on its next write and usually dies of it.
+/// Only a command that writes nothing, or redirects its own output (e.g.
+/// `./bench > bench.log 2>&1`), outlives ProcMaster.
+#[derive(Default)]
+pub struct Launcher {
+    launched: Vec<Launched>,
+}
+
+impl Launcher {
+    /// Starts `spec` and returns the new PID.
+    pub fn launch(&mut self, spec: &LaunchSpec) -> Result<i32, String> {
+        let args = json!({
+            "command": spec.command,
+            "cwd": spec.cwd,
+            "env": spec.env.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>(),
+            "nice": spec.nice,
+            "ioprio": spec.io_priority.map(|priority| priority.to_string()),
+            "cpus": spec.cpus,
+            "rlimits": spec.rlimits.iter().map(|limit| format!("{}={}:{}", limit.name, limit.soft, limit.hard)).collect::<Vec<_>>(),
+            "cgroup": spec.cgroup,
+        });
+        let mut child = match spawn(spec) {
+            Ok(child) => child,
+            Err(err) => {
+                let _ = audited("launch", None, args, || Err::<(), String>(err.clone()));
+                return Err(err);
+            }
+        };
+        let pid = child.id() as i32;
+        let _ = audited("launch", Some(pid), args, || Ok::<(), String>(()));
+
+        let output = Arc::new(Mutex::new(VecDeque::new()));
+        if let Some(stdout) = child.stdout.take() {
+            capture(stdout, false, Arc::clone(&output));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            capture(stderr, true, Arc::clone(&output));
+        }
+        let status = Arc::new(Mutex::new(None));
+        {
+            let status = Arc::clone(&status);
+            thread::spawn(move || {
+                if let Ok(exit) = child.wait() {
+                    *status.lock().unwrap() = Some(exit);
+                }
+            });
+        }
+        self.launched.push(Launched { pid, command: spec.command.clone(), started: now(), output, status });
+        Ok(pid)
+    }
+
+    /// Everything launched so far, oldest first, finished ones included.
+    pub fn list(&self) -> &[Launched] {
+        &self.launched
+    }
+
+    pub fn get(&self, pid: i32) -> Option<&Launched> {
+        self.launched.iter().find(|launched| launched.pid == pid)
+    }
+
+    /// Whether `pid` is a running process ProcMaster started.
+    pub fn is_launched(&self, pid: i32) -> bool {
+        self.get(pid).is_some_and(Launched::is_running)
+    }
+
+    /// Forgets the ones that have exited.
+    pub fn clear_finished(&mut self) {
+        self.launched.retain(Launched::is_running);
+    }
+}
+
+fn spawn(spec: &LaunchSpec) -> Result<std::process::Child, String> {
+    // Anything that allocates or may fail with a useful message is prepared
+    // here; the pre_exec hook below only makes system calls.
+    let cgroup_procs: Option<OwnedFd> = match &spec.cgroup {
+        Some(cgroup) => {
+            let cgroups = CgroupFs::system();
+            cgroups.create(cgroup).map_err(|err| format!("Failed to create cgroup {}: {}", cgroup, err))?;
+            let dir = cgroups.path(cgroup).map_err(|err| format!("Failed to open cgroup {}: {}", cgroup, err))?;
+            let file = OpenOptions::new()
+                .write(true)
+                .open(dir.join("cgroup.procs"))
+                .map_err(|err| format!("Failed to open cgroup {}: {}", cgroup, err))?;
+            Some(file.into())
+        }
+        None => None,
+    };
+    let cpus = match &spec.cpus {
+        Some(cpus) => {
+            let mut set = CpuSet::new();
+            for &cpu in cpus {
+                set.set(cpu).map_err(|err| format!("Invalid CPU {}: {}", cpu, err))?;
+            }
+            Some(set)
+        }
+        None => None,
+    };
+    let nice = spec.nice;
+    let io_priority = spec.io_priority.map(IoPriority::to_raw);
+    let rlimits = spec.rlimits.clone();
+
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(&spec.command)
+        .envs(spec.env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = &spec.cwd {
+        command.current_dir(cwd);
+    }
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(fd) = &cgroup_procs {
+                // "0" moves the writing process, i.e. this child.
+                if libc::write(fd.as_raw_fd(), b"0".as_ptr().cast(), 1) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            for limit in &rlimits {
+                setrlimit(limit.resource, limit.soft, limit.hard)?;
+            }
+            if let Some(cpus) = &cpus {
+                sched_setaffinity(Pid::from_raw(0), cpus)?;
+            }
+            if let Some(raw) = io_priority {
+                if libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, raw) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            if let Some(nice) = nice {
+                if libc::setpriority(libc::PRIO_PROCESS as _, 0, nice) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    command.spawn().map_err(|err| format!("Failed to start {}: {}", spec.command, err))
+}
+
+/// Reads `stream` line by line into `output` until it closes, keeping the
+/// last [`OUTPUT_LINES`].
+fn capture(stream: impl Read + Send + 'static, stderr: bool, output: Arc<Mutex<VecDeque<OutputLine>>>) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).is_ok_and(|read| read > 0) {
+            let text = String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']).to_string();
+            let mut output = output.lock().unwrap();
+            if output.len() == OUTPUT_LINES {
+                output.pop_front();
+            }
+            output.push_back(OutputLine { stderr, text });
+            line.clear();
+        }
+    });
+}
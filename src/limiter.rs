@@ -0,0 +1,286 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use procfs::process::all_processes;
+use procfs::ticks_per_second;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::audit::audited;
+use crate::ctrl::{set_stopped, Bypass, Guard, Protection};
+use crate::suspended::{self, SuspendMethod};
+
+/// Length of one run/stop cycle.
+const PERIOD: Duration = Duration::from_millis(100);
+/// How many cycles pass between rescans for new children.
+const RESCAN_CYCLES: u32 = 10;
+/// Weight of the newest sample in the smoothed CPU usage.
+const ALPHA: f64 = 0.2;
+
+/// Set when ProcMaster is being killed, to end every limiter at once.
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+/// Limiter threads that have not yet continued their processes.
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+/// Ends every limiter and waits, for up to a second, until each one has
+/// continued its processes. For a termination signal, when there is no time to
+/// drop [`Limiters`] the normal way.
+pub fn shutdown() {
+    SHUTDOWN.store(true, Ordering::Relaxed);
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while RUNNING.load(Ordering::Relaxed) > 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// What the UI shows about one active limiter.
+#[derive(Clone, Debug, Serialize)]
+pub struct LimiterInfo {
+    pub id: u64,
+    pub pid: i32,
+    pub command: String,
+    /// Target in percent of one CPU, like `cpulimit -l`.
+    pub target: f64,
+    pub children: bool,
+    /// Smoothed measured usage in percent of one CPU.
+    pub usage: f64,
+    /// Share of each cycle the processes are currently allowed to run, 0 to 1.
+    pub duty: f64,
+    /// Number of processes being limited, the target plus any children.
+    pub processes: usize,
+}
+
+struct Limiter {
+    info: Arc<Mutex<LimiterInfo>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Limiter {
+    /// Stops the thread and waits for it, which ends with a SIGCONT to every
+    /// process it was limiting.
+    fn finish(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// CPU limiters for hosts where the cgroup cpu controller is not delegated: each
+/// one keeps a process under a CPU share by stopping and continuing it on a
+/// duty cycle. Dropping this stops them all, so nothing is left stopped when
+/// ProcMaster exits.
+#[derive(Default)]
+pub struct Limiters {
+    next_id: u64,
+    active: Vec<Limiter>,
+}
+
+impl Limiters {
+    /// Starts limiting `pid` (and its descendants with `children`) to `target`
+    /// percent of one CPU.
+    pub fn start(&mut self, pid: i32, target: f64, children: bool, bypass: Bypass) -> Result<u64, String> {
+        if !(target > 0.0 && target <= 100.0 * num_cpus() as f64) {
+            return Err(format!("CPU limit must be between 0 and {}%", 100 * num_cpus()));
+        }
+        self.active.retain(|limiter| limiter.thread.as_ref().is_some_and(|thread| !thread.is_finished()));
+        if self.active.iter().any(|limiter| limiter.info.lock().unwrap().pid == pid) {
+            return Err(format!("PID {} is already limited", pid));
+        }
+        let stat = procfs::process::Process::new(pid)
+            .and_then(|proc| proc.stat())
+            .map_err(|err| format!("Failed to read process {}: {}", pid, err))?;
+        audited("cpu_limit", Some(pid), json!({ "percent": target, "children": children }), || {
+            Guard::load().allow(pid, bypass)
+        })?;
+
+        self.next_id += 1;
+        let info = Arc::new(Mutex::new(LimiterInfo {
+            id: self.next_id,
+            pid,
+            command: stat.comm,
+            target,
+            children,
+            usage: 0.0,
+            duty: 1.0,
+            processes: 1,
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+        RUNNING.fetch_add(1, Ordering::Relaxed);
+        let thread = {
+            let info = Arc::clone(&info);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || duty_cycle(pid, stat.starttime, target / 100.0, children, &stop, &info))
+        };
+        self.active.push(Limiter { info, stop, thread: Some(thread) });
+        Ok(self.next_id)
+    }
+
+    /// Removes a limiter, leaving its processes running.
+    pub fn remove(&mut self, id: u64) -> bool {
+        let Some(index) = self.active.iter().position(|limiter| limiter.info.lock().unwrap().id == id) else {
+            return false;
+        };
+        let mut limiter = self.active.remove(index);
+        limiter.finish();
+        let pid = limiter.info.lock().unwrap().pid;
+        let _ = audited("cpu_unlimit", Some(pid), json!({}), || Ok::<(), String>(()));
+        true
+    }
+
+    /// Active limiters, leaving out ones that ended because their process exited.
+    pub fn list(&self) -> Vec<LimiterInfo> {
+        self.active.iter()
+            .filter(|limiter| limiter.thread.as_ref().is_some_and(|thread| !thread.is_finished()))
+            .map(|limiter| limiter.info.lock().unwrap().clone())
+            .collect()
+    }
+
+    pub fn remove_all(&mut self) {
+        for mut limiter in self.active.drain(..) {
+            limiter.finish();
+        }
+    }
+}
+
+impl Drop for Limiters {
+    fn drop(&mut self) {
+        self.remove_all();
+    }
+}
+
+fn num_cpus() -> usize {
+    crate::ctrl::cpu_count().max(1)
+}
+
+/// `pid` and, with `children`, every process descending from it, each with its
+/// start time. Protected descendants are left out: only the process the user
+/// picked was checked against the [`Guard`].
+fn target_processes(pid: i32, starttime: u64, children: bool, guard: &Guard) -> Vec<(i32, u64)> {
+    let mut targets = vec![(pid, starttime)];
+    if !children {
+        return targets;
+    }
+    let Ok(processes) = all_processes() else { return targets };
+    let stats: Vec<_> = processes.flatten().filter_map(|proc| proc.stat().ok()).collect();
+    let mut parents = vec![pid];
+    let mut index = 0;
+    while index < parents.len() {
+        let parent = parents[index];
+        for stat in stats.iter().filter(|stat| stat.ppid == parent) {
+            parents.push(stat.pid);
+            if guard.check(stat.pid) == Protection::Unprotected {
+                targets.push((stat.pid, stat.starttime));
+            }
+        }
+        index += 1;
+    }
+    targets
+}
+
+/// Whether `pid` is still the process that started at `starttime`.
+fn is_alive(pid: i32, starttime: u64) -> bool {
+    procfs::process::Process::new(pid)
+        .and_then(|proc| proc.stat())
+        .is_ok_and(|stat| stat.starttime == starttime)
+}
+
+/// utime + stime of each of `targets` that is still alive, in clock ticks.
+fn cpu_ticks(targets: &[(i32, u64)]) -> HashMap<i32, u64> {
+    targets.iter()
+        .filter_map(|&(pid, starttime)| {
+            let stat = procfs::process::Process::new(pid).and_then(|proc| proc.stat()).ok()?;
+            (stat.starttime == starttime).then_some((pid, stat.utime + stat.stime))
+        })
+        .collect()
+}
+
+fn signal_all(targets: &[(i32, u64)], stopped: bool) {
+    for &(pid, starttime) in targets {
+        // A child may have exited since the last scan, and its PID been reused.
+        if is_alive(pid, starttime) {
+            let _ = set_stopped(pid, stopped);
+        }
+    }
+}
+
+/// Continues `targets` and drops them from the suspended registry.
+fn release(targets: &[(i32, u64)]) {
+    signal_all(targets, false);
+    for &(pid, _) in targets {
+        suspended::remove(pid);
+    }
+}
+
+/// Feedback loop in the style of cpulimit: each cycle the processes run for
+/// `duty * PERIOD` and are stopped for the rest, and `duty` is scaled by how far
+/// the measured usage is from `target` (a fraction of one CPU).
+fn duty_cycle(pid: i32, starttime: u64, target: f64, children: bool, stop: &AtomicBool, info: &Mutex<LimiterInfo>) {
+    let ticks_per_second = ticks_per_second().unwrap_or(100) as f64;
+    let guard = Guard::load();
+    let mut targets = target_processes(pid, starttime, children, &guard);
+    // Every process ever stopped, so each one gets a final SIGCONT even after
+    // it left the tree, e.g. by being reparented when its parent exited. Each
+    // is in the suspended registry too, so a crash can be recovered from.
+    let mut stopped: HashSet<(i32, u64)> = HashSet::new();
+    let mut last_ticks = cpu_ticks(&targets);
+    let mut last_sample = Instant::now();
+    let mut usage: Option<f64> = None;
+    let mut duty = target.min(1.0);
+    let mut cycle = 0;
+
+    while !stop.load(Ordering::Relaxed) && !SHUTDOWN.load(Ordering::Relaxed) {
+        if !is_alive(pid, starttime) {
+            break;
+        }
+        cycle += 1;
+        if children && cycle % RESCAN_CYCLES == 0 {
+            let rescanned = target_processes(pid, starttime, children, &guard);
+            // The previous cycle stopped these, and nothing else would continue them.
+            let gone: Vec<(i32, u64)> = targets.iter().copied().filter(|target| !rescanned.contains(target)).collect();
+            release(&gone);
+            for target in &gone {
+                stopped.remove(target);
+            }
+            targets = rescanned;
+        }
+
+        signal_all(&targets, false);
+        thread::sleep(PERIOD.mul_f64(duty));
+        if duty < 1.0 {
+            for &target in &targets {
+                if stopped.insert(target) {
+                    suspended::add(target.0, SuspendMethod::Limit);
+                }
+            }
+            signal_all(&targets, true);
+            thread::sleep(PERIOD.mul_f64(1.0 - duty));
+        }
+
+        let ticks = cpu_ticks(&targets);
+        let used: u64 = ticks.iter()
+            .map(|(pid, &now)| now.saturating_sub(last_ticks.get(pid).copied().unwrap_or(now)))
+            .sum();
+        let elapsed = last_sample.elapsed().as_secs_f64();
+        last_ticks = ticks;
+        last_sample = Instant::now();
+        let sample = used as f64 / ticks_per_second / elapsed;
+        let smoothed = usage.map_or(sample, |usage| (1.0 - ALPHA) * usage + ALPHA * sample);
+        usage = Some(smoothed);
+        duty = if smoothed > 0.0 { (duty * target / smoothed).clamp(0.01, 1.0) } else { 1.0 };
+
+        let mut info = info.lock().unwrap();
+        info.usage = smoothed * 100.0;
+        info.duty = duty;
+        info.processes = targets.len();
+    }
+
+    // Whatever ended the loop, nothing may be left stopped.
+    stopped.extend(targets);
+    release(&stopped.into_iter().collect::<Vec<_>>());
+    RUNNING.fetch_sub(1, Ordering::Relaxed);
+}
@@ -1,13 +1,50 @@
 use overview::{start_background_update,Process};
 mod ctrl;
+mod cgroup;
+mod config;
+mod audit;
+mod suspended;
+mod schedule;
+mod limiter;
+mod watchdog;
+mod helper;
+mod launch;
+mod supervisor;
+mod syscalls;
+mod trace;
+mod sampler;
+mod zombies;
+mod hung;
+use cgroup::{CgroupFs, CgroupInfo, CgroupLimit};
 use std::sync::{Mutex,Arc};
-use nix::sys::signal::{kill, Signal};
-use nix::unistd::{Pid};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use limiter::Limiters;
+use watchdog::Watchdog;
+use supervisor::Supervisor;
+use trace::Tracer;
+use sampler::Sampler;
+use zombies::Zombies;
+use hung::HungTasks;
+use launch::{LaunchSpec, Launcher};
+use schedule::{format_local_time, format_remaining, format_seconds, ActionQueue, ScheduleSpec};
 pub use ctrl::kill_process;
 pub use ctrl::terminate_process;
 pub use ctrl::suspend_process;
 pub use ctrl::resume_process;
-pub use ctrl::change_priority;
+use ctrl::{freeze_process, Bypass, Guard, Protection};
+use ctrl::{killall, killall_previewed, KillallMatch, KillallOptions};
+use ctrl::{get_priority, may_lower_nice, set_priority, PriorityTarget};
+use ctrl::{cpu_count, format_cpu_list, get_affinity, set_affinity};
+use ctrl::{get_io_priority, set_io_priority, IoClass, IoPriority};
+use ctrl::{get_scheduler, set_scheduler, SchedPolicy};
+use ctrl::{get_oom_score_adj, has_cap_sys_resource, set_oom_score_adj};
+use ctrl::{move_to_cgroup, set_cgroup_limit};
+use ctrl::resume_suspended;
+use ctrl::SignalDisposition;
+use nix::sys::signal::{SigSet, Signal};
+use config::{OnQuit, ServiceConfig};
+use suspended::SuspendedProcess;
 use Memory::start_background_update_mem;
 use IO::start_background_update_io;
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
@@ -15,8 +52,8 @@ use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Layout, Rect, Direction},
     style::{palette::tailwind, Color, Style, Styled, Stylize, Modifier},
-    text::Line,
-    widgets::{Block, Borders, Cell, Row, Paragraph, Tabs, Table, Widget, Gauge},    DefaultTerminal,
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Row, Paragraph, Tabs, Table, Widget, Gauge, Wrap},    DefaultTerminal,
 };
 use strum::{Display, EnumIter, FromRepr, IntoEnumIterator};
 use color_eyre::Result;
@@ -26,7 +63,7 @@ pub use overview::get_processes;
 
 mod cpuUsage;
 pub use cpuUsage::CpuUsage;
-pub use cpuUsage::cpu_result;
+use cpuUsage::{start_background_update_cpu, CpuUsages};
 mod Memory;
 use Memory::MemoryUsage;
 use Memory::Mem_Usage;
@@ -48,9 +85,39 @@ fn calculate_gauge_color(percent: u16) -> Color {
     }
 }
 
+/// Blocks SIGHUP, SIGTERM and SIGINT and waits for them on a thread of their own,
+/// which continues every process a CPU limiter stopped before exiting. The main
+/// thread may be stuck reading a terminal that hung up, so it cannot do this.
+/// Must run before any other thread starts, so that they all inherit the mask.
+fn handle_termination() {
+    let mut signals = SigSet::empty();
+    for signal in [Signal::SIGHUP, Signal::SIGTERM, Signal::SIGINT] {
+        signals.add(signal);
+    }
+    if signals.thread_block().is_err() {
+        return;
+    }
+    std::thread::spawn(move || {
+        let Ok(signal) = signals.wait() else { return };
+        limiter::shutdown();
+        ratatui::restore();
+        std::process::exit(128 + signal as i32);
+    });
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--watchdog") {
+        run_watchdog(args.iter().any(|arg| arg == "--dry-run"));
+        return;
+    }
+    if args.iter().any(|arg| arg == "--supervise") {
+        run_supervisor();
+        return;
+    }
+    handle_termination();
     let terminal: ratatui::Terminal<ratatui::prelude::CrosstermBackend<std::io::Stdout>> = ratatui::init();
-    let app_result: std::result::Result<(), color_eyre::eyre::Error> = App::default().run(terminal);
+    let app_result: std::result::Result<(), color_eyre::eyre::Error> = App::new().run(terminal);
     ratatui::restore();
     app_result.unwrap();
 }
@@ -63,11 +130,245 @@ struct App {
     is_cursed: bool,
     pub vertical_scroll: usize,
     process_data: Arc<Mutex<Vec<Process>>>,
-    is_priority: bool,
+    cpu_usage: Arc<Mutex<CpuUsages>>,
     memory_usage: Arc<Mutex<MemoryUsage>>,
     disk_usage: Arc<Mutex<DiskUsage>>,
+    modal: Option<Modal>,
+    status: Option<String>,
+    sort: SortKey,
+    /// Processes marked with space, by PID and start time so that a PID reused
+    /// after the marked process exited is not acted on. When any are marked,
+    /// actions apply to them instead of the cursor row.
+    marked: HashSet<(i32, u64)>,
+    schedule: ActionQueue,
+    /// Selected row of the Scheduled tab.
+    scheduled_row: usize,
+    /// Dropped with the app, which continues every process they stopped.
+    limiters: Limiters,
+    /// Selected row of the Limiters tab.
+    limiter_row: usize,
+    watchdog: Watchdog,
+    /// Last snapshot the watchdog checked, so it sees each one about once.
+    watchdog_run: Option<Instant>,
+    launcher: Launcher,
+    /// Selected row of the Launched tab.
+    launched_row: usize,
+    supervisor: Supervisor,
+    /// Selected row of the Supervisor tab.
+    service_row: usize,
+    /// Syscall trace shown in the Trace tab; dropping it detaches.
+    tracer: Option<Tracer>,
+    /// Why the last attach failed, shown in the Trace tab instead.
+    trace_error: Option<String>,
+    /// Thread the Trace tab's log is narrowed to, if any.
+    trace_thread: Option<i32>,
+    /// Lines the Trace tab's log is scrolled back from the newest.
+    trace_scroll: usize,
+    /// Wait-site sampler shown in the Sampler tab; dropping it stops it.
+    sampler: Option<Sampler>,
+    /// Selected row of the Sampler tab, whose kernel stack is shown.
+    sample_row: usize,
+    /// Kept up to date from the run loop so zombie ages add up.
+    zombies: Zombies,
+    /// Selected zombie parent in the Zombies tab.
+    zombie_row: usize,
+    /// Threads stuck in D state, warned about above every tab.
+    hung: HungTasks,
+}
+
+/// `--watchdog [--dry-run]`: runs the watchdog rules without the TUI, printing
+/// each firing until killed.
+fn run_watchdog(dry_run: bool) {
+    let mut watchdog = match Watchdog::load() {
+        Ok(watchdog) => watchdog,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    if dry_run {
+        watchdog.set_dry_run(true);
+    }
+    println!("Watching with {} rules from {}", watchdog.rules().len(), config::config_path().display());
+    loop {
+        for event in watchdog.evaluate(&overview::get_processes()) {
+            println!("{}", event);
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// `--supervise`: keeps the configured services running without the TUI,
+/// printing starts, exits and restarts until killed.
+fn run_supervisor() {
+    let mut supervisor = match Supervisor::load() {
+        Ok(supervisor) => supervisor,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let mut launcher = Launcher::default();
+    println!("Supervising {} services from {}", supervisor.status().len(), config::config_path().display());
+    loop {
+        for event in supervisor.tick(&mut launcher) {
+            println!("{}", event);
+        }
+        launcher.clear_finished();
+        std::thread::sleep(Duration::from_millis(500));
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    #[default]
+    Pid,
+    /// Highest `oom_score` first, i.e. the OOM killer's next victim on top.
+    OomScore,
+}
+
+/// The rows of the Processes tab in display order; `selected_row` indexes into this.
+fn visible_processes(data: &[Process], sort: SortKey) -> Vec<&Process> {
+    let mut visible: Vec<&Process> = data.iter()
+        .filter(|process| process.user != "root")
+        .collect();
+    if sort == SortKey::OomScore {
+        visible.sort_by_key(|process| std::cmp::Reverse(process.oom_score));
+    }
+    visible
+}
+
+/// Whether `pid` is still the process that started at `starttime`.
+fn is_running(pid: i32, starttime: u64) -> bool {
+    procfs::process::Process::new(pid)
+        .and_then(|process| process.stat())
+        .is_ok_and(|stat| stat.starttime == starttime)
+}
+
+/// A dialog drawn on top of the current tab that takes all key input while open.
+enum Modal {
+    Killall { input: String, error: Option<String> },
+    /// A run spec for [`LaunchSpec`], e.g. `-n 10 -c 0-1 ./bench`.
+    Launch { input: String, error: Option<String> },
+    AddService { input: String, error: Option<String> },
+    KillallPreview { options: KillallOptions, matches: Vec<KillallMatch> },
+    /// Asks before signalling one process. A protected process in `confirm`
+    /// mode needs `y` twice (`confirmed` marks the first), in `refuse` mode only
+    /// the force key `F` gets through. `disposition` warns about a signal the
+    /// process ignores or blocks.
+    Confirm {
+        pid: i32,
+        command: String,
+        user: String,
+        action: SignalAction,
+        protection: Protection,
+        disposition: Option<SignalDisposition>,
+        confirmed: bool,
+    },
+    /// Shown on quit while processes ProcMaster suspended are still stopped, or
+    /// scheduled actions are still pending (they die with ProcMaster).
+    QuitWarning { processes: Vec<SuspendedProcess>, pending: usize },
+    /// Asks before signalling every marked process; protected ones are only hit
+    /// with `F`. The last field says why the signal would do nothing yet.
+    BatchConfirm { action: SignalAction, targets: Vec<(Process, Protection, Option<String>)> },
+    /// Per-PID outcome of an action applied to the marked processes.
+    BatchResult { title: String, results: Vec<(i32, String, std::result::Result<(), String>)> },
+    /// `protected` lists the protected targets once Enter found some, and then
+    /// waits for y to confirm or F to force, since the action fires unattended.
+    Schedule { pid: i32, command: String, input: String, error: Option<String>, protected: Option<String> },
+    /// Which signals a process has pending, blocks, ignores and catches.
+    Signals { pid: i32, command: String, disposition: SignalDisposition },
+    /// `input` is the target CPU% of one CPU; `children` extends the limit to descendants.
+    CpuLimit { pid: i32, command: String, input: String, children: bool, error: Option<String> },
+    Renice { pid: i32, command: String, target: PriorityTarget, current: Option<i32>, input: String },
+    Affinity { pid: i32, command: String, current: String, cpus: Vec<bool>, cursor: usize, all_threads: bool },
+    IoPriority { pid: i32, command: String, current: String, priority: IoPriority },
+    /// `score` is the current `oom_score`, `current` the `oom_score_adj`
+    /// being replaced and `input` the new value being typed.
+    OomScoreAdj { pid: i32, command: String, score: u32, current: i32, input: String },
+    /// `input` is the value being typed for the selected limit, `moving` the
+    /// cgroup path being typed to move the process into.
+    Cgroup { pid: i32, command: String, info: CgroupInfo, selected: usize, input: Option<String>, moving: Option<String> },
+    /// `confirm_realtime` is set after the first Enter on a real-time policy,
+    /// so granting one always takes a second, deliberate keypress.
+    Scheduler {
+        pid: i32,
+        command: String,
+        current: String,
+        policy: SchedPolicy,
+        priority: i32,
+        all_threads: bool,
+        confirm_realtime: bool,
+    },
+}
+
+/// Signals that go through the confirmation dialog.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SignalAction {
+    Kill,
+    Terminate,
+    Suspend,
+    Freeze,
 }
 
+impl SignalAction {
+    fn run(self, pid: i32, bypass: Bypass) -> std::result::Result<(), String> {
+        match self {
+            SignalAction::Kill => kill_process(pid, bypass),
+            SignalAction::Terminate => terminate_process(pid, bypass),
+            SignalAction::Suspend => suspend_process(pid, bypass),
+            SignalAction::Freeze => freeze_process(pid, bypass),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SignalAction::Kill => "Kill (SIGKILL)",
+            SignalAction::Terminate => "Terminate (SIGTERM)",
+            SignalAction::Suspend => "Suspend (SIGSTOP)",
+            SignalAction::Freeze => "Freeze (cgroup)",
+        }
+    }
+
+    /// The signal sent, or `None` when the action does not send one.
+    fn signal(self) -> Option<Signal> {
+        match self {
+            SignalAction::Kill => Some(Signal::SIGKILL),
+            SignalAction::Terminate => Some(Signal::SIGTERM),
+            SignalAction::Suspend => Some(Signal::SIGSTOP),
+            SignalAction::Freeze => None,
+        }
+    }
+
+    /// Why the signal would not take effect, e.g. because the process ignores it.
+    fn warning(self, disposition: &SignalDisposition) -> Option<String> {
+        disposition.warning(self.signal()?)
+    }
+
+    fn verb(self) -> &'static str {
+        match self {
+            SignalAction::Kill => "Killed",
+            SignalAction::Terminate => "Terminated",
+            SignalAction::Suspend => "Suspended",
+            SignalAction::Freeze => "Froze",
+        }
+    }
+
+    /// Kill and terminate always ask first; the others only for protected processes.
+    fn always_confirm(self) -> bool {
+        matches!(self, SignalAction::Kill | SignalAction::Terminate)
+    }
+}
+
+/// Order in which Left/Right cycle through I/O classes.
+const IO_CLASSES: [IoClass; 4] = [IoClass::Realtime, IoClass::BestEffort, IoClass::Idle, IoClass::None];
+
+/// Columns in the affinity dialog's CPU grid.
+const AFFINITY_COLUMNS: usize = 8;
+
+/// Most lines the hung task warning takes above the tabs.
+const HUNG_LINES: usize = 3;
+
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
 enum AppState {
     #[default]
@@ -84,29 +385,129 @@ enum SelectedTab {
     Tab2,
     #[strum(to_string = "Memory/IO")]
     Tab3,
+    #[strum(to_string = "Actions")]
+    Tab4,
+    #[strum(to_string = "Scheduled")]
+    Tab5,
+    #[strum(to_string = "Limiters")]
+    Tab6,
+    #[strum(to_string = "Launched")]
+    Tab7,
+    #[strum(to_string = "Supervisor")]
+    Tab8,
+    #[strum(to_string = "Trace")]
+    Tab9,
+    #[strum(to_string = "Sampler")]
+    Tab10,
+    #[strum(to_string = "Zombies")]
+    Tab11,
 
 }
 
 impl App {
+    fn new() -> Self {
+        // Freezing and thawing only clean up around their own target, so
+        // leftovers from a crash elsewhere in the hierarchy go here, once.
+        CgroupFs::system().remove_stale_freeze_cgroups();
+        let mut app = App::default();
+        match Watchdog::load() {
+            Ok(watchdog) => app.watchdog = watchdog,
+            Err(err) => app.status = Some(format!("Watchdog disabled: {}", err)),
+        }
+        match Supervisor::load() {
+            Ok(supervisor) => app.supervisor = supervisor,
+            Err(err) => app.status = Some(format!("Supervisor disabled: {}", err)),
+        }
+        match HungTasks::load() {
+            Ok(hung) => app.hung = hung,
+            Err(err) => app.status = Some(format!("Using the default hung task threshold: {}", err)),
+        }
+        app
+    }
+
     fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
+        start_background_update_cpu(Arc::clone(&self.cpu_usage));
+        start_background_update_mem(Arc::clone(&self.memory_usage));
+        start_background_update(Arc::clone(&self.process_data));
+        start_background_update_io(Arc::clone(&self.disk_usage));
         while self.state == AppState::Running {
-            start_background_update_mem(Arc::clone(&self.memory_usage));
-            start_background_update(Arc::clone(&self.process_data));
-            start_background_update_io(Arc::clone(&self.disk_usage));
             terminal.draw(|frame| frame.render_widget(&self, frame.area()))?;
             self.handle_events()?;
+            self.run_scheduled();
+            self.run_watchdog();
+            self.run_supervisor();
+            self.zombies.update();
+            self.hung.update();
         }
         Ok(())
     }
 
     fn handle_events(&mut self) -> std::io::Result<()> {
+        // Wake up regularly even without input so scheduled actions fire on time.
+        if !event::poll(Duration::from_millis(250))? {
+            return Ok(());
+        }
         if let Event::Key(key) = event::read()? {
             if key.kind == KeyEventKind::Press {
+                // Batch actions run without asking, so marks on processes
+                // that have gone are dropped before any key can apply them.
+                self.prune_marks();
+                if self.modal.is_some() {
+                    self.handle_modal_key(key.code);
+                    return Ok(());
+                }
                 match key.code {
                     KeyCode::Char('l') | KeyCode::Right => self.next_tab(),
                     KeyCode::Char('h') | KeyCode::Left => self.previous_tab(),
                     KeyCode::Char('q') | KeyCode::Esc => self.quit(),
                     KeyCode::Char('c') if self.selected_tab == SelectedTab::Tab1 =>self.curse(),
+                    KeyCode::Up if self.selected_tab == SelectedTab::Tab5 => self.scheduled_row = self.scheduled_row.saturating_sub(1),
+                    KeyCode::Down if self.selected_tab == SelectedTab::Tab5 => {
+                        self.scheduled_row = (self.scheduled_row + 1).min(self.schedule.pending().len().saturating_sub(1));
+                    }
+                    KeyCode::Char('x') | KeyCode::Delete if self.selected_tab == SelectedTab::Tab5 => self.cancel_scheduled(),
+                    KeyCode::Up if self.selected_tab == SelectedTab::Tab6 => self.limiter_row = self.limiter_row.saturating_sub(1),
+                    KeyCode::Down if self.selected_tab == SelectedTab::Tab6 => {
+                        self.limiter_row = (self.limiter_row + 1).min(self.limiters.list().len().saturating_sub(1));
+                    }
+                    KeyCode::Char('x') | KeyCode::Delete if self.selected_tab == SelectedTab::Tab6 => self.remove_limiter(),
+                    KeyCode::Up if self.selected_tab == SelectedTab::Tab7 => self.launched_row = self.launched_row.saturating_sub(1),
+                    KeyCode::Down if self.selected_tab == SelectedTab::Tab7 => {
+                        self.launched_row = (self.launched_row + 1).min(self.launcher.list().len().saturating_sub(1));
+                    }
+                    KeyCode::Char('x') if self.selected_tab == SelectedTab::Tab7 => self.clear_launched(),
+                    KeyCode::Char('N') if matches!(self.selected_tab, SelectedTab::Tab1 | SelectedTab::Tab7) => self.open_launch(),
+                    KeyCode::Up if self.selected_tab == SelectedTab::Tab8 => self.service_row = self.service_row.saturating_sub(1),
+                    KeyCode::Down if self.selected_tab == SelectedTab::Tab8 => {
+                        self.service_row = (self.service_row + 1).min(self.supervisor.status().len().saturating_sub(1));
+                    }
+                    KeyCode::Char('a') if self.selected_tab == SelectedTab::Tab8 => self.open_add_service(),
+                    KeyCode::Char('s') if self.selected_tab == SelectedTab::Tab8 => self.service_action("Started", Supervisor::start),
+                    KeyCode::Char('x') if self.selected_tab == SelectedTab::Tab8 => self.service_action("Stopped", Supervisor::stop),
+                    KeyCode::Char('r') if self.selected_tab == SelectedTab::Tab8 => self.service_action("Restarted", Supervisor::restart),
+                    KeyCode::Char('d') if self.selected_tab == SelectedTab::Tab8 => self.service_action("Removed", Supervisor::remove),
+                    KeyCode::Up if self.selected_tab == SelectedTab::Tab9 => self.trace_scroll += 1,
+                    KeyCode::Down if self.selected_tab == SelectedTab::Tab9 => self.trace_scroll = self.trace_scroll.saturating_sub(1),
+                    KeyCode::Char('t') if self.selected_tab == SelectedTab::Tab9 => self.next_trace_thread(),
+                    KeyCode::Char('d') if self.selected_tab == SelectedTab::Tab9 => self.detach_trace(),
+                    KeyCode::Up if self.selected_tab == SelectedTab::Tab10 => self.sample_row = self.sample_row.saturating_sub(1),
+                    KeyCode::Down if self.selected_tab == SelectedTab::Tab10 => {
+                        let sites = self.sampler.as_ref().map_or(0, |sampler| sampler.histogram().len());
+                        self.sample_row = (self.sample_row + 1).min(sites.saturating_sub(1));
+                    }
+                    KeyCode::Char('r') if self.selected_tab == SelectedTab::Tab10 => {
+                        if let Some(sampler) = &self.sampler {
+                            sampler.reset();
+                            self.sample_row = 0;
+                        }
+                    }
+                    KeyCode::Char('d') if self.selected_tab == SelectedTab::Tab10 => self.stop_sampler(),
+                    KeyCode::Up if self.selected_tab == SelectedTab::Tab11 => self.zombie_row = self.zombie_row.saturating_sub(1),
+                    KeyCode::Down if self.selected_tab == SelectedTab::Tab11 => {
+                        self.zombie_row = (self.zombie_row + 1).min(self.zombies.report().parents.len().saturating_sub(1));
+                    }
+                    KeyCode::Char('c') if self.selected_tab == SelectedTab::Tab11 => self.nudge_zombie_parent(),
+                    KeyCode::Char('t') if self.selected_tab == SelectedTab::Tab11 => self.terminate_zombie_parent(),
                     KeyCode::Up if self.is_cursed => self.move_cursor_up(),  
                     KeyCode::Down if self.is_cursed => self.move_cursor_down(), 
                     KeyCode::Up => self.scroll_up(),
@@ -115,7 +516,25 @@ impl App {
                     KeyCode::Char('s') if self.is_cursed && self.selected_tab == SelectedTab::Tab1=> self.suspend(),
                     KeyCode::Char('r') if self.is_cursed && self.selected_tab == SelectedTab::Tab1=> self.resume(),
                     KeyCode::Char('t') if self.is_cursed && self.selected_tab == SelectedTab::Tab1=> self.terminate(),
+                    KeyCode::Char('f') if self.is_cursed && self.selected_tab == SelectedTab::Tab1=> self.freeze(),
                     KeyCode::Char('p') if self.is_cursed && self.selected_tab == SelectedTab::Tab1=> self.priority(),
+                    KeyCode::Char('a') if self.is_cursed && self.selected_tab == SelectedTab::Tab1=> self.affinity(),
+                    KeyCode::Char('i') if self.is_cursed && self.selected_tab == SelectedTab::Tab1=> self.io_priority(),
+                    KeyCode::Char('P') if self.is_cursed && self.selected_tab == SelectedTab::Tab1=> self.scheduler(),
+                    KeyCode::Char('o') if self.is_cursed && self.selected_tab == SelectedTab::Tab1=> self.oom_score_adj(),
+                    KeyCode::Char('g') if self.is_cursed && self.selected_tab == SelectedTab::Tab1=> self.cgroup(),
+                    KeyCode::Char('O') if self.selected_tab == SelectedTab::Tab1 => self.toggle_oom_sort(),
+                    KeyCode::Char('K') if self.selected_tab == SelectedTab::Tab1 => self.open_killall(),
+                    KeyCode::Char('T') if self.is_cursed && self.selected_tab == SelectedTab::Tab1 => self.open_schedule(),
+                    KeyCode::Char('L') if self.is_cursed && self.selected_tab == SelectedTab::Tab1 => self.open_cpu_limit(),
+                    KeyCode::Char('S') if self.is_cursed && self.selected_tab == SelectedTab::Tab1 => self.trace(),
+                    KeyCode::Char('W') if self.is_cursed && self.selected_tab == SelectedTab::Tab1 => self.sample(),
+                    KeyCode::Char('I') if self.is_cursed && self.selected_tab == SelectedTab::Tab1 => self.signals(),
+                    KeyCode::Char('R') if self.selected_tab == SelectedTab::Tab1 => self.resume_all(),
+                    KeyCode::Char(' ') if self.is_cursed && self.selected_tab == SelectedTab::Tab1 => self.toggle_mark(),
+                    KeyCode::Char('+') if self.selected_tab == SelectedTab::Tab1 => self.mark_all(),
+                    KeyCode::Char('*') if self.selected_tab == SelectedTab::Tab1 => self.invert_marks(),
+                    KeyCode::Char('-') if self.selected_tab == SelectedTab::Tab1 => self.clear_marks(),
                     _ => {}
                 }
             }
@@ -123,6 +542,403 @@ impl App {
         Ok(())
     }
 
+    fn handle_modal_key(&mut self, code: KeyCode) {
+        let Some(modal) = self.modal.take() else { return };
+        self.modal = match modal {
+            Modal::Killall { mut input, error } => match code {
+                KeyCode::Esc => None,
+                KeyCode::Enter => match input.parse::<KillallOptions>() {
+                    Ok(options) => self.preview_killall(options),
+                    Err(err) => Some(Modal::Killall { input, error: Some(err) }),
+                },
+                KeyCode::Backspace => {
+                    input.pop();
+                    Some(Modal::Killall { input, error: None })
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    Some(Modal::Killall { input, error: None })
+                }
+                _ => Some(Modal::Killall { input, error }),
+            },
+            Modal::Launch { mut input, error } => match code {
+                KeyCode::Esc => None,
+                KeyCode::Enter => match input.parse::<LaunchSpec>() {
+                    Ok(spec) => match self.launcher.launch(&spec) {
+                        Ok(pid) => {
+                            self.status = Some(format!("Started {} as PID {}", spec.command, pid));
+                            self.launched_row = self.launcher.list().len() - 1;
+                            None
+                        }
+                        Err(err) => Some(Modal::Launch { input, error: Some(err) }),
+                    },
+                    Err(err) => Some(Modal::Launch { input, error: Some(err) }),
+                },
+                KeyCode::Backspace => {
+                    input.pop();
+                    Some(Modal::Launch { input, error: None })
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    Some(Modal::Launch { input, error: None })
+                }
+                _ => Some(Modal::Launch { input, error }),
+            },
+            Modal::AddService { mut input, error } => match code {
+                KeyCode::Esc => None,
+                KeyCode::Enter => match input.parse::<ServiceConfig>() {
+                    Ok(service) => {
+                        let name = service.name.clone();
+                        match self.supervisor.add(service) {
+                            Ok(()) => {
+                                self.status = Some(format!("Added service {} to {}", name, config::config_path().display()));
+                                self.service_row = self.supervisor.status().len() - 1;
+                                None
+                            }
+                            Err(err) => Some(Modal::AddService { input, error: Some(err) }),
+                        }
+                    }
+                    Err(err) => Some(Modal::AddService { input, error: Some(err) }),
+                },
+                KeyCode::Backspace => {
+                    input.pop();
+                    Some(Modal::AddService { input, error: None })
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    Some(Modal::AddService { input, error: None })
+                }
+                _ => Some(Modal::AddService { input, error }),
+            },
+            Modal::KillallPreview { options, matches } => match code {
+                KeyCode::Esc | KeyCode::Char('n') => None,
+                KeyCode::Char('y') | KeyCode::Enter if !options.dry_run => {
+                    self.run_killall(&options, &matches, options.bypass);
+                    None
+                }
+                KeyCode::Char('F') if !options.dry_run => {
+                    self.run_killall(&options, &matches, Bypass::Forced);
+                    None
+                }
+                KeyCode::Enter => None,
+                _ => Some(Modal::KillallPreview { options, matches }),
+            },
+            Modal::Confirm { pid, command, user, action, protection, disposition, confirmed } => match code {
+                KeyCode::Esc | KeyCode::Char('n') => None,
+                KeyCode::Char('F') => {
+                    self.run_signal(pid, &command, action, Bypass::Forced);
+                    None
+                }
+                KeyCode::Char('y') | KeyCode::Enter => match protection {
+                    Protection::Unprotected => {
+                        self.run_signal(pid, &command, action, Bypass::None);
+                        None
+                    }
+                    Protection::Confirm(_) if confirmed => {
+                        self.run_signal(pid, &command, action, Bypass::Confirmed);
+                        None
+                    }
+                    Protection::Confirm(_) => {
+                        Some(Modal::Confirm { pid, command, user, action, protection, disposition, confirmed: true })
+                    }
+                    Protection::Refuse(_) => Some(Modal::Confirm { pid, command, user, action, protection, disposition, confirmed }),
+                },
+                _ => Some(Modal::Confirm { pid, command, user, action, protection, disposition, confirmed }),
+            },
+            Modal::BatchConfirm { action, targets } => match code {
+                KeyCode::Esc | KeyCode::Char('n') => None,
+                KeyCode::Char('y') | KeyCode::Enter => self.run_batch(action.name(), |pid| action.run(pid, Bypass::None)),
+                KeyCode::Char('F') => self.run_batch(action.name(), |pid| action.run(pid, Bypass::Forced)),
+                _ => Some(Modal::BatchConfirm { action, targets }),
+            },
+            Modal::BatchResult { title, results } => match code {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => None,
+                _ => Some(Modal::BatchResult { title, results }),
+            },
+            Modal::Signals { pid, command, disposition } => match code {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => None,
+                _ => Some(Modal::Signals { pid, command, disposition }),
+            },
+            Modal::Schedule { pid, command, input, protected: Some(_), .. } if matches!(code, KeyCode::Char('y' | 'F')) => {
+                let bypass = if code == KeyCode::Char('F') { Bypass::Forced } else { Bypass::Confirmed };
+                let spec = input.parse::<ScheduleSpec>();
+                match spec {
+                    Ok(spec) => self.schedule_action(pid, command, input, spec, bypass),
+                    Err(err) => Some(Modal::Schedule { pid, command, input, error: Some(err), protected: None }),
+                }
+            }
+            Modal::Schedule { pid, command, mut input, error, protected } => match code {
+                KeyCode::Esc => None,
+                KeyCode::Enter => match input.parse::<ScheduleSpec>() {
+                    Ok(spec) => self.schedule_action(pid, command, input, spec, Bypass::None),
+                    Err(err) => Some(Modal::Schedule { pid, command, input, error: Some(err), protected: None }),
+                },
+                KeyCode::Backspace => {
+                    input.pop();
+                    Some(Modal::Schedule { pid, command, input, error: None, protected: None })
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    Some(Modal::Schedule { pid, command, input, error: None, protected: None })
+                }
+                _ => Some(Modal::Schedule { pid, command, input, error, protected }),
+            },
+            Modal::CpuLimit { pid, command, mut input, mut children, error } => match code {
+                KeyCode::Esc => None,
+                KeyCode::Enter => match input.parse::<f64>() {
+                    Ok(target) => self.start_cpu_limit(pid, target, children),
+                    Err(_) => Some(Modal::CpuLimit { pid, command, input, children, error: Some("Enter a percentage".to_string()) }),
+                },
+                KeyCode::Tab => {
+                    children = !children;
+                    Some(Modal::CpuLimit { pid, command, input, children, error })
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                    Some(Modal::CpuLimit { pid, command, input, children, error: None })
+                }
+                KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => {
+                    input.push(c);
+                    Some(Modal::CpuLimit { pid, command, input, children, error: None })
+                }
+                _ => Some(Modal::CpuLimit { pid, command, input, children, error }),
+            },
+            Modal::QuitWarning { processes, pending } => match code {
+                KeyCode::Esc => None,
+                KeyCode::Char('r') => {
+                    self.resume_all();
+                    self.state = AppState::Quitting;
+                    None
+                }
+                KeyCode::Char('q') => {
+                    self.state = AppState::Quitting;
+                    None
+                }
+                _ => Some(Modal::QuitWarning { processes, pending }),
+            },
+            Modal::Renice { pid, command, mut target, mut current, mut input } => match code {
+                KeyCode::Esc => None,
+                KeyCode::Enter => match parse_nice(&input) {
+                    Ok(nice) => self.apply_priority(target, nice),
+                    Err(_) => Some(Modal::Renice { pid, command, target, current, input }),
+                },
+                KeyCode::Tab => {
+                    target = next_priority_target(pid, target);
+                    current = get_priority(target).ok();
+                    Some(Modal::Renice { pid, command, target, current, input })
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                    Some(Modal::Renice { pid, command, target, current, input })
+                }
+                KeyCode::Char(c) if c.is_ascii_digit() || (c == '-' && input.is_empty()) => {
+                    input.push(c);
+                    Some(Modal::Renice { pid, command, target, current, input })
+                }
+                _ => Some(Modal::Renice { pid, command, target, current, input }),
+            },
+            Modal::Affinity { pid, command, current, mut cpus, mut cursor, mut all_threads } => {
+                match code {
+                    KeyCode::Esc => return,
+                    KeyCode::Enter => {
+                        self.modal = self.apply_affinity(pid, &cpus, all_threads);
+                        return;
+                    }
+                    KeyCode::Left => cursor = cursor.saturating_sub(1),
+                    KeyCode::Right => cursor = (cursor + 1).min(cpus.len() - 1),
+                    KeyCode::Up => cursor = cursor.saturating_sub(AFFINITY_COLUMNS),
+                    KeyCode::Down if cursor + AFFINITY_COLUMNS < cpus.len() => cursor += AFFINITY_COLUMNS,
+                    KeyCode::Char(' ') => cpus[cursor] = !cpus[cursor],
+                    KeyCode::Char('a') => cpus.iter_mut().for_each(|cpu| *cpu = true),
+                    KeyCode::Char('n') => cpus.iter_mut().for_each(|cpu| *cpu = false),
+                    KeyCode::Char('t') => all_threads = !all_threads,
+                    _ => {}
+                }
+                Some(Modal::Affinity { pid, command, current, cpus, cursor, all_threads })
+            }
+            Modal::IoPriority { pid, command, current, mut priority } => {
+                let index = IO_CLASSES.iter().position(|&class| class == priority.class).unwrap_or(0);
+                match code {
+                    KeyCode::Esc => return,
+                    KeyCode::Enter => {
+                        self.modal = self.apply_io_priority(pid, priority);
+                        return;
+                    }
+                    KeyCode::Left => priority.class = IO_CLASSES[(index + IO_CLASSES.len() - 1) % IO_CLASSES.len()],
+                    KeyCode::Right | KeyCode::Tab => priority.class = IO_CLASSES[(index + 1) % IO_CLASSES.len()],
+                    KeyCode::Up => priority.level = priority.level.saturating_sub(1),
+                    KeyCode::Down => priority.level = (priority.level + 1).min(7),
+                    _ => {}
+                }
+                Some(Modal::IoPriority { pid, command, current, priority })
+            }
+            Modal::Cgroup { pid, command, mut info, mut selected, mut input, mut moving } => {
+                if let Some(mut text) = input.take() {
+                    match code {
+                        KeyCode::Esc => {}
+                        KeyCode::Enter => {
+                            let limit = CgroupLimit::ALL[selected];
+                            self.status = Some(match set_cgroup_limit(pid, &info.path, limit, &text) {
+                                Ok(()) => format!("Set {} of {} to {}", limit, info.path, text.trim()),
+                                Err(err) => format!("Failed to set {} of {}: {}", limit, info.path, err),
+                            });
+                            info = CgroupFs::system().info(&info.path);
+                        }
+                        KeyCode::Backspace => {
+                            text.pop();
+                            input = Some(text);
+                        }
+                        KeyCode::Char(c) => {
+                            text.push(c);
+                            input = Some(text);
+                        }
+                        _ => input = Some(text),
+                    }
+                } else if let Some(mut text) = moving.take() {
+                    match code {
+                        KeyCode::Esc => {}
+                        KeyCode::Enter if !self.marked.is_empty() => {
+                            self.modal = self.run_batch(&format!("Move into {}", text), |pid| {
+                                move_to_cgroup(pid, &text).map_err(|err| err.to_string())
+                            });
+                            return;
+                        }
+                        KeyCode::Enter => {
+                            let cgroups = CgroupFs::system();
+                            self.status = Some(match move_to_cgroup(pid, &text) {
+                                Ok(()) => format!("Moved PID {} into {}", pid, text),
+                                Err(err) => format!("Failed to move PID {} into {}: {}", pid, text, err),
+                            });
+                            if let Ok(path) = cgroups.cgroup_of(pid) {
+                                info = cgroups.info(&path);
+                            }
+                        }
+                        KeyCode::Backspace => {
+                            text.pop();
+                            moving = Some(text);
+                        }
+                        KeyCode::Char(c) => {
+                            text.push(c);
+                            moving = Some(text);
+                        }
+                        _ => moving = Some(text),
+                    }
+                } else {
+                    match code {
+                        KeyCode::Esc => return,
+                        KeyCode::Up => selected = selected.saturating_sub(1),
+                        KeyCode::Down => selected = (selected + 1).min(CgroupLimit::ALL.len() - 1),
+                        KeyCode::Enter => {
+                            input = Some(info.limits[selected].1.clone().unwrap_or_default());
+                        }
+                        KeyCode::Char('m') => moving = Some(info.path.clone()),
+                        _ => {}
+                    }
+                }
+                Some(Modal::Cgroup { pid, command, info, selected, input, moving })
+            }
+            Modal::OomScoreAdj { pid, command, score, current, mut input } => {
+                match code {
+                    KeyCode::Esc => return,
+                    KeyCode::Enter => {
+                        if let Ok(value) = parse_oom_score_adj(&input) {
+                            self.apply_oom_score_adj(pid, value);
+                            return;
+                        }
+                    }
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) if c.is_ascii_digit() || (c == '-' && input.is_empty()) => input.push(c),
+                    _ => {}
+                }
+                Some(Modal::OomScoreAdj { pid, command, score, current, input })
+            }
+            Modal::Scheduler { pid, command, current, mut policy, mut priority, mut all_threads, mut confirm_realtime } => {
+                let index = SchedPolicy::ALL.iter().position(|&p| p == policy).unwrap_or(0);
+                let count = SchedPolicy::ALL.len();
+                match code {
+                    KeyCode::Esc => return,
+                    KeyCode::Enter if policy.is_realtime() && !confirm_realtime => confirm_realtime = true,
+                    KeyCode::Enter => {
+                        self.apply_scheduler(pid, policy, priority, all_threads);
+                        return;
+                    }
+                    KeyCode::Left => policy = SchedPolicy::ALL[(index + count - 1) % count],
+                    KeyCode::Right | KeyCode::Tab => policy = SchedPolicy::ALL[(index + 1) % count],
+                    KeyCode::Up => priority += 1,
+                    KeyCode::Down => priority -= 1,
+                    KeyCode::Char('t') => all_threads = !all_threads,
+                    _ => {}
+                }
+                if let Ok((min, max)) = policy.priority_range() {
+                    priority = priority.clamp(min, max);
+                }
+                if !matches!(code, KeyCode::Enter) {
+                    confirm_realtime = false;
+                }
+                Some(Modal::Scheduler { pid, command, current, policy, priority, all_threads, confirm_realtime })
+            }
+        };
+    }
+
+    pub fn open_killall(&mut self) {
+        self.modal = Some(Modal::Killall { input: String::new(), error: None });
+    }
+
+    pub fn open_launch(&mut self) {
+        self.modal = Some(Modal::Launch { input: String::new(), error: None });
+    }
+
+    pub fn open_add_service(&mut self) {
+        self.modal = Some(Modal::AddService { input: String::new(), error: None });
+    }
+
+    /// Runs a supervisor operation on the selected service and reports it.
+    fn service_action(&mut self, done: &str, action: fn(&mut Supervisor, &str) -> std::result::Result<(), String>) {
+        let Some(service) = self.supervisor.status().into_iter().nth(self.service_row) else { return };
+        self.status = Some(match action(&mut self.supervisor, &service.name) {
+            Ok(()) => format!("{} {}", done, service.name),
+            Err(err) => err,
+        });
+        self.service_row = self.service_row.min(self.supervisor.status().len().saturating_sub(1));
+    }
+
+    /// Forgets launched processes that have exited, with their output.
+    fn clear_launched(&mut self) {
+        self.launcher.clear_finished();
+        self.launched_row = self.launched_row.min(self.launcher.list().len().saturating_sub(1));
+    }
+
+    /// Lists what a killall spec would hit so the user can confirm before anything is sent.
+    fn preview_killall(&mut self, options: KillallOptions) -> Option<Modal> {
+        let mut dry_run = options.clone();
+        dry_run.dry_run = true;
+        match killall(&dry_run) {
+            Ok(matches) => Some(Modal::KillallPreview { options, matches }),
+            Err(err) => {
+                self.status = Some(err);
+                None
+            }
+        }
+    }
+
+    /// Signals the processes shown in the preview, not whatever matches by now.
+    fn run_killall(&mut self, options: &KillallOptions, previewed: &[KillallMatch], bypass: Bypass) {
+        let matches = killall_previewed(previewed, options.signal, bypass);
+        let failed: Vec<&KillallMatch> = matches.iter().filter(|m| m.error.is_some()).collect();
+        self.status = Some(match failed.first() {
+            None => format!("Sent {} to {} processes", options.signal, matches.len()),
+            Some(first) => format!(
+                "Sent {} to {} of {} processes: {}",
+                options.signal,
+                matches.len() - failed.len(),
+                matches.len(),
+                first.error.as_deref().unwrap_or_default()
+            ),
+        });
+    }
+
     pub fn scroll_up(&mut self) {
         if self.vertical_scroll > 0 {
             self.vertical_scroll -= 1;
@@ -136,110 +952,525 @@ impl App {
         }
     }
 
-    pub fn priority(&mut self) {
-        self.is_priority = !self.is_priority;
-    
+    /// The process under the cursor, looked up the same way `render_processes` lists them.
+    fn selected_process(&self) -> Option<Process> {
         let data = self.process_data.lock().unwrap();
-        let filtered_data: Vec<&Process> = data.iter()
-            .filter(|process| process.user != "root")
-            .collect();
-    
-        // if self.is_priority && self.is_cursed {
-    
-        //     let mut new_priority = String::new();
-    
-        //     if let Event::Key(key) = event::read().unwrap() {
-        //         if key.kind == KeyEventKind::Press {
-        //             match key.code {
-        //                 KeyCode::Char(c) => {
-        //                     new_priority.push(c);
-        //                 }
-        //                 KeyCode::Enter => {
-        //                     if let Ok(priority) = new_priority.parse::<i32>() {
-        //                         if let Some(process) = filtered_data.get(self.selected_row) {
-        //                            change_priority(process.pid, priority) ;
-        //                         }
-        //                     } else {
-        //                         Line::raw("Invalid priority! Please enter a number between -20 and 19.")
-        //                             .centered()
-        //                             .render(footer_area, buf);
-        //                     }
-        //                     self.is_priority = false;
-        //                 }
-        //                 KeyCode::Esc => {
-        //                     self.is_priority = false;
-        //                 }
-        //                 _ => {}
-        //             }
-                }
-            //}
-        //}
-    //}
-    
+        visible_processes(&data, self.sort)
+            .get(self.selected_row)
+            .map(|process| (*process).clone())
+    }
 
-    pub fn kill(&mut self) {
-        
-        let data = self.process_data.lock().unwrap();
+    pub fn priority(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        let target = PriorityTarget::Process(process.pid);
+        self.modal = Some(Modal::Renice {
+            pid: process.pid,
+            command: process.command,
+            target,
+            current: get_priority(target).ok(),
+            input: String::new(),
+        });
+    }
 
-        let filtered_data: Vec<&Process> = data.iter()
-        .filter(|process| process.user != "root")
-        .collect();
+    fn apply_priority(&mut self, target: PriorityTarget, nice: i32) -> Option<Modal> {
+        if matches!(target, PriorityTarget::Process(_)) && !self.marked.is_empty() {
+            return self.run_batch(&format!("Renice to {}", nice), |pid| {
+                set_priority(PriorityTarget::Process(pid), nice).map_err(|err| err.to_string())
+            });
+        }
+        self.status = Some(match set_priority(target, nice) {
+            Ok(()) => format!("Set nice of {} to {}", describe_target(target), nice),
+            Err(err) => format!("Failed to renice {}: {}", describe_target(target), err),
+        });
+        None
+    }
 
-        if let Some(process) = filtered_data.get(self.selected_row) {
-            let pid = process.pid; 
-            let p_id = Pid::from_raw(pid);
-            if let Err(err) = kill(p_id, Signal::SIGKILL) {
-                eprintln!("Failed to send signal to process {}: {}", pid, err);
+    pub fn affinity(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        match get_affinity(process.pid) {
+            Ok(current) => {
+                let mut cpus = vec![false; cpu_count()];
+                for &cpu in &current {
+                    cpus[cpu] = true;
+                }
+                self.modal = Some(Modal::Affinity {
+                    pid: process.pid,
+                    command: process.command,
+                    current: format_cpu_list(&current),
+                    cpus,
+                    cursor: 0,
+                    all_threads: true,
+                });
             }
+            Err(err) => self.status = Some(format!("Failed to read affinity of PID {}: {}", process.pid, err)),
         }
     }
-    pub fn terminate(&mut self) {
-        
-        let data = self.process_data.lock().unwrap();
 
-        let filtered_data: Vec<&Process> = data.iter()
-        .filter(|process| process.user != "root")
-        .collect();
+    fn apply_affinity(&mut self, pid: i32, cpus: &[bool], all_threads: bool) -> Option<Modal> {
+        let selected: Vec<usize> = cpus.iter().enumerate().filter(|(_, &on)| on).map(|(cpu, _)| cpu).collect();
+        if !self.marked.is_empty() {
+            return self.run_batch(&format!("Pin to CPUs {}", format_cpu_list(&selected)), |pid| {
+                set_affinity(pid, &selected, all_threads).map_err(|err| err.to_string())
+            });
+        }
+        let scope = if all_threads { "all threads of PID" } else { "PID" };
+        self.status = Some(match set_affinity(pid, &selected, all_threads) {
+            Ok(()) => format!("Pinned {} {} to CPUs {}", scope, pid, format_cpu_list(&selected)),
+            Err(err) => format!("Failed to set affinity of PID {}: {}", pid, err),
+        });
+        None
+    }
 
-        if let Some(process) = filtered_data.get(self.selected_row) {
-            let pid = process.pid; 
-            let p_id = Pid::from_raw(pid);
-            if let Err(err) = kill(p_id, Signal::SIGTERM) {
-                eprintln!("Failed to send signal to process {}: {}", pid, err);
+    pub fn io_priority(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        match get_io_priority(process.pid) {
+            Ok(current) => {
+                let mut priority = current;
+                if priority.class == IoClass::None {
+                    priority = IoPriority { class: IoClass::BestEffort, level: 4 };
+                }
+                self.modal = Some(Modal::IoPriority {
+                    pid: process.pid,
+                    command: process.command,
+                    current: current.to_string(),
+                    priority,
+                });
             }
+            Err(err) => self.status = Some(format!("Failed to read I/O priority of PID {}: {}", process.pid, err)),
         }
     }
-    pub fn resume(&mut self) {
-        
-        let data = self.process_data.lock().unwrap();
 
-        let filtered_data: Vec<&Process> = data.iter()
-        .filter(|process| process.user != "root")
-        .collect();
+    fn apply_io_priority(&mut self, pid: i32, priority: IoPriority) -> Option<Modal> {
+        if !self.marked.is_empty() {
+            return self.run_batch(&format!("Set I/O priority to {}", priority), |pid| {
+                set_io_priority(pid, priority).map_err(|err| err.to_string())
+            });
+        }
+        self.status = Some(match set_io_priority(pid, priority) {
+            Ok(()) => format!("Set I/O priority of PID {} to {}", pid, priority),
+            Err(err) => format!("Failed to set I/O priority of PID {}: {}", pid, err),
+        });
+        None
+    }
 
-        if let Some(process) = filtered_data.get(self.selected_row) {
-            let pid = process.pid; 
-            let p_id = Pid::from_raw(pid);
-            if let Err(err) = kill(p_id, Signal::SIGCONT) {
-                eprintln!("Failed to send signal to process {}: {}", pid, err);
+    pub fn scheduler(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        match get_scheduler(process.pid) {
+            Ok((policy, priority)) => {
+                self.modal = Some(Modal::Scheduler {
+                    pid: process.pid,
+                    command: process.command,
+                    current: format!("{} (priority {})", policy, priority),
+                    policy,
+                    priority,
+                    all_threads: true,
+                    confirm_realtime: false,
+                });
             }
+            Err(err) => self.status = Some(format!("Failed to read scheduling policy of PID {}: {}", process.pid, err)),
         }
     }
+
+    fn apply_scheduler(&mut self, pid: i32, policy: SchedPolicy, priority: i32, all_threads: bool) {
+        self.status = Some(match set_scheduler(pid, policy, priority, all_threads) {
+            Ok(()) => format!("Set scheduling policy of PID {} to {} (priority {})", pid, policy, priority),
+            Err(err) => format!("Failed to set scheduling policy of PID {}: {}", pid, err),
+        });
+    }
+
+    pub fn oom_score_adj(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        match get_oom_score_adj(process.pid) {
+            Ok(current) => {
+                self.modal = Some(Modal::OomScoreAdj {
+                    pid: process.pid,
+                    command: process.command,
+                    score: process.oom_score,
+                    current,
+                    input: String::new(),
+                });
+            }
+            Err(err) => self.status = Some(format!("Failed to read oom_score_adj of PID {}: {}", process.pid, err)),
+        }
+    }
+
+    fn apply_oom_score_adj(&mut self, pid: i32, value: i32) {
+        self.status = Some(match set_oom_score_adj(pid, value) {
+            Ok(()) => format!("Set oom_score_adj of PID {} to {}", pid, value),
+            Err(err) => format!("Failed to set oom_score_adj of PID {}: {}", pid, err),
+        });
+    }
+
+    pub fn signals(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        match SignalDisposition::read(process.pid) {
+            Ok(disposition) => self.modal = Some(Modal::Signals { pid: process.pid, command: process.command, disposition }),
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    pub fn cgroup(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        let cgroups = CgroupFs::system();
+        match cgroups.cgroup_of(process.pid) {
+            Ok(path) => {
+                self.modal = Some(Modal::Cgroup {
+                    pid: process.pid,
+                    command: process.command,
+                    info: cgroups.info(&path),
+                    selected: 0,
+                    input: None,
+                    moving: None,
+                });
+            }
+            Err(err) => self.status = Some(format!("Failed to read cgroup of PID {}: {}", process.pid, err)),
+        }
+    }
+
+    pub fn toggle_oom_sort(&mut self) {
+        self.sort = match self.sort {
+            SortKey::Pid => SortKey::OomScore,
+            SortKey::OomScore => SortKey::Pid,
+        };
+    }
+
+    /// Runs `action` on the selected process, through the confirmation dialog
+    /// when the action or the process calls for one.
+    fn signal_selected(&mut self, action: SignalAction) {
+        if !self.marked.is_empty() {
+            let guard = Guard::load();
+            let targets = self.marked_processes()
+                .into_iter()
+                .map(|process| {
+                    let protection = guard.check(process.pid);
+                    let warning = SignalDisposition::read(process.pid).ok().and_then(|disposition| action.warning(&disposition));
+                    (process, protection, warning)
+                })
+                .collect();
+            self.modal = Some(Modal::BatchConfirm { action, targets });
+            return;
+        }
+        let Some(process) = self.selected_process() else { return };
+        let protection = Guard::load().check(process.pid);
+        let disposition = SignalDisposition::read(process.pid).ok();
+        let warned = disposition.as_ref().and_then(|disposition| action.warning(disposition)).is_some();
+        if action.always_confirm() || protection != Protection::Unprotected || warned {
+            self.modal = Some(Modal::Confirm {
+                pid: process.pid,
+                command: process.command,
+                user: process.user,
+                action,
+                protection,
+                disposition,
+                confirmed: false,
+            });
+        } else {
+            self.run_signal(process.pid, &process.command, action, Bypass::None);
+        }
+    }
+
+    fn run_signal(&mut self, pid: i32, command: &str, action: SignalAction, bypass: Bypass) {
+        self.status = Some(match action.run(pid, bypass) {
+            Ok(()) => format!("{} {} ({})", action.verb(), pid, command),
+            Err(err) => err,
+        });
+    }
+
+    pub fn kill(&mut self) {
+        self.signal_selected(SignalAction::Kill);
+    }
+    pub fn terminate(&mut self) {
+        self.signal_selected(SignalAction::Terminate);
+    }
+    pub fn resume(&mut self) {
+        if !self.marked.is_empty() {
+            self.modal = self.run_batch("Resume", resume_process);
+            return;
+        }
+        let Some(process) = self.selected_process() else { return };
+        self.status = Some(match resume_process(process.pid) {
+            Ok(()) => format!("Resumed {} ({})", process.pid, process.command),
+            Err(err) => err,
+        });
+    }
     pub fn suspend(&mut self) {
-        
-        let data = self.process_data.lock().unwrap();
+        self.signal_selected(SignalAction::Suspend);
+    }
+    pub fn freeze(&mut self) {
+        self.signal_selected(SignalAction::Freeze);
+    }
 
-        let filtered_data: Vec<&Process> = data.iter()
-        .filter(|process| process.user != "root")
-        .collect();
+    pub fn open_schedule(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        self.modal = Some(Modal::Schedule { pid: process.pid, command: process.command, input: String::new(), error: None, protected: None });
+    }
 
-        if let Some(process) = filtered_data.get(self.selected_row) {
-            let pid = process.pid; 
-            let p_id = Pid::from_raw(pid);
-            if let Err(err) = kill(p_id, Signal::SIGSTOP) {
-                eprintln!("Failed to send signal to process {}: {}", pid, err);
+    /// Queues `spec` for the marked processes, or for `pid` when none are marked.
+    /// Protected targets of a signal or freeze are asked about now, keeping the
+    /// dialog open, as nobody may be there to confirm when the action fires.
+    fn schedule_action(&mut self, pid: i32, command: String, input: String, spec: ScheduleSpec, bypass: Bypass) -> Option<Modal> {
+        let pids: Vec<i32> = if self.marked.is_empty() {
+            vec![pid]
+        } else {
+            self.marked_processes().iter().map(|process| process.pid).collect()
+        };
+        if spec.action.is_guarded() && bypass == Bypass::None {
+            let guard = Guard::load();
+            let protected: Vec<String> = pids.iter()
+                .filter_map(|&pid| match guard.check(pid) {
+                    Protection::Unprotected => None,
+                    Protection::Confirm(reason) | Protection::Refuse(reason) => Some(format!("{} ({})", pid, reason)),
+                })
+                .collect();
+            if !protected.is_empty() {
+                return Some(Modal::Schedule { pid, command, input, error: None, protected: Some(protected.join(", ")) });
             }
         }
+        let errors: Vec<String> = pids.iter()
+            .filter_map(|&pid| self.schedule.schedule(pid, spec, bypass).err())
+            .collect();
+        self.status = Some(if errors.is_empty() {
+            format!("Scheduled {} of {} processes at {}", spec.action, pids.len(), format_local_time(spec.due))
+        } else {
+            errors.join("; ")
+        });
+        None
+    }
+
+    fn cancel_scheduled(&mut self) {
+        let Some(id) = self.schedule.pending().get(self.scheduled_row).map(|action| action.id) else { return };
+        if let Some(action) = self.schedule.cancel(id) {
+            self.status = Some(format!("Cancelled {} of {} ({})", action.action, action.pid, action.command));
+        }
+        self.scheduled_row = self.scheduled_row.min(self.schedule.pending().len().saturating_sub(1));
+    }
+
+    pub fn open_cpu_limit(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        self.modal = Some(Modal::CpuLimit {
+            pid: process.pid,
+            command: process.command,
+            input: String::new(),
+            children: false,
+            error: None,
+        });
+    }
+
+    /// Attaches the syscall tracer to the selected process, detaching from the
+    /// previous one, and shows the Trace tab.
+    pub fn trace(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        // Its ptrace stops would be reported to the launcher's wait instead of the tracer.
+        if self.launcher.is_launched(process.pid) {
+            self.status = Some(format!("{} was started by ProcMaster and cannot be traced from it, use strace", process.pid));
+            return;
+        }
+        self.tracer = None;
+        self.trace_thread = None;
+        self.trace_scroll = 0;
+        match Tracer::attach(process.pid) {
+            Ok(tracer) => {
+                self.tracer = Some(tracer);
+                self.trace_error = None;
+            }
+            Err(err) => self.trace_error = Some(err),
+        }
+        self.selected_tab = SelectedTab::Tab9;
+    }
+
+    fn detach_trace(&mut self) {
+        if let Some(tracer) = self.tracer.take() {
+            self.status = Some(format!("Detached from {} ({})", tracer.pid, tracer.command));
+        }
+    }
+
+    /// Starts sampling where the selected process's threads wait, replacing
+    /// the previous sampler, and shows the Sampler tab.
+    pub fn sample(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        self.sampler = None;
+        self.sample_row = 0;
+        match Sampler::start(process.pid, sampler::DEFAULT_INTERVAL) {
+            Ok(sampler) => {
+                self.sampler = Some(sampler);
+                self.selected_tab = SelectedTab::Tab10;
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    fn nudge_zombie_parent(&mut self) {
+        let Some(parent) = self.zombies.report().parents.get(self.zombie_row) else { return };
+        self.status = Some(match zombies::nudge_parent(parent.pid) {
+            Ok(()) => format!("Sent SIGCHLD to {} ({})", parent.pid, parent.command),
+            Err(err) => err,
+        });
+    }
+
+    /// Asks before terminating the selected zombie parent, whose zombies its
+    /// reaper then collects.
+    fn terminate_zombie_parent(&mut self) {
+        let Some(parent) = self.zombies.report().parents.get(self.zombie_row) else { return };
+        if parent.pid == 1 {
+            self.status = Some("PID 1 cannot be terminated".to_string());
+            return;
+        }
+        let process = self.process_data.lock().unwrap().iter().find(|process| process.pid == parent.pid).cloned();
+        let Some(process) = process else {
+            self.status = Some(format!("{} ({}) is no longer running", parent.pid, parent.command));
+            return;
+        };
+        self.modal = Some(Modal::Confirm {
+            pid: process.pid,
+            command: process.command,
+            user: process.user,
+            action: SignalAction::Terminate,
+            protection: Guard::load().check(process.pid),
+            disposition: SignalDisposition::read(process.pid).ok(),
+            confirmed: false,
+        });
+    }
+
+    fn stop_sampler(&mut self) {
+        if let Some(sampler) = self.sampler.take() {
+            self.status = Some(format!("Stopped sampling {} ({})", sampler.pid, sampler.command));
+        }
+    }
+
+    /// Narrows the trace log to the next thread, and back to all of them after the last.
+    fn next_trace_thread(&mut self) {
+        let Some(tracer) = &self.tracer else { return };
+        let threads = tracer.threads();
+        self.trace_thread = match self.trace_thread {
+            None => threads.first().copied(),
+            Some(tid) => threads.iter().copied().find(|&next| next > tid),
+        };
+        self.trace_scroll = 0;
+    }
+
+    /// Starts limiters on the marked processes, or on `pid` when none are marked.
+    fn start_cpu_limit(&mut self, pid: i32, target: f64, children: bool) -> Option<Modal> {
+        if !self.marked.is_empty() {
+            let processes = self.marked_processes();
+            let results = processes
+                .into_iter()
+                .map(|process| {
+                    let result = self.limiters.start(process.pid, target, children, Bypass::None).map(|_| ());
+                    (process.pid, process.command, result)
+                })
+                .collect();
+            return Some(Modal::BatchResult { title: format!("Limit to {}% CPU", target), results });
+        }
+        self.status = Some(match self.limiters.start(pid, target, children, Bypass::None) {
+            Ok(_) => format!("Limiting PID {} to {}% CPU{}", pid, target, if children { " with its children" } else { "" }),
+            Err(err) => err,
+        });
+        None
+    }
+
+    /// Removes the selected limiter; its processes are continued before it goes.
+    fn remove_limiter(&mut self) {
+        let limiters = self.limiters.list();
+        let Some(limiter) = limiters.get(self.limiter_row) else { return };
+        if self.limiters.remove(limiter.id) {
+            self.status = Some(format!("Removed CPU limit of {} ({})", limiter.pid, limiter.command));
+        }
+        self.limiter_row = self.limiter_row.min(limiters.len().saturating_sub(2));
+    }
+
+    /// Fires whatever scheduled actions are due and reports them on the status line.
+    fn run_scheduled(&mut self) {
+        let ran = self.schedule.run_due();
+        if ran.is_empty() {
+            return;
+        }
+        self.status = Some(ran.iter()
+            .map(|(action, result)| match result {
+                Ok(()) => format!("Scheduled {} of {} ({}) done", action.action, action.pid, action.command),
+                Err(err) => format!("Scheduled {} of {} failed: {}", action.action, action.pid, err),
+            })
+            .collect::<Vec<_>>()
+            .join("; "));
+        self.scheduled_row = self.scheduled_row.min(self.schedule.pending().len().saturating_sub(1));
+    }
+
+    /// Checks the watchdog rules against the latest snapshot, once a second at
+    /// most, and reports firings on the status line. Each one is also in the
+    /// Actions tab through the audit log.
+    fn run_watchdog(&mut self) {
+        if self.watchdog.rules().is_empty() || self.watchdog_run.is_some_and(|run| run.elapsed() < Duration::from_secs(1)) {
+            return;
+        }
+        self.watchdog_run = Some(Instant::now());
+        let processes = self.process_data.lock().unwrap().clone();
+        let events = self.watchdog.evaluate(&processes);
+        if !events.is_empty() {
+            self.status = Some(events.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "));
+        }
+    }
+
+    /// Starts, restarts and health-checks the supervised services and reports
+    /// what happened on the status line.
+    fn run_supervisor(&mut self) {
+        let events = self.supervisor.tick(&mut self.launcher);
+        if !events.is_empty() {
+            self.status = Some(events.join("; "));
+        }
+    }
+
+    /// The marked processes that are still listed, in display order.
+    fn marked_processes(&self) -> Vec<Process> {
+        let data = self.process_data.lock().unwrap();
+        visible_processes(&data, self.sort)
+            .into_iter()
+            .filter(|process| self.marked.contains(&(process.pid, process.starttime)))
+            .cloned()
+            .collect()
+    }
+
+    /// Drops marks on processes that exited, including ones whose PID now
+    /// belongs to another process.
+    fn prune_marks(&mut self) {
+        if !self.marked.is_empty() {
+            self.marked.retain(|&(pid, starttime)| is_running(pid, starttime));
+        }
+    }
+
+    /// Runs `action` on every marked process and returns the summary dialog.
+    fn run_batch(&self, title: &str, action: impl Fn(i32) -> std::result::Result<(), String>) -> Option<Modal> {
+        let results = self.marked_processes()
+            .into_iter()
+            .map(|process| (process.pid, process.command, action(process.pid)))
+            .collect();
+        Some(Modal::BatchResult { title: title.to_string(), results })
+    }
+
+    pub fn toggle_mark(&mut self) {
+        let Some(process) = self.selected_process() else { return };
+        let key = (process.pid, process.starttime);
+        if !self.marked.remove(&key) {
+            self.marked.insert(key);
+        }
+        self.move_cursor_down();
+    }
+
+    /// Marks every process the Processes tab currently lists.
+    pub fn mark_all(&mut self) {
+        let data = self.process_data.lock().unwrap();
+        self.marked.extend(visible_processes(&data, self.sort).iter().map(|process| (process.pid, process.starttime)));
+    }
+
+    pub fn invert_marks(&mut self) {
+        let data = self.process_data.lock().unwrap();
+        self.marked = visible_processes(&data, self.sort)
+            .iter()
+            .map(|process| (process.pid, process.starttime))
+            .filter(|key| !self.marked.contains(key))
+            .collect();
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
     }
 
     pub fn curse(&mut self) {
@@ -276,8 +1507,41 @@ impl App {
         self.selected_tab = self.selected_tab.previous();
     }
 
+    /// Quits, unless processes ProcMaster suspended are still stopped: those are
+    /// resumed first or warned about, depending on `suspend.on_quit`.
     pub fn quit(&mut self) {
-        self.state = AppState::Quitting;
+        // Limited processes are continued when the limiters are dropped.
+        let mut processes: Vec<SuspendedProcess> = suspended::load()
+            .into_iter()
+            .filter(|process| process.method != suspended::SuspendMethod::Limit)
+            .collect();
+        if !processes.is_empty() && config::current().suspend.on_quit == OnQuit::Resume {
+            self.resume_all();
+            processes.clear();
+        }
+        let pending = self.schedule.pending().len();
+        if processes.is_empty() && pending == 0 {
+            self.state = AppState::Quitting;
+        } else {
+            self.modal = Some(Modal::QuitWarning { processes, pending });
+        }
+    }
+
+    /// Resumes everything ProcMaster stopped or froze, ending the CPU limiters
+    /// first so they do not stop their processes again.
+    pub fn resume_all(&mut self) {
+        self.limiters.remove_all();
+        let results = resume_suspended();
+        let failed: Vec<String> = results.iter()
+            .filter_map(|(process, result)| result.as_ref().err().map(|err| format!("{}: {}", process.pid, err)))
+            .collect();
+        self.status = Some(if results.is_empty() {
+            "No processes suspended by ProcMaster".to_string()
+        } else if failed.is_empty() {
+            format!("Resumed {} processes", results.len())
+        } else {
+            format!("Resumed {} of {} processes; failed {}", results.len() - failed.len(), results.len(), failed.join(", "))
+        });
     }
 }
 
@@ -285,16 +1549,559 @@ impl App {
 impl Widget for &App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         use Constraint::{Length, Min};
-        let vertical = Layout::vertical([Length(1), Min(0), Length(1)]);
-        let [header_area, inner_area, footer_area] = vertical.areas(area);
+        let hung_height = self.hung.hung().len().min(HUNG_LINES) as u16;
+        let vertical = Layout::vertical([Length(1), Length(hung_height), Min(0), Length(1), Length(1)]);
+        let [header_area, hung_area, inner_area, status_area, footer_area] = vertical.areas(area);
 
         let horizontal = Layout::horizontal([Min(0), Length(20)]);
         let [tabs_area, title_area] = horizontal.areas(header_area);
 
         render_title(title_area, buf);
         self.render_tabs(tabs_area, buf);
+        render_hung_tasks(hung_area, buf, &self.hung);
         self.selected_tab.render(inner_area, buf, self); 
-        render_footer(footer_area, buf, self.selected_tab, self.is_cursed, self.is_priority);
+        render_footer(footer_area, buf, self.selected_tab, self.is_cursed);
+        if self.modal.as_ref().is_some_and(Modal::applies_to_marked) && !self.marked.is_empty() {
+            Line::raw(format!("Applies to the {} marked processes", self.marked.len()))
+                .centered()
+                .fg(Color::Magenta)
+                .render(status_area, buf);
+        } else if let Some(status) = &self.status {
+            Line::raw(status.as_str()).centered().render(status_area, buf);
+        }
+        if let Some(modal) = &self.modal {
+            modal.render(inner_area, buf);
+        }
+    }
+}
+
+/// Warns about threads stuck in D state, one line each, the longest first.
+fn render_hung_tasks(area: Rect, buf: &mut Buffer, hung: &HungTasks) {
+    let tasks = hung.hung();
+    let shown = if tasks.len() > HUNG_LINES { HUNG_LINES - 1 } else { tasks.len() };
+    let mut lines: Vec<Line> = tasks[..shown].iter().map(|task| Line::raw(format!("Hung: {}", task.describe()))).collect();
+    if shown < tasks.len() {
+        lines.push(Line::raw(format!(
+            "and {} more threads in D state for over {}",
+            tasks.len() - shown,
+            format_seconds(hung.threshold().as_secs())
+        )));
+    }
+    Paragraph::new(lines)
+        .style(Style::default().fg(Color::White).bg(Color::Red).bold())
+        .render(area, buf);
+}
+
+/// Returns a rectangle of the given size centered inside `area`.
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(ratatui::layout::Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(ratatui::layout::Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+fn parse_oom_score_adj(input: &str) -> std::result::Result<i32, String> {
+    match input.parse::<i32>() {
+        Ok(value) if (-1000..=1000).contains(&value) => Ok(value),
+        Ok(_) => Err("oom_score_adj must be between -1000 and 1000".to_string()),
+        Err(_) => Err("Enter a number between -1000 and 1000".to_string()),
+    }
+}
+
+fn parse_nice(input: &str) -> std::result::Result<i32, String> {
+    match input.parse::<i32>() {
+        Ok(nice) if (-20..=19).contains(&nice) => Ok(nice),
+        Ok(_) => Err("Nice must be between -20 and 19".to_string()),
+        Err(_) => Err("Enter a number between -20 and 19".to_string()),
+    }
+}
+
+/// Cycles the renice target: the process, then its process group, then its owner.
+fn next_priority_target(pid: i32, target: PriorityTarget) -> PriorityTarget {
+    let proc = procfs::process::Process::new(pid);
+    match target {
+        PriorityTarget::Process(_) => match proc.as_ref().ok().and_then(|p| p.stat().ok()) {
+            Some(stat) => PriorityTarget::ProcessGroup(stat.pgrp),
+            None => target,
+        },
+        PriorityTarget::ProcessGroup(_) => match proc.as_ref().ok().and_then(|p| p.uid().ok()) {
+            Some(uid) => PriorityTarget::User(uid),
+            None => PriorityTarget::Process(pid),
+        },
+        PriorityTarget::User(_) => PriorityTarget::Process(pid),
+    }
+}
+
+fn describe_target(target: PriorityTarget) -> String {
+    match target {
+        PriorityTarget::Process(pid) => format!("PID {}", pid),
+        PriorityTarget::ProcessGroup(pgid) => format!("process group {}", pgid),
+        PriorityTarget::User(uid) => match users::get_user_by_uid(uid) {
+            Some(user) => format!("user {}", user.name().to_string_lossy()),
+            None => format!("uid {}", uid),
+        },
+    }
+}
+
+impl Modal {
+    /// Whether confirming this dialog acts on the marked processes rather than
+    /// only on the one it was opened for.
+    fn applies_to_marked(&self) -> bool {
+        matches!(
+            self,
+            Modal::BatchConfirm { .. }
+                | Modal::Schedule { .. }
+                | Modal::CpuLimit { .. }
+                | Modal::Renice { target: PriorityTarget::Process(_), .. }
+                | Modal::Affinity { .. }
+                | Modal::IoPriority { .. }
+                | Modal::Cgroup { moving: Some(_), .. }
+        )
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        match self {
+            Modal::Killall { input, error } => {
+                let area = popup_area(area, 70, 30);
+                Clear.render(area, buf);
+                let mut lines = vec![
+                    Line::raw(format!("killall> {}_", input)),
+                    Line::raw(""),
+                    Line::raw("-r regex | -g glob | -f full cmdline | -x exe path | -u USER | -o AGE (e.g. 1h)"),
+                    Line::raw("-S STATE | -s SIGNAL (default TERM) | -n dry run"),
+                    Line::raw("Enter to preview matches, Esc to cancel"),
+                ];
+                if let Some(error) = error {
+                    lines.push(Line::raw(error.as_str()).fg(Color::Red));
+                }
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title("Kill processes by pattern"))
+                    .render(area, buf);
+            }
+            Modal::Launch { input, error } => {
+                let area = popup_area(area, 70, 30);
+                Clear.render(area, buf);
+                let mut lines = vec![
+                    Line::raw(format!("run> {}_", input)),
+                    Line::raw(""),
+                    Line::raw("-d DIR | -e KEY=VALUE | -n NICE | -i IOPRIO (e.g. be/7) | -c CPUS (e.g. 0-3)"),
+                    Line::raw("-l LIMIT=SOFT[:HARD] (e.g. nofile=1024) | -g CGROUP | then the command, run with sh -c"),
+                    Line::raw("Enter to start, Esc to cancel"),
+                ];
+                if let Some(error) = error {
+                    lines.push(Line::raw(error.as_str()).fg(Color::Red));
+                }
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title("Run a command"))
+                    .render(area, buf);
+            }
+            Modal::AddService { input, error } => {
+                let area = popup_area(area, 70, 30);
+                Clear.render(area, buf);
+                let mut lines = vec![
+                    Line::raw(format!("service> {}_", input)),
+                    Line::raw(""),
+                    Line::raw("name=NAME restart=always|on-failure|never max_restarts=N backoff=1s,5s,30s"),
+                    Line::raw("health_check=\"COMMAND\" health_interval=30s autostart=false -- then the run spec (as for N)"),
+                    Line::raw("Enter to add and save to the config file, Esc to cancel"),
+                ];
+                if let Some(error) = error {
+                    lines.push(Line::raw(error.as_str()).fg(Color::Red));
+                }
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title("Add a supervised service"))
+                    .render(area, buf);
+            }
+            Modal::KillallPreview { options, matches } => {
+                let area = popup_area(area, 80, 60);
+                Clear.render(area, buf);
+                let protected = matches.iter().filter(|m| m.protected.is_some()).count();
+                let title = if options.dry_run {
+                    format!("{} matches (dry run) | Esc to close", matches.len())
+                } else if protected > 0 {
+                    format!("{} matches, {} protected | y to send {} to the rest | F to force all | Esc to cancel",
+                        matches.len(), protected, options.signal)
+                } else {
+                    format!("{} matches | y to send {} | Esc to cancel", matches.len(), options.signal)
+                };
+                let rows: Vec<Row> = matches.iter().map(|m| {
+                    let row = Row::new(vec![
+                        Cell::from(m.pid.to_string()),
+                        Cell::from(m.user.clone()),
+                        Cell::from(m.state.to_string()),
+                        Cell::from(m.command.clone()),
+                        Cell::from(m.protected.clone().unwrap_or_default()),
+                        Cell::from(m.signal_warning.clone().unwrap_or_default()),
+                    ]);
+                    if m.protected.is_some() || m.signal_warning.is_some() { row.fg(Color::Yellow) } else { row }
+                }).collect();
+                let widths = [Constraint::Length(8), Constraint::Length(10), Constraint::Length(6), Constraint::Min(0), Constraint::Min(0), Constraint::Min(0)];
+                Table::new(rows, widths)
+                    .header(Row::new(vec!["PID", "User", "State", "Command", "Protected", "Warning"]))
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .render(area, buf);
+            }
+            Modal::Confirm { pid, command, user, action, protection, disposition, confirmed } => {
+                let area = popup_area(area, 60, 30);
+                Clear.render(area, buf);
+                let mut lines = vec![
+                    Line::raw(format!("PID:     {}", pid)),
+                    Line::raw(format!("Command: {}", command)),
+                    Line::raw(format!("User:    {}", user)),
+                    Line::raw(""),
+                ];
+                if let Some(disposition) = disposition {
+                    if let Some(warning) = action.warning(disposition) {
+                        lines.push(Line::raw(warning).fg(Color::Yellow));
+                    } else if let Some(signal) = action.signal().filter(|&signal| disposition.catches(signal)) {
+                        lines.push(Line::raw(format!("It handles {} itself and may take a while to exit, or not exit", signal)));
+                    }
+                }
+                match protection {
+                    Protection::Unprotected => lines.push(Line::raw("y to proceed, Esc to cancel")),
+                    Protection::Confirm(reason) => {
+                        lines.push(Line::raw(format!("Protected: {}", reason)).fg(Color::Yellow));
+                        lines.push(Line::raw(if *confirmed {
+                            "Press y again to really proceed, Esc to cancel"
+                        } else {
+                            "y twice to proceed, F to force, Esc to cancel"
+                        }));
+                    }
+                    Protection::Refuse(reason) => {
+                        lines.push(Line::raw(format!("Protected: {}", reason)).fg(Color::Red));
+                        lines.push(Line::raw("Refused. F to force anyway, Esc to cancel"));
+                    }
+                }
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title(format!("{}?", action.name())))
+                    .render(area, buf);
+            }
+            Modal::BatchConfirm { action, targets } => {
+                let area = popup_area(area, 70, 60);
+                Clear.render(area, buf);
+                let protected = targets.iter().filter(|(_, protection, _)| *protection != Protection::Unprotected).count();
+                let title = if protected > 0 {
+                    format!("{} {} processes, {} protected | y for the rest | F to force all | Esc to cancel",
+                        action.name(), targets.len(), protected)
+                } else {
+                    format!("{} {} processes | y to proceed | Esc to cancel", action.name(), targets.len())
+                };
+                let rows: Vec<Row> = targets.iter().map(|(process, protection, warning)| {
+                    let reason = match protection {
+                        Protection::Unprotected => String::new(),
+                        Protection::Confirm(reason) | Protection::Refuse(reason) => reason.clone(),
+                    };
+                    let row = Row::new(vec![
+                        Cell::from(process.pid.to_string()),
+                        Cell::from(process.user.clone()),
+                        Cell::from(process.command.clone()),
+                        Cell::from(reason),
+                        Cell::from(warning.clone().unwrap_or_default()),
+                    ]);
+                    if *protection == Protection::Unprotected && warning.is_none() { row } else { row.fg(Color::Yellow) }
+                }).collect();
+                let widths = [Constraint::Length(8), Constraint::Length(10), Constraint::Min(0), Constraint::Min(0), Constraint::Min(0)];
+                Table::new(rows, widths)
+                    .header(Row::new(vec!["PID", "User", "Command", "Protected", "Warning"]))
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .render(area, buf);
+            }
+            Modal::BatchResult { title, results } => {
+                let area = popup_area(area, 70, 60);
+                Clear.render(area, buf);
+                let failed = results.iter().filter(|(_, _, result)| result.is_err()).count();
+                let rows: Vec<Row> = results.iter().map(|(pid, command, result)| {
+                    let (outcome, color) = match result {
+                        Ok(()) => ("ok".to_string(), Color::Green),
+                        Err(err) => (err.clone(), Color::Red),
+                    };
+                    Row::new(vec![Cell::from(pid.to_string()), Cell::from(command.clone()), Cell::from(outcome).fg(color)])
+                }).collect();
+                let widths = [Constraint::Length(8), Constraint::Length(20), Constraint::Min(0)];
+                Table::new(rows, widths)
+                    .header(Row::new(vec!["PID", "Command", "Result"]))
+                    .block(Block::default().borders(Borders::ALL).title(format!(
+                        "{}: {} succeeded, {} failed | Esc to close",
+                        title,
+                        results.len() - failed,
+                        failed
+                    )))
+                    .render(area, buf);
+            }
+            Modal::Schedule { pid, command, input, error, protected } => {
+                let area = popup_area(area, 70, 30);
+                Clear.render(area, buf);
+                let mut lines = vec![
+                    Line::raw(format!("schedule> {}_", input)),
+                    Line::raw(""),
+                    Line::raw("term | kill | stop | freeze | cont | renice N, then in DELAY (30s, 10m, 1h) or at HH:MM"),
+                    Line::raw("e.g. \"cont in 10m\", \"term at 02:00\", \"renice 0 in 1h\""),
+                    Line::raw("Enter to schedule, Esc to cancel"),
+                ];
+                if let Some(error) = error {
+                    lines.push(Line::raw(error.as_str()).fg(Color::Red));
+                }
+                if let Some(protected) = protected {
+                    lines.push(Line::raw(format!("Protected: {}", protected)).fg(Color::Yellow));
+                    lines.push(Line::raw("y to schedule anyway | F to force past refusals | type to edit"));
+                }
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title(format!("Schedule an action on {} ({})", pid, command)))
+                    .render(area, buf);
+            }
+            Modal::CpuLimit { pid, command, input, children, error } => {
+                let area = popup_area(area, 60, 30);
+                Clear.render(area, buf);
+                let mut lines = vec![
+                    Line::raw(format!("Target CPU % (of one CPU, up to {}): {}_", 100 * cpu_count(), input)),
+                    Line::raw(format!("[{}] include children (Tab)", if *children { "x" } else { " " })),
+                    Line::raw(""),
+                    Line::raw("Stops and continues the process many times a second to hold it under the target."),
+                    Line::raw("Enter to start, Esc to cancel"),
+                ];
+                if let Some(error) = error {
+                    lines.push(Line::raw(error.as_str()).fg(Color::Red));
+                }
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title(format!("Limit CPU of {} ({})", pid, command)))
+                    .render(area, buf);
+            }
+            Modal::QuitWarning { processes, pending } => {
+                let area = popup_area(area, 60, 40);
+                Clear.render(area, buf);
+                let mut lines = Vec::new();
+                if *pending > 0 {
+                    lines.push(Line::raw(format!("{} scheduled actions are pending and will not run after quitting.", pending)).fg(Color::Yellow));
+                    lines.push(Line::raw(""));
+                }
+                if !processes.is_empty() {
+                    lines.push(Line::raw(format!("{} processes ProcMaster suspended are still stopped:", processes.len())));
+                    lines.push(Line::raw(""));
+                }
+                lines.extend(processes.iter().map(|process| {
+                    let method = match process.method {
+                        suspended::SuspendMethod::Signal => "stopped",
+                        suspended::SuspendMethod::Freeze => "frozen",
+                        suspended::SuspendMethod::Limit => "limited",
+                    };
+                    Line::raw(format!("  {} {} ({} since {})", process.pid, process.command, method, process.since))
+                }));
+                lines.push(Line::raw(""));
+                lines.push(Line::raw(if processes.is_empty() {
+                    "q to quit anyway | Esc to stay"
+                } else {
+                    "r to resume them and quit | q to quit anyway | Esc to stay"
+                }));
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title("Quit"))
+                    .render(area, buf);
+            }
+            Modal::Renice { pid, command, target, current, input } => {
+                let area = popup_area(area, 60, 30);
+                Clear.render(area, buf);
+                let current_text = current.map_or("unknown".to_string(), |nice| nice.to_string());
+                let validation = if input.is_empty() {
+                    Line::raw("")
+                } else {
+                    match parse_nice(input) {
+                        Err(err) => Line::raw(err).fg(Color::Red),
+                        Ok(nice) if current.is_some_and(|current| nice < current) && !may_lower_nice(nice) => {
+                            if helper::available() {
+                                Line::raw(format!("Lowering nice to {} needs CAP_SYS_NICE, procmaster-helper will be asked", nice))
+                                    .fg(Color::Yellow)
+                            } else {
+                                Line::raw(format!("Lowering nice to {} needs CAP_SYS_NICE, this will likely fail", nice))
+                                    .fg(Color::Yellow)
+                            }
+                        }
+                        Ok(_) => Line::raw("OK").fg(Color::Green),
+                    }
+                };
+                let lines = vec![
+                    Line::raw(format!("Target: {} (Tab: process / group / user)", describe_target(*target))),
+                    Line::raw(format!("Current nice: {}", current_text)),
+                    Line::raw(format!("New nice (-20 to 19): {}_", input)),
+                    validation,
+                    Line::raw("Enter to apply, Esc to cancel"),
+                ];
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title(format!("Renice {} ({})", pid, command)))
+                    .render(area, buf);
+            }
+            Modal::Affinity { pid, command, current, cpus, cursor, all_threads } => {
+                let area = popup_area(area, 70, 50);
+                Clear.render(area, buf);
+                let mut lines = vec![
+                    Line::raw(format!("Current mask: {}", current)),
+                    Line::raw(format!("Apply to: {} (t to toggle)", if *all_threads { "all threads" } else { "main thread only" })),
+                    Line::raw(""),
+                ];
+                for (row, chunk) in cpus.chunks(AFFINITY_COLUMNS).enumerate() {
+                    let spans: Vec<Span> = chunk.iter().enumerate().map(|(column, &on)| {
+                        let cpu = row * AFFINITY_COLUMNS + column;
+                        let text = format!("[{}] {:<3} ", if on { 'x' } else { ' ' }, cpu);
+                        if cpu == *cursor {
+                            Span::styled(text, Style::default().fg(Color::Blue).bg(Color::LightGreen))
+                        } else {
+                            Span::raw(text)
+                        }
+                    }).collect();
+                    lines.push(Line::from(spans));
+                }
+                lines.push(Line::raw(""));
+                lines.push(Line::raw("Arrows to move | Space to toggle | a all | n none | Enter to apply | Esc to cancel"));
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title(format!("CPU affinity of {} ({})", pid, command)))
+                    .render(area, buf);
+            }
+            Modal::IoPriority { pid, command, current, priority } => {
+                let area = popup_area(area, 60, 30);
+                Clear.render(area, buf);
+                let class = match priority.class {
+                    IoClass::Realtime => "realtime (needs CAP_SYS_ADMIN)",
+                    IoClass::BestEffort => "best-effort",
+                    IoClass::Idle => "idle (only gets disk time when nobody else wants it)",
+                    IoClass::None => "none (follow the nice value)",
+                };
+                let level = match priority.class {
+                    IoClass::Realtime | IoClass::BestEffort => format!("{} (0 highest, 7 lowest)", priority.level),
+                    IoClass::Idle | IoClass::None => "-".to_string(),
+                };
+                let lines = vec![
+                    Line::raw(format!("Current: {}", current)),
+                    Line::raw(format!("Class: {}", class)),
+                    Line::raw(format!("Level: {}", level)),
+                    Line::raw(""),
+                    Line::raw("← → to change class | ↑ ↓ to change level | Enter to apply | Esc to cancel"),
+                ];
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title(format!("I/O priority of {} ({})", pid, command)))
+                    .render(area, buf);
+            }
+            Modal::Cgroup { pid, command, info, selected, input, moving } => {
+                let area = popup_area(area, 70, 50);
+                Clear.render(area, buf);
+                let usage = format!(
+                    "Usage: memory {} | CPU {} | {} tasks",
+                    info.memory_current.map_or("-".to_string(), |bytes| format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))),
+                    info.cpu_usage_usec.map_or("-".to_string(), |usec| format!("{:.1} s", usec as f64 / 1_000_000.0)),
+                    info.pids_current.map_or("-".to_string(), |count| count.to_string()),
+                );
+                let mut lines = vec![
+                    Line::raw(format!("Cgroup: {}", info.path)),
+                    Line::raw(usage),
+                    Line::raw(""),
+                ];
+                for (index, (limit, value)) in info.limits.iter().enumerate() {
+                    let text = format!("{:<12} {}", limit.file_name(), value.as_deref().unwrap_or("(controller not enabled)"));
+                    if index == *selected {
+                        lines.push(Line::raw(text).fg(Color::Blue).bg(Color::LightGreen));
+                    } else {
+                        lines.push(Line::raw(text));
+                    }
+                }
+                lines.push(Line::raw(""));
+                if let Some(text) = input {
+                    let limit = CgroupLimit::ALL[*selected];
+                    lines.push(Line::raw(format!("{} = {}_", limit, text)));
+                    lines.push(Line::raw(limit.hint()));
+                    if let Err(err) = limit.validate(text) {
+                        lines.push(Line::raw(err.to_string()).fg(Color::Red));
+                    }
+                    lines.push(Line::raw("Enter to apply, Esc to cancel"));
+                } else if let Some(text) = moving {
+                    lines.push(Line::raw(format!("Move into cgroup: {}_", text)));
+                    lines.push(Line::raw("Created if it does not exist. Enter to move, Esc to cancel"));
+                } else {
+                    lines.push(Line::raw("↑ ↓ to select | Enter to edit | m to move to another cgroup | Esc to close"));
+                }
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title(format!("Cgroup of {} ({})", pid, command)))
+                    .render(area, buf);
+            }
+            Modal::Signals { pid, command, disposition } => {
+                let area = popup_area(area, 70, 40);
+                Clear.render(area, buf);
+                let list = |signals: &[String]| if signals.is_empty() { "none".to_string() } else { signals.join(" ") };
+                let lines = vec![
+                    Line::raw(format!("Pending: {}", list(&disposition.pending))),
+                    Line::raw(format!("Blocked: {}", list(&disposition.blocked))),
+                    Line::raw(format!("Ignored: {}", list(&disposition.ignored))),
+                    Line::raw(format!("Caught:  {}", list(&disposition.caught))),
+                    Line::raw(""),
+                    Line::raw("Blocked signals stay pending until a thread unblocks them, ignored ones are dropped,"),
+                    Line::raw("caught ones run the process's own handler. SIGKILL and SIGSTOP always get through."),
+                    Line::raw("Esc to close"),
+                ];
+                Paragraph::new(lines)
+                    .wrap(Wrap { trim: false })
+                    .block(Block::default().borders(Borders::ALL).title(format!("Signals of {} ({})", pid, command)))
+                    .render(area, buf);
+            }
+            Modal::OomScoreAdj { pid, command, score, current, input } => {
+                let area = popup_area(area, 60, 30);
+                Clear.render(area, buf);
+                let validation = if input.is_empty() {
+                    Line::raw("")
+                } else {
+                    match parse_oom_score_adj(input) {
+                        Err(err) => Line::raw(err).fg(Color::Red),
+                        Ok(value) if value < *current && !has_cap_sys_resource() => {
+                            if helper::available() {
+                                Line::raw(format!("Lowering below {} needs CAP_SYS_RESOURCE, procmaster-helper will be asked", current))
+                                    .fg(Color::Yellow)
+                            } else {
+                                Line::raw(format!("Lowering below {} needs CAP_SYS_RESOURCE, this will likely fail", current))
+                                    .fg(Color::Yellow)
+                            }
+                        }
+                        Ok(_) => Line::raw("OK").fg(Color::Green),
+                    }
+                };
+                let lines = vec![
+                    Line::raw(format!("OOM score: {} | current oom_score_adj: {}", score, current)),
+                    Line::raw("-1000 never kill, 0 default, 1000 kill first"),
+                    Line::raw(format!("New oom_score_adj: {}_", input)),
+                    validation,
+                    Line::raw("Enter to apply, Esc to cancel"),
+                ];
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title(format!("OOM adjustment of {} ({})", pid, command)))
+                    .render(area, buf);
+            }
+            Modal::Scheduler { pid, command, current, policy, priority, all_threads, confirm_realtime } => {
+                let area = popup_area(area, 60, 35);
+                Clear.render(area, buf);
+                let description = match policy {
+                    SchedPolicy::Other => "default time-sharing",
+                    SchedPolicy::Batch => "CPU-bound batch work, fewer wakeup preemptions",
+                    SchedPolicy::Idle => "only runs when nothing else wants the CPU",
+                    SchedPolicy::Fifo => "real-time, runs until it blocks or yields",
+                    SchedPolicy::RoundRobin => "real-time with time slices",
+                };
+                let range = match policy.priority_range() {
+                    Ok((min, max)) => format!("{} to {}", min, max),
+                    Err(_) => "unknown".to_string(),
+                };
+                let mut lines = vec![
+                    Line::raw(format!("Current: {}", current)),
+                    Line::raw(format!("Policy: {} ({})", policy, description)),
+                    Line::raw(format!("Priority: {} (range {})", priority, range)),
+                    Line::raw(format!("Apply to: {} (t to toggle)", if *all_threads { "all threads" } else { "main thread only" })),
+                    Line::raw(""),
+                ];
+                if *confirm_realtime {
+                    lines.push(Line::raw("A real-time task that spins can lock up this CPU. Press Enter again to confirm.").fg(Color::Red));
+                } else if policy.is_realtime() {
+                    lines.push(Line::raw("Real-time policy: you will be asked to confirm.").fg(Color::Yellow));
+                }
+                lines.push(Line::raw("← → to change policy | ↑ ↓ to change priority | Enter to apply | Esc to cancel"));
+                Paragraph::new(lines)
+                    .block(Block::default().borders(Borders::ALL).title(format!("Scheduling policy of {} ({})", pid, command)))
+                    .render(area, buf);
+            }
+        }
     }
 }
 
@@ -316,22 +2123,55 @@ fn render_title(area: Rect, buf: &mut Buffer) {
     "ProcMaster".bold().render(area, buf);
 }
 
-fn render_footer(area: Rect, buf: &mut Buffer, selected_tab: SelectedTab, cursor:bool, priority:bool) {
+fn render_footer(area: Rect, buf: &mut Buffer, selected_tab: SelectedTab, cursor:bool) {
     if cursor && selected_tab == SelectedTab::Tab1 {
-        if priority {
-            // If we're in the priority change mode, show a different message
-            Line::raw("Enter new priority (1-20): Press Enter to confirm, Esc to cancel.")
-                .centered()
-                .render(area, buf);
-        } else {
-            Line::raw("← → to change tab | Press q to quit | Press c to cursor | ↑ ↓ to move | k to kill | t to terminate | s to suspend | r to resume | p to set priority")
-                .centered()
-                .render(area, buf);
-        }
+        Line::raw("← → to change tab | Press q to quit | Press c to cursor | ↑ ↓ to move | k to kill | t to terminate | s to suspend | f to freeze | r to resume | p to set priority | a to set affinity | i to set I/O priority | P to set scheduling policy | o to set OOM adjustment | g for cgroup limits | O to sort by OOM score | K to killall | N to run | T to schedule | L to limit CPU | S to trace syscalls | W to sample waits | I for signals | R to resume all | space to mark | + mark all | * invert | - clear marks")
+            .centered()
+            .render(area, buf);
     }
     else if selected_tab == SelectedTab::Tab1 {
         
-        Line::raw("← → to change tab | Press q to quit | Press c to cursor")
+        Line::raw("← → to change tab | Press q to quit | Press c to cursor | O to sort by OOM score | K to killall | N to run | R to resume all | + mark all | * invert | - clear marks")
+        .centered()
+        .render(area, buf);
+    }
+    else if selected_tab == SelectedTab::Tab11 {
+        Line::raw("← → to change tab | Press q to quit | ↑ ↓ to select a parent | c to send it SIGCHLD | t to terminate it")
+        .centered()
+        .render(area, buf);
+    }
+    else if selected_tab == SelectedTab::Tab10 {
+        Line::raw("← → to change tab | Press q to quit | ↑ ↓ to select | r to reset | d to stop | W on a process to sample")
+        .centered()
+        .render(area, buf);
+    }
+    else if selected_tab == SelectedTab::Tab9 {
+        Line::raw("← → to change tab | Press q to quit | ↑ ↓ to scroll | t to show one thread | d to detach | S on a process to trace")
+        .centered()
+        .render(area, buf);
+    }
+    else if selected_tab == SelectedTab::Tab8 {
+        Line::raw("← → to change tab | Press q to quit | ↑ ↓ to select | a to add | s to start | x to stop | r to restart | d to delete")
+        .centered()
+        .render(area, buf);
+    }
+    else if selected_tab == SelectedTab::Tab7 {
+        Line::raw("← → to change tab | Press q to quit | ↑ ↓ to select | N to run a command | x to clear finished")
+        .centered()
+        .render(area, buf);
+    }
+    else if selected_tab == SelectedTab::Tab6 {
+        Line::raw("← → to change tab | Press q to quit | ↑ ↓ to select | x to remove (continues the process) | L on a process to limit")
+        .centered()
+        .render(area, buf);
+    }
+    else if selected_tab == SelectedTab::Tab5 {
+        Line::raw("← → to change tab | Press q to quit | ↑ ↓ to select | x to cancel | T on a process to schedule")
+        .centered()
+        .render(area, buf);
+    }
+    else if selected_tab == SelectedTab::Tab4 {
+        Line::raw("← → to change tab | Press q to quit | ↑ ↓ to scroll")
         .centered()
         .render(area, buf);
     }
@@ -346,9 +2186,17 @@ impl SelectedTab {
     fn render(self, area: Rect, buf: &mut Buffer, app: &App) {
 
         match self {
-            Self::Tab1 => render_processes(area, buf, app.selected_row, app.is_cursed,app.process_data.clone(), app.vertical_scroll),
-            Self::Tab2 => render_cpu(area, buf),
+            Self::Tab1 => render_processes(area, buf, app),
+            Self::Tab2 => render_cpu(area, buf, &app.cpu_usage.lock().unwrap()),
             Self::Tab3 => render_memory(area, buf, app.memory_usage.clone(), app.disk_usage.clone()),
+            Self::Tab4 => render_actions(area, buf, app.vertical_scroll),
+            Self::Tab5 => render_scheduled(area, buf, &app.schedule, app.scheduled_row),
+            Self::Tab6 => render_limiters(area, buf, &app.limiters, app.limiter_row),
+            Self::Tab7 => render_launched(area, buf, &app.launcher, app.launched_row),
+            Self::Tab8 => render_supervisor(area, buf, &app.supervisor, app.service_row),
+            Self::Tab9 => render_trace(area, buf, app),
+            Self::Tab10 => render_sampler(area, buf, app.sampler.as_ref(), app.sample_row),
+            Self::Tab11 => render_zombies(area, buf, &app.zombies, app.zombie_row),
         }
     }
 
@@ -364,6 +2212,14 @@ impl SelectedTab {
             Self::Tab1 => tailwind::BLUE,
             Self::Tab2 => tailwind::EMERALD,
             Self::Tab3 => tailwind::INDIGO,
+            Self::Tab4 => tailwind::AMBER,
+            Self::Tab5 => tailwind::ROSE,
+            Self::Tab6 => tailwind::CYAN,
+            Self::Tab7 => tailwind::LIME,
+            Self::Tab8 => tailwind::FUCHSIA,
+            Self::Tab9 => tailwind::TEAL,
+            Self::Tab10 => tailwind::SKY,
+            Self::Tab11 => tailwind::ORANGE,
         }
     }
 
@@ -380,33 +2236,422 @@ impl SelectedTab {
     }
 }
 
-fn render_processes(area: Rect, buf: &mut Buffer, selected_row: usize, is_cursed: bool, processes: Arc<Mutex<Vec<Process>>>, vertical_scroll: usize) {
-    let data = processes.lock().unwrap();
+/// Processes started with `N`, above the captured output of the selected one.
+fn render_launched(area: Rect, buf: &mut Buffer, launcher: &Launcher, selected_row: usize) {
+    let [list_area, output_area] = Layout::vertical([Constraint::Length(10), Constraint::Min(0)]).areas(area);
+    let rows: Vec<Row> = launcher.list().iter().enumerate().map(|(index, launched)| {
+        let status = launched.status();
+        let status_color = if launched.is_running() { Color::Green } else { Color::DarkGray };
+        let row = Row::new(vec![
+            Cell::from(launched.pid.to_string()),
+            Cell::from(format_local_time(launched.started)),
+            Cell::from(status).fg(status_color),
+            Cell::from(launched.command.clone()),
+        ]);
+        if index == selected_row { row.fg(Color::Blue).bg(Color::LightGreen).bold() } else { row }
+    }).collect();
+    let widths = [Constraint::Length(8), Constraint::Length(10), Constraint::Length(20), Constraint::Min(20)];
+    Table::new(rows, widths)
+        .header(Row::new(vec!["PID", "Started", "Status", "Command"]).bold())
+        .block(Block::default().borders(Borders::ALL).title(format!("Launched ({})", launcher.list().len())))
+        .render(list_area, buf);
 
-    let filtered_data: Vec<&Process> = data.iter()
-        .filter(|process| process.user != "root")
+    let Some(launched) = launcher.list().get(selected_row) else {
+        Paragraph::new("Nothing launched yet, press N to run a command")
+            .block(Block::default().borders(Borders::ALL).title("Output"))
+            .render(output_area, buf);
+        return;
+    };
+    // Show the tail, like `tail -f`.
+    let output = launched.output();
+    let height = output_area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = output[output.len().saturating_sub(height)..].iter()
+        .map(|line| if line.stderr { Line::raw(line.text.clone()).fg(Color::Red) } else { Line::raw(line.text.clone()) })
         .collect();
+    Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(format!("Output of {} (stderr in red)", launched.pid)))
+        .render(output_area, buf);
+}
+
+/// The live syscall log of the traced process, the calls its threads are in
+/// right now, and per-syscall totals.
+fn render_trace(area: Rect, buf: &mut Buffer, app: &App) {
+    let Some(tracer) = &app.tracer else {
+        let text = match &app.trace_error {
+            Some(err) => Line::raw(err.as_str()).fg(Color::Red),
+            None => Line::raw("Nothing traced, press S on a process in the Processes tab"),
+        };
+        Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("Trace")).render(area, buf);
+        return;
+    };
+    let in_syscall = tracer.in_syscall();
+    let [blocked_area, main_area] = Layout::vertical([
+        Constraint::Length((in_syscall.len() as u16).clamp(1, 6) + 2),
+        Constraint::Min(0),
+    ]).areas(area);
+    let [log_area, summary_area] = Layout::horizontal([Constraint::Min(40), Constraint::Length(52)]).areas(main_area);
+
+    let blocked: Vec<Line> = in_syscall.iter()
+        .map(|call| {
+            let line = Line::raw(format!("[{}] {:.1}s in {}", call.tid, call.seconds, call.call));
+            if call.seconds >= 1.0 { line.fg(Color::Yellow) } else { line }
+        })
+        .collect();
+    Paragraph::new(blocked)
+        .block(Block::default().borders(Borders::ALL).title("In a syscall now"))
+        .render(blocked_area, buf);
+
+    let log: Vec<_> = tracer.log().into_iter()
+        .filter(|line| app.trace_thread.is_none_or(|tid| line.tid == tid))
+        .collect();
+    let height = log_area.height.saturating_sub(2) as usize;
+    let end = log.len().saturating_sub(app.trace_scroll);
+    let lines: Vec<Line> = log[end.saturating_sub(height)..end].iter()
+        .map(|line| {
+            let text = Line::raw(format!("{:>8.3} [{}] {}", line.time, line.tid, line.text));
+            if line.error { text.fg(Color::Red) } else { text }
+        })
+        .collect();
+    let mut title = format!("Syscalls of {} ({}), {} threads", tracer.pid, tracer.command, tracer.threads().len());
+    if let Some(tid) = app.trace_thread {
+        title.push_str(&format!(", showing {}", tid));
+    }
+    if let Some(ended) = tracer.ended() {
+        title.push_str(&format!(" | {}", ended));
+    }
+    Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .render(log_area, buf);
+
+    let summary = tracer.summary();
+    let total: f64 = summary.iter().map(|syscall| syscall.seconds).sum();
+    let rows: Vec<Row> = summary.iter().map(|syscall| {
+        Row::new(vec![
+            Cell::from(syscall.name.clone()),
+            Cell::from(syscall.calls.to_string()),
+            Cell::from(syscall.errors.to_string()).fg(if syscall.errors > 0 { Color::Red } else { Color::Reset }),
+            Cell::from(format!("{:.3}", syscall.seconds)),
+            Cell::from(format!("{:.1}%", if total > 0.0 { syscall.seconds / total * 100.0 } else { 0.0 })),
+        ])
+    }).collect();
+    let widths = [Constraint::Length(16), Constraint::Length(7), Constraint::Length(7), Constraint::Length(9), Constraint::Length(7)];
+    Table::new(rows, widths)
+        .header(Row::new(vec!["Syscall", "Calls", "Errors", "Time (s)", "Time"]).bold())
+        .block(Block::default().borders(Borders::ALL).title("Summary"))
+        .render(summary_area, buf);
+}
+
+/// Where the sampled process's threads were seen, most often first, and the
+/// kernel stack of the selected place.
+fn render_sampler(area: Rect, buf: &mut Buffer, sampler: Option<&Sampler>, selected_row: usize) {
+    let Some(sampler) = sampler else {
+        Paragraph::new("Nothing sampled, press W on a process in the Processes tab")
+            .block(Block::default().borders(Borders::ALL).title("Sampler"))
+            .render(area, buf);
+        return;
+    };
+    let [sites_area, stack_area] = Layout::vertical([Constraint::Percentage(60), Constraint::Min(0)]).areas(area);
+    let sites = sampler.histogram();
+    let rows: Vec<Row> = sites.iter().enumerate().map(|(index, site)| {
+        let state_color = match site.state {
+            'D' => Color::Red,
+            'R' => Color::Green,
+            _ => Color::Reset,
+        };
+        let row = Row::new(vec![
+            Cell::from(site.samples.to_string()),
+            Cell::from(format!("{:.1}%", site.percent)),
+            Cell::from(site.threads.to_string()),
+            Cell::from(site.state.to_string()).fg(state_color),
+            Cell::from(if site.wchan.is_empty() { "-".to_string() } else { site.wchan.clone() }),
+            Cell::from(site.syscall.clone().unwrap_or_else(|| "-".to_string())),
+            Cell::from(site.stack.first().cloned().unwrap_or_default()),
+        ]);
+        if index == selected_row { row.fg(Color::Blue).bg(Color::LightGreen).bold() } else { row }
+    }).collect();
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Length(7),
+        Constraint::Length(8),
+        Constraint::Length(6),
+        Constraint::Length(28),
+        Constraint::Length(18),
+        Constraint::Min(20),
+    ];
+    let mut title = format!(
+        "Where {} ({}) waits: {} samples every {}ms",
+        sampler.pid,
+        sampler.command,
+        sampler.rounds(),
+        sampler.interval.as_millis()
+    );
+    if let Some(ended) = sampler.ended() {
+        title.push_str(&format!(" | {}", ended));
+    }
+    Table::new(rows, widths)
+        .header(Row::new(vec!["Samples", "Share", "Threads", "State", "Wait channel", "Syscall", "Innermost frame"]).bold())
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .render(sites_area, buf);
+
+    let mut lines: Vec<Line> = match sites.get(selected_row) {
+        Some(site) if !site.stack.is_empty() => site.stack.iter().map(|frame| Line::raw(frame.clone())).collect(),
+        Some(_) if !sampler.has_stacks() => vec![Line::raw("Kernel stacks can only be read as root").fg(Color::DarkGray)],
+        _ => Vec::new(),
+    };
+    if !sampler.has_syscalls() {
+        lines.push(Line::raw("Current syscalls could not be read: not permitted for this process").fg(Color::DarkGray));
+    }
+    let stack_title = sites.get(selected_row).map_or("Kernel stack".to_string(), |site| format!("Kernel stack of {}", site.describe()));
+    Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(stack_title))
+        .render(stack_area, buf);
+}
+
+/// Zombies grouped under the parent that should reap them, and orphans adopted
+/// by init or a subreaper.
+fn render_zombies(area: Rect, buf: &mut Buffer, zombies: &Zombies, selected_row: usize) {
+    let report = zombies.report();
+    let [parents_area, orphans_area] = Layout::vertical([Constraint::Percentage(60), Constraint::Min(0)]).areas(area);
+    let age = |zombie: &zombies::Zombie| format!("{}{}", if zombie.at_least { ">= " } else { "" }, format_seconds(zombie.seconds));
+    let mut rows = Vec::new();
+    for (index, parent) in report.parents.iter().enumerate() {
+        let oldest = parent.zombies.first().map_or(String::new(), age);
+        let row = Row::new(vec![
+            Cell::from(parent.pid.to_string()),
+            Cell::from(parent.command.clone()),
+            Cell::from(parent.state.to_string()),
+            Cell::from(format!("{} zombies", parent.zombies.len())),
+            Cell::from(oldest),
+        ]);
+        rows.push(if index == selected_row { row.fg(Color::Blue).bg(Color::LightGreen).bold() } else { row.bold() });
+        for zombie in &parent.zombies {
+            rows.push(Row::new(vec![
+                Cell::from(format!("  {}", zombie.pid)),
+                Cell::from(format!("  {}", zombie.command)),
+                Cell::from("Z").fg(Color::Red),
+                Cell::from(""),
+                Cell::from(age(zombie)),
+            ]));
+        }
+    }
+    let title = match report.parents.get(selected_row) {
+        Some(parent) => format!("Zombies by parent | {}", parent.advice()),
+        None => "No zombies".to_string(),
+    };
+    let widths = [Constraint::Length(10), Constraint::Length(24), Constraint::Length(6), Constraint::Length(12), Constraint::Min(10)];
+    Table::new(rows, widths)
+        .header(Row::new(vec!["PID", "Command", "State", "Zombies", "Zombie for"]).bold())
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .render(parents_area, buf);
+
+    let rows: Vec<Row> = report.orphans.iter().map(|orphan| {
+        Row::new(vec![
+            Cell::from(orphan.pid.to_string()),
+            Cell::from(orphan.command.clone()),
+            Cell::from(format!("{} ({})", orphan.reaper, orphan.reaper_command)),
+            Cell::from(orphan.session.to_string()),
+        ])
+    }).collect();
+    let widths = [Constraint::Length(10), Constraint::Length(24), Constraint::Length(24), Constraint::Min(8)];
+    Table::new(rows, widths)
+        .header(Row::new(vec!["PID", "Command", "Adopted by", "Session"]).bold())
+        .block(Block::default().borders(Borders::ALL).title(format!("{} orphans reparented to init or a subreaper", report.orphans.len())))
+        .render(orphans_area, buf);
+}
+
+/// The supervised services with their state, restart count and health.
+fn render_supervisor(area: Rect, buf: &mut Buffer, supervisor: &Supervisor, selected_row: usize) {
+    let services = supervisor.status();
+    let rows: Vec<Row> = services.iter().enumerate().map(|(index, service)| {
+        let state_color = match service.state.as_str() {
+            "running" => Color::Green,
+            "stopped" => Color::DarkGray,
+            _ => Color::Yellow,
+        };
+        let health = service.health.clone().unwrap_or_else(|| "-".to_string());
+        let health_color = if health.starts_with("failing") { Color::Red } else { Color::Reset };
+        let row = Row::new(vec![
+            Cell::from(service.name.clone()),
+            Cell::from(service.state.clone()).fg(state_color),
+            Cell::from(service.pid.map(|pid| pid.to_string()).unwrap_or_default()),
+            Cell::from(service.restarts.to_string()),
+            Cell::from(service.last_exit.clone().unwrap_or_default()),
+            Cell::from(health).fg(health_color),
+            Cell::from(service.run.clone()),
+        ]);
+        if index == selected_row { row.fg(Color::Blue).bg(Color::LightGreen).bold() } else { row }
+    }).collect();
+    let widths = [
+        Constraint::Length(16),
+        Constraint::Length(24),
+        Constraint::Length(8),
+        Constraint::Length(9),
+        Constraint::Length(20),
+        Constraint::Length(14),
+        Constraint::Min(20),
+    ];
+    let title = if services.is_empty() {
+        "Services (none, press a to add one)".to_string()
+    } else {
+        format!("Services ({})", services.len())
+    };
+    Table::new(rows, widths)
+        .header(Row::new(vec!["Name", "Status", "PID", "Restarts", "Last exit", "Health", "Command"]).bold())
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .render(area, buf);
+}
+
+fn render_limiters(area: Rect, buf: &mut Buffer, limiters: &Limiters, selected_row: usize) {
+    let limiters = limiters.list();
+    let rows: Vec<Row> = limiters.iter().enumerate().map(|(index, limiter)| {
+        let usage_color = if limiter.usage > limiter.target * 1.1 { Color::Yellow } else { Color::Green };
+        let row = Row::new(vec![
+            Cell::from(limiter.pid.to_string()),
+            Cell::from(limiter.command.clone()),
+            Cell::from(format!("{:.0}%", limiter.target)),
+            Cell::from(format!("{:.1}%", limiter.usage)).fg(usage_color),
+            Cell::from(format!("{:.0}%", limiter.duty * 100.0)),
+            Cell::from(if limiter.children { format!("yes ({})", limiter.processes) } else { "no".to_string() }),
+        ]);
+        if index == selected_row { row.fg(Color::Blue).bg(Color::LightGreen).bold() } else { row }
+    }).collect();
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Length(20),
+        Constraint::Length(8),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Min(10),
+    ];
+    Table::new(rows, widths)
+        .header(Row::new(vec!["PID", "Command", "Target", "Measured", "Running", "Children"]).bold())
+        .block(Block::default().borders(Borders::ALL).title(format!("CPU limiters ({})", limiters.len())))
+        .render(area, buf);
+}
+
+/// Pending scheduled actions, soonest first, above the ones that already fired.
+fn render_scheduled(area: Rect, buf: &mut Buffer, schedule: &ActionQueue, selected_row: usize) {
+    let [pending_area, fired_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).areas(area);
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Length(12),
+        Constraint::Length(8),
+        Constraint::Length(16),
+        Constraint::Min(12),
+    ];
+
+    let rows: Vec<Row> = schedule.pending().iter().enumerate().map(|(index, action)| {
+        let row = Row::new(vec![
+            Cell::from(format_local_time(action.due)),
+            Cell::from(format!("in {}", format_remaining(action.due))),
+            Cell::from(action.pid.to_string()),
+            Cell::from(action.command.clone()),
+            Cell::from(action.action.to_string()),
+        ]);
+        if index == selected_row { row.fg(Color::Blue).bg(Color::LightGreen).bold() } else { row }
+    }).collect();
+    Table::new(rows, widths)
+        .header(Row::new(vec!["Due", "Remaining", "PID", "Command", "Action"]).bold())
+        .block(Block::default().borders(Borders::ALL).title(format!("Pending ({})", schedule.pending().len())))
+        .render(pending_area, buf);
+
+    let rows: Vec<Row> = schedule.fired().iter().map(|(action, result)| {
+        let (outcome, color) = match result {
+            Ok(()) => ("ok".to_string(), Color::Green),
+            Err(err) => (err.clone(), Color::Red),
+        };
+        Row::new(vec![
+            Cell::from(format_local_time(action.due)),
+            Cell::from(action.pid.to_string()),
+            Cell::from(action.command.clone()),
+            Cell::from(action.action.to_string()),
+            Cell::from(outcome).fg(color),
+        ])
+    }).collect();
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Length(16),
+        Constraint::Length(12),
+        Constraint::Min(0),
+    ];
+    Table::new(rows, widths)
+        .header(Row::new(vec!["Fired", "PID", "Command", "Action", "Result"]).bold())
+        .block(Block::default().borders(Borders::ALL).title("Recently fired"))
+        .render(fired_area, buf);
+}
+
+/// Control actions from the audit log, newest first.
+fn render_actions(area: Rect, buf: &mut Buffer, vertical_scroll: usize) {
+    let entries = audit::recent(500);
+    let max_visible_rows = (area.height as usize).saturating_sub(3);
+    let start_index = vertical_scroll.min(entries.len());
+    let rows: Vec<Row> = entries.iter().skip(start_index).take(max_visible_rows).map(|entry| {
+        let (outcome, color) = match &entry.error {
+            None => ("ok".to_string(), Color::Green),
+            Some(error) => (error.clone(), Color::Red),
+        };
+        Row::new(vec![
+            Cell::from(entry.timestamp.clone()),
+            Cell::from(entry.user.clone()),
+            Cell::from(entry.pid.map_or("-".to_string(), |pid| pid.to_string())),
+            Cell::from(entry.command.clone().unwrap_or_default()),
+            Cell::from(entry.operation.clone()),
+            Cell::from(entry.args.to_string()),
+            Cell::from(outcome).fg(color),
+        ])
+    }).collect();
+
+    let widths = [
+        Constraint::Length(30),
+        Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Length(16),
+        Constraint::Length(14),
+        Constraint::Min(20),
+        Constraint::Min(10),
+    ];
+    let title = format!("Actions ({})", config::current().audit.path().display());
+    Table::new(rows, widths)
+        .header(Row::new(vec!["Time", "User", "PID", "Command", "Operation", "Arguments", "Result"]).bold())
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .render(area, buf);
+}
+
+fn render_processes(area: Rect, buf: &mut Buffer, app: &App) {
+    let (selected_row, is_cursed, vertical_scroll, sort, marked) =
+        (app.selected_row, app.is_cursed, app.vertical_scroll, app.sort, &app.marked);
+    let data = app.process_data.lock().unwrap();
+
+    let filtered_data = visible_processes(&data, sort);
    
     let max_visible_rows = (area.height as usize) - 2;
-    let start_index = vertical_scroll;
+    let start_index = vertical_scroll.min(filtered_data.len());
     let end_index = std::cmp::min(start_index + max_visible_rows, filtered_data.len()); 
     let rows: Vec<Row> = filtered_data[start_index..end_index].iter().enumerate().map(|(index, process)|
     {   
         let global_index = start_index + index;
         let is_selected = global_index == selected_row;
+        let is_marked = marked.contains(&(process.pid, process.starttime));
         let style = if is_selected && is_cursed {
             Style::default()
                 .fg(Color::Blue).bold()  
                 .bg(Color::LightGreen)  
                  
+        } else if is_marked {
+            Style::default().fg(Color::Magenta).bold()
         } else {
             Style::default() 
         };
         
         Row::new(vec![
-            Cell::from(process.pid.to_string()).style(style),        
+            Cell::from(if is_marked { format!("+{}", process.pid) } else { process.pid.to_string() }).style(style),
             Cell::from(process.user.clone()).style(style),
-            Cell::from(process.command.clone()).style(style),
+            if app.launcher.is_launched(process.pid) {
+                Cell::from(format!("▸{}", process.command)).style(style.fg(Color::LightCyan))
+            } else {
+                Cell::from(process.command.clone()).style(style)
+            },
             Cell::from(format!("{:.2} MB", process.v_memory)).style(style),
             Cell::from(format!("{:.2} MB", process.rss_memory)).style(style),
             Cell::from(format!("{:.2} MB", process.shared_memory)).style(style),
@@ -416,8 +2661,19 @@ fn render_processes(area: Rect, buf: &mut Buffer, selected_row: usize, is_cursed
             Cell::from(process.priority.to_string()).style(style),
             Cell::from(process.nice.to_string()).style(style),
             Cell::from(process.ppid.to_string()).style(style),
-            Cell::from(process.state.clone()).style(style),
+            if process.suspended_by_procmaster {
+                Cell::from(format!("{}*", state_label(process))).style(style.fg(Color::Yellow))
+            } else if app.hung.of(process.pid).is_some() {
+                Cell::from(format!("{} hung", state_label(process))).style(style.fg(Color::Red).bold())
+            } else if process.state == "Z" {
+                Cell::from(state_label(process)).style(style.fg(Color::Red))
+            } else {
+                Cell::from(state_label(process)).style(style)
+            },
             Cell::from(process.threads.to_string()).style(style),
+            Cell::from(process.io_priority.clone()).style(style),
+            Cell::from(process.oom_score.to_string()).style(style),
+            Cell::from(process.oom_score_adj.to_string()).style(style),
         ])
     }).collect();
 
@@ -436,6 +2692,9 @@ fn render_processes(area: Rect, buf: &mut Buffer, selected_row: usize, is_cursed
         Constraint::Length(10),
         Constraint::Length(10),
         Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Length(10),
+        Constraint::Length(8),
     ];
 
     let table = Table::new(rows, widths)
@@ -454,29 +2713,57 @@ fn render_processes(area: Rect, buf: &mut Buffer, selected_row: usize, is_cursed
             Cell::from("Parent PID"),
             Cell::from("State"),
             Cell::from("Threads"),
+            Cell::from("IO Prio"),
+            Cell::from(if sort == SortKey::OomScore { "OOM Score ↓" } else { "OOM Score" }),
+            Cell::from("OOM Adj"),
         ]))
-        .block(Block::default().borders(Borders::ALL).title("Processes"))
+        .block(Block::default().borders(Borders::ALL).title(if marked.is_empty() {
+            "Processes (* suspended by ProcMaster, R to resume all)".to_string()
+        } else {
+            format!("Processes ({} marked, - to clear)", marked.len())
+        }))
         .widths(&widths);
 
     table.render(area, buf);
 }
 
 
-fn render_cpu(area: Rect, buf: &mut Buffer) {
-    let cpu_usages: Vec<CpuUsage> = cpu_result();
-        
-    let gauges: Vec<Gauge> = cpu_usages.iter().map(|cpu_usage| {
-        let percent_value = cpu_usage.cpu_usage as u16;
-        let label = format!("{:.1}%", cpu_usage.cpu_usage);
-        let gauge_color = calculate_gauge_color(percent_value);
-
-        Gauge::default()
-            .block(Block::default().title(format!("CPU {} Usage", cpu_usage.core_number)).borders(Borders::ALL))
-            .gauge_style(gauge_color)
-            .percent(percent_value as u16)
-            .label(label)
-            .set_style(Style::default().fg(gaugeTextColor))
-    }).collect();
+/// Spells out the two kinds of suspension, which look alike in the raw state.
+fn state_label(process: &Process) -> String {
+    if process.frozen {
+        format!("{} frozen", process.state)
+    } else if process.state == "T" {
+        "T stopped".to_string()
+    } else if process.state == "Z" {
+        "Z zombie".to_string()
+    } else {
+        process.state.clone()
+    }
+}
+
+fn cpu_gauge(title: String, usage: f64) -> Gauge<'static> {
+    let percent_value = usage.clamp(0.0, 100.0) as u16;
+    Gauge::default()
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .gauge_style(calculate_gauge_color(percent_value))
+        .percent(percent_value)
+        .label(format!("{:.1}%", usage))
+        .set_style(Style::default().fg(gaugeTextColor))
+}
+
+fn render_cpu(area: Rect, buf: &mut Buffer, cpu_usage: &CpuUsages) {
+    if cpu_usage.cores.is_empty() {
+        Paragraph::new("Measuring CPU usage...").render(area, buf);
+        return;
+    }
+    let [total_area, area] = Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+    cpu_gauge("All CPUs".to_string(), cpu_usage.total).render(total_area, buf);
+
+    let gauges: Vec<Gauge> = cpu_usage.cores.iter()
+        .map(|core| cpu_gauge(format!("CPU {} Usage", core.core_number), core.cpu_usage))
+        .collect();
+    // The left column takes the extra gauge of an odd count.
+    let left_count = gauges.len().div_ceil(2);
 
     // Split the area into two columns
     let columns = Layout::default()
@@ -485,8 +2772,8 @@ fn render_cpu(area: Rect, buf: &mut Buffer) {
         .split(area);
 
     // Split each column into rows for the gauges
-    let left_column_constraints: Vec<Constraint> = vec![Constraint::Length((gauges.len() / 2) as u16); gauges.len() / 2];
-    let right_column_constraints: Vec<Constraint> = vec![Constraint::Length((gauges.len() / 2) as u16); gauges.len() / 2];
+    let left_column_constraints: Vec<Constraint> = vec![Constraint::Length((gauges.len() / 2) as u16); left_count];
+    let right_column_constraints: Vec<Constraint> = vec![Constraint::Length((gauges.len() / 2) as u16); gauges.len() - left_count];
 
     let left_column_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -499,12 +2786,12 @@ fn render_cpu(area: Rect, buf: &mut Buffer) {
         .split(columns[1]);
 
     // Render the gauges in the left column
-    for (i, gauge) in gauges.iter().take(gauges.len() / 2).enumerate() {
+    for (i, gauge) in gauges.iter().take(left_count).enumerate() {
         gauge.render(left_column_chunks[i], buf);
     }
 
     // Render the gauges in the right column
-    for (i, gauge) in gauges.iter().skip(gauges.len() / 2).enumerate() {
+    for (i, gauge) in gauges.iter().skip(left_count).enumerate() {
         gauge.render(right_column_chunks[i], buf);
     }
 }
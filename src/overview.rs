@@ -2,6 +2,10 @@ use procfs::process::all_processes;
 use procfs::{ticks_per_second,Uptime};
 use sysinfo::{System, SystemExt};
 use users::get_user_by_uid;
+use crate::ctrl::{get_io_priority, get_oom_score_adj};
+use crate::cgroup::CgroupFs;
+use crate::suspended;
+use std::collections::HashMap;
 use std::fmt::Write;
 use std::thread;
 use std::sync::{Arc, Mutex};
@@ -23,6 +27,12 @@ pub struct ProcessInfo {
     ppid: i32,
     state: String,
     threads: i64,
+    io_priority: String,
+    oom_score: u32,
+    oom_score_adj: i32,
+    frozen: bool,
+    suspended_by_procmaster: bool,
+    starttime: u64,
 }
 
 fn seconds_to_hhmmss(seconds: f64) -> String {
@@ -44,6 +54,10 @@ pub fn get_processes_info() -> Vec<ProcessInfo> {
             let system_uptime = Uptime::new().unwrap().uptime; 
             // let total_cpu_time: f64 = stat().unwrap().cpu().cpu_time() as f64 / ticks_per_second;
             let total_memory = system.total_memory();
+            let cgroups = CgroupFs::system();
+            // Many processes share a cgroup, so each one's freezer state is read once.
+            let mut frozen_cgroups: HashMap<String, bool> = HashMap::new();
+            let suspended = suspended::load();
 
             for process in processes {
                 if let Ok(proc) = process {
@@ -92,6 +106,17 @@ pub fn get_processes_info() -> Vec<ProcessInfo> {
                         let ppid = stat.ppid; 
                         let state = stat.state.to_string(); 
                         let threads = stat.num_threads; 
+                        let io_priority = get_io_priority(pid).map(|p| p.to_string()).unwrap_or_default();
+                        let oom_score = proc.oom_score().unwrap_or(0);
+                        let oom_score_adj = get_oom_score_adj(pid).unwrap_or(0);
+                        let frozen = match cgroups.cgroup_of(pid) {
+                            Ok(cgroup) => *frozen_cgroups
+                                .entry(cgroup)
+                                .or_insert_with_key(|cgroup| cgroups.is_frozen(cgroup)),
+                            Err(_) => false,
+                        };
+                        let suspended_by_procmaster = suspended.iter()
+                            .any(|entry| entry.pid == pid && entry.starttime == stat.starttime);
 
                         let time = seconds_to_hhmmss(proc_usage_time);
 
@@ -110,6 +135,12 @@ pub fn get_processes_info() -> Vec<ProcessInfo> {
                             ppid,
                             state,
                             threads,
+                            io_priority,
+                            oom_score,
+                            oom_score_adj,
+                            frozen,
+                            suspended_by_procmaster,
+                            starttime: stat.starttime,
                         });
                 }
             }
@@ -139,6 +170,16 @@ pub struct Process {
     pub ppid: i32,
     pub state: String,
     pub threads: i64,
+    pub io_priority: String,
+    pub oom_score: u32,
+    pub oom_score_adj: i32,
+    /// Suspended through the cgroup freezer rather than SIGSTOP.
+    pub frozen: bool,
+    /// Stopped or frozen by ProcMaster, see [`crate::suspended`].
+    pub suspended_by_procmaster: bool,
+    /// In clock ticks after boot; with the PID it tells this process apart
+    /// from a later one that reuses the PID.
+    pub starttime: u64,
 }
 
 impl From<&ProcessInfo> for Process {
@@ -158,6 +199,12 @@ impl From<&ProcessInfo> for Process {
             ppid: info.ppid,
             state: info.state.clone(),
             threads: info.threads,
+            io_priority: info.io_priority.clone(),
+            oom_score: info.oom_score,
+            oom_score_adj: info.oom_score_adj,
+            frozen: info.frozen,
+            suspended_by_procmaster: info.suspended_by_procmaster,
+            starttime: info.starttime,
         }
     }
 }
@@ -174,7 +221,7 @@ pub fn print_process() -> String {
     for process in processes_info {
         let _ = write!(
             output,
-            "PID: {} | User: {} | Command: {} | Virtual Memory: {:.2} MB | RSS Memory: {:.2} MB | Shared Memory: {:.2} MB | Memory Usage: {:.2}% | CPU Usage: {:.2}% | Time: {} | Priority: {} | Nice: {} | Parent PID: {} | State: {} | Threads: {}\n",
+            "PID: {} | User: {} | Command: {} | Virtual Memory: {:.2} MB | RSS Memory: {:.2} MB | Shared Memory: {:.2} MB | Memory Usage: {:.2}% | CPU Usage: {:.2}% | Time: {} | Priority: {} | Nice: {} | Parent PID: {} | State: {} | Threads: {} | IO Priority: {} | OOM Score: {} | OOM Adj: {}\n",
             process.pid,
             process.user,
             process.command,
@@ -188,7 +235,10 @@ pub fn print_process() -> String {
             process.nice,
             process.ppid,
             process.state,
-            process.threads
+            process.threads,
+            process.io_priority,
+            process.oom_score,
+            process.oom_score_adj
         );
     }
 
@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::syscalls;
+
+/// Time between two looks at every thread.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(100);
+/// Innermost kernel frames that tell two stacks apart.
+const STACK_DEPTH: usize = 12;
+
+/// One place threads were seen at, with how often.
+#[derive(Clone, Debug, Serialize)]
+pub struct WaitSite {
+    /// Scheduler state letter from `stat`: R, S, D, T, ...
+    pub state: char,
+    /// Kernel function the thread sleeps in, from `wchan`; empty while running.
+    pub wchan: String,
+    /// Syscall the thread is in, from `/proc/PID/task/TID/syscall`. `None`
+    /// when it is running, not in a syscall (e.g. a page fault) or unreadable.
+    pub syscall: Option<String>,
+    /// Kernel stack, innermost frame first, without offsets. Empty unless
+    /// `/proc/PID/task/TID/stack` could be read, which takes root.
+    pub stack: Vec<String>,
+    pub samples: u64,
+    /// Share of all thread samples, in percent.
+    pub percent: f64,
+    /// Distinct threads seen here.
+    pub threads: usize,
+}
+
+impl WaitSite {
+    /// One-line description, like `D io_schedule (read)`.
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} {}", self.state, if self.wchan.is_empty() { "running" } else { &self.wchan });
+        if let Some(syscall) = &self.syscall {
+            text.push_str(&format!(" ({})", syscall));
+        }
+        text
+    }
+}
+
+#[derive(Default)]
+struct Site {
+    samples: u64,
+    threads: HashSet<i32>,
+}
+
+#[derive(Default)]
+struct SampleState {
+    sites: HashMap<(char, String, Option<String>, Vec<String>), Site>,
+    rounds: u64,
+    thread_samples: u64,
+    /// Whether a kernel stack, and a syscall, could ever be read.
+    stacks: bool,
+    syscalls: bool,
+    ended: Option<String>,
+}
+
+/// Reads the state, `wchan`, current syscall and kernel stack of every thread
+/// of a process at a fixed interval and counts where they were. Unlike tracing
+/// it never stops the process, so it also works on ones stuck in D state.
+pub struct Sampler {
+    pub pid: i32,
+    pub command: String,
+    pub interval: Duration,
+    state: Arc<Mutex<SampleState>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Sampler {
+    pub fn start(pid: i32, interval: Duration) -> Result<Sampler, String> {
+        let stat = procfs::process::Process::new(pid)
+            .and_then(|process| process.stat())
+            .map_err(|err| format!("Failed to read process {}: {}", pid, err))?;
+        let state = Arc::new(Mutex::new(SampleState::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let state = Arc::clone(&state);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    if !sample(pid, stat.starttime, &state) {
+                        state.lock().unwrap().ended = Some("The process exited".to_string());
+                        return;
+                    }
+                    thread::sleep(interval);
+                }
+            })
+        };
+        Ok(Sampler { pid, command: stat.comm, interval, state, stop, thread: Some(thread) })
+    }
+
+    /// Where the threads were, most samples first.
+    pub fn histogram(&self) -> Vec<WaitSite> {
+        let state = self.state.lock().unwrap();
+        let total = state.thread_samples.max(1) as f64;
+        let mut sites: Vec<WaitSite> = state.sites.iter()
+            .map(|((thread_state, wchan, syscall, stack), site)| WaitSite {
+                state: *thread_state,
+                wchan: wchan.clone(),
+                syscall: syscall.clone(),
+                stack: stack.clone(),
+                samples: site.samples,
+                percent: site.samples as f64 / total * 100.0,
+                threads: site.threads.len(),
+            })
+            .collect();
+        sites.sort_by(|a, b| b.samples.cmp(&a.samples).then_with(|| a.wchan.cmp(&b.wchan)));
+        sites
+    }
+
+    /// How many times the threads were looked at.
+    pub fn rounds(&self) -> u64 {
+        self.state.lock().unwrap().rounds
+    }
+
+    /// Whether kernel stacks could be read; without root they cannot.
+    pub fn has_stacks(&self) -> bool {
+        self.state.lock().unwrap().stacks
+    }
+
+    /// Whether current syscalls could be read, which needs ptrace access.
+    pub fn has_syscalls(&self) -> bool {
+        self.state.lock().unwrap().syscalls
+    }
+
+    /// Starts counting from scratch.
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.sites.clear();
+        state.rounds = 0;
+        state.thread_samples = 0;
+    }
+
+    pub fn ended(&self) -> Option<String> {
+        self.state.lock().unwrap().ended.clone()
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Takes one sample of every thread. Returns false once the process is gone,
+/// or its PID belongs to another process now.
+fn sample(pid: i32, starttime: u64, state: &Mutex<SampleState>) -> bool {
+    let Ok(process) = procfs::process::Process::new(pid) else { return false };
+    if !process.stat().is_ok_and(|stat| stat.starttime == starttime) {
+        return false;
+    }
+    let Ok(tasks) = fs::read_dir(format!("/proc/{}/task", pid)) else { return false };
+    let mut seen = Vec::new();
+    for tid in tasks.filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<i32>().ok()) {
+        let dir = format!("/proc/{}/task/{}", pid, tid);
+        // A thread that exited between listing and reading is skipped.
+        let Some(thread_state) = fs::read_to_string(format!("{}/stat", dir)).ok().as_deref().and_then(stat_state) else {
+            continue;
+        };
+        let wchan = fs::read_to_string(format!("{}/wchan", dir))
+            .map(|wchan| wchan.trim().to_string())
+            .ok()
+            .filter(|wchan| wchan != "0")
+            .unwrap_or_default();
+        let syscall = fs::read_to_string(format!("{}/syscall", dir));
+        let stack = fs::read_to_string(format!("{}/stack", dir));
+        seen.push((tid, thread_state, wchan, syscall, stack));
+    }
+
+    let mut state = state.lock().unwrap();
+    state.rounds += 1;
+    for (tid, thread_state, wchan, syscall, stack) in seen {
+        state.syscalls |= syscall.is_ok();
+        state.stacks |= stack.is_ok();
+        let syscall = syscall.ok().as_deref().and_then(current_syscall);
+        let stack = stack.map(|stack| stack_frames(&stack)).unwrap_or_default();
+        state.thread_samples += 1;
+        let site = state.sites.entry((thread_state, wchan, syscall, stack)).or_default();
+        site.samples += 1;
+        site.threads.insert(tid);
+    }
+    true
+}
+
+/// The state letter of a `stat` line, which follows the parenthesized comm.
+fn stat_state(stat: &str) -> Option<char> {
+    stat.rsplit_once(')')?.1.trim_start().chars().next()
+}
+
+/// Name of the syscall in a `syscall` file: `NR ARGS... SP PC` while in one,
+/// `-1 SP PC` when blocked outside a syscall and `running` otherwise.
+fn current_syscall(text: &str) -> Option<String> {
+    let nr: i64 = text.split_whitespace().next()?.parse().ok()?;
+    let nr = u64::try_from(nr).ok()?;
+    Some(syscalls::native_name(nr).map(str::to_string).unwrap_or_else(|| format!("syscall_{}", nr)))
+}
+
+/// Function names of a kernel stack, e.g. `[<0>] futex_wait_queue+0x60/0x90`
+/// becomes `futex_wait_queue`.
+fn stack_frames(stack: &str) -> Vec<String> {
+    stack.lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|frame| frame.split('+').next().unwrap_or(frame).to_string())
+        .take(STACK_DEPTH)
+        .collect()
+}
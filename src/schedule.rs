@@ -0,0 +1,245 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::ctrl::{self, Bypass, Guard, PriorityTarget};
+
+/// What a scheduled action does when it fires. Each one maps onto a `ctrl` call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum PlannedAction {
+    Terminate,
+    Kill,
+    Suspend,
+    Freeze,
+    Resume,
+    Renice(i32),
+}
+
+impl PlannedAction {
+    /// Whether the action goes through the protected-process [`Guard`].
+    pub fn is_guarded(&self) -> bool {
+        matches!(self, PlannedAction::Terminate | PlannedAction::Kill | PlannedAction::Suspend | PlannedAction::Freeze)
+    }
+}
+
+impl fmt::Display for PlannedAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlannedAction::Terminate => write!(f, "terminate"),
+            PlannedAction::Kill => write!(f, "kill"),
+            PlannedAction::Suspend => write!(f, "suspend"),
+            PlannedAction::Freeze => write!(f, "freeze"),
+            PlannedAction::Resume => write!(f, "resume"),
+            PlannedAction::Renice(nice) => write!(f, "renice {}", nice),
+        }
+    }
+}
+
+/// A parsed `<action> [nice] (in <delay> | at <HH:MM[:SS]>)` spec, e.g.
+/// `resume in 10m`, `term at 02:00` or `renice 0 in 1h`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduleSpec {
+    pub action: PlannedAction,
+    /// Unix time in seconds.
+    pub due: i64,
+}
+
+impl FromStr for ScheduleSpec {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut words = text.split_whitespace();
+        let action = match words.next() {
+            Some("term" | "terminate") => PlannedAction::Terminate,
+            Some("kill") => PlannedAction::Kill,
+            Some("stop" | "suspend") => PlannedAction::Suspend,
+            Some("freeze") => PlannedAction::Freeze,
+            Some("cont" | "resume") => PlannedAction::Resume,
+            Some("renice") => {
+                let nice = words.next().ok_or("renice needs a nice value")?;
+                PlannedAction::Renice(nice.parse().map_err(|_| format!("Invalid nice value {}", nice))?)
+            }
+            Some(other) => return Err(format!("Unknown action {}", other)),
+            None => return Err("Expected an action, e.g. \"resume in 10m\"".to_string()),
+        };
+        let due = match (words.next(), words.next()) {
+            (Some("in"), Some(delay)) => now() + ctrl::parse_age(delay)? as i64,
+            (Some("at"), Some(clock)) => next_local_time(clock)?,
+            _ => return Err("Expected \"in <delay>\" or \"at <HH:MM>\"".to_string()),
+        };
+        if let Some(extra) = words.next() {
+            return Err(format!("Unexpected {}", extra));
+        }
+        Ok(ScheduleSpec { action, due })
+    }
+}
+
+/// A pending action on one process. `starttime` is recorded when it is
+/// scheduled so that a different process reusing the PID is never hit.
+#[derive(Clone, Debug, Serialize)]
+pub struct ScheduledAction {
+    pub id: u64,
+    pub pid: i32,
+    pub starttime: u64,
+    pub command: String,
+    pub action: PlannedAction,
+    pub due: i64,
+    /// How the user vouched for a protected target when scheduling, since no
+    /// one may be around to confirm when it fires.
+    pub bypass: Bypass,
+}
+
+impl ScheduledAction {
+    /// Runs the action if `pid` is still the process it was scheduled for.
+    pub fn run(&self) -> Result<(), String> {
+        let stat = procfs::process::Process::new(self.pid)
+            .and_then(|proc| proc.stat())
+            .map_err(|_| format!("PID {} ({}) has exited", self.pid, self.command))?;
+        if stat.starttime != self.starttime {
+            return Err(format!("PID {} now belongs to another process ({}), skipped", self.pid, stat.comm));
+        }
+        match self.action {
+            PlannedAction::Terminate => ctrl::terminate_process(self.pid, self.bypass),
+            PlannedAction::Kill => ctrl::kill_process(self.pid, self.bypass),
+            PlannedAction::Suspend => ctrl::suspend_process(self.pid, self.bypass),
+            PlannedAction::Freeze => ctrl::freeze_process(self.pid, self.bypass),
+            PlannedAction::Resume => ctrl::resume_process(self.pid),
+            PlannedAction::Renice(nice) => {
+                ctrl::set_priority(PriorityTarget::Process(self.pid), nice).map_err(|err| err.to_string())
+            }
+        }
+    }
+}
+
+/// Pending actions plus the outcome of the most recent ones that fired. The
+/// queue lives in memory: nothing fires while ProcMaster is not running.
+#[derive(Default)]
+pub struct ActionQueue {
+    next_id: u64,
+    pending: Vec<ScheduledAction>,
+    fired: Vec<(ScheduledAction, Result<(), String>)>,
+}
+
+/// How many fired actions [`ActionQueue::fired`] keeps.
+const FIRED_HISTORY: usize = 20;
+
+impl ActionQueue {
+    /// Queues `spec` for `pid`. A protected target is checked now, with
+    /// `bypass`, rather than failing unattended when the action fires.
+    pub fn schedule(&mut self, pid: i32, spec: ScheduleSpec, bypass: Bypass) -> Result<u64, String> {
+        let stat = procfs::process::Process::new(pid)
+            .and_then(|proc| proc.stat())
+            .map_err(|err| format!("Failed to read process {}: {}", pid, err))?;
+        if spec.action.is_guarded() {
+            Guard::load().allow(pid, bypass)?;
+        }
+        self.next_id += 1;
+        self.pending.push(ScheduledAction {
+            id: self.next_id,
+            pid,
+            starttime: stat.starttime,
+            command: stat.comm,
+            action: spec.action,
+            due: spec.due,
+            bypass,
+        });
+        self.pending.sort_by_key(|action| action.due);
+        Ok(self.next_id)
+    }
+
+    pub fn cancel(&mut self, id: u64) -> Option<ScheduledAction> {
+        let index = self.pending.iter().position(|action| action.id == id)?;
+        Some(self.pending.remove(index))
+    }
+
+    /// Pending actions, soonest first.
+    pub fn pending(&self) -> &[ScheduledAction] {
+        &self.pending
+    }
+
+    /// The last actions that fired, most recent first.
+    pub fn fired(&self) -> &[(ScheduledAction, Result<(), String>)] {
+        &self.fired
+    }
+
+    /// Runs every action that is due and returns them with their outcomes.
+    pub fn run_due(&mut self) -> Vec<(ScheduledAction, Result<(), String>)> {
+        let now = now();
+        let split = self.pending.partition_point(|action| action.due <= now);
+        let ran: Vec<_> = self.pending.drain(..split).map(|action| {
+            let result = action.run();
+            (action, result)
+        }).collect();
+        for entry in &ran {
+            self.fired.insert(0, entry.clone());
+        }
+        self.fired.truncate(FIRED_HISTORY);
+        ran
+    }
+}
+
+pub fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// Formats a Unix time as local `HH:MM:SS`.
+pub fn format_local_time(time: i64) -> String {
+    let tm = local_tm(time);
+    format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+/// Formats how long until `due`, e.g. `9m 58s`.
+pub fn format_remaining(due: i64) -> String {
+    format_seconds((due - now()).max(0) as u64)
+}
+
+/// Formats a number of seconds, e.g. `9m 58s` or `2h 5m`.
+pub fn format_seconds(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m {}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+fn local_tm(time: i64) -> libc::tm {
+    let time = time as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&time, &mut tm) };
+    tm
+}
+
+/// The next time the local clock shows `HH:MM` or `HH:MM:SS`, today or tomorrow.
+fn next_local_time(clock: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid time {}, expected HH:MM", clock);
+    let mut parts = clock.split(':').map(|part| part.parse::<i32>());
+    let hour = parts.next().and_then(Result::ok).filter(|hour| (0..24).contains(hour)).ok_or_else(invalid)?;
+    let minute = parts.next().and_then(Result::ok).filter(|minute| (0..60).contains(minute)).ok_or_else(invalid)?;
+    let second = match parts.next() {
+        Some(second) => second.ok().filter(|second| (0..60).contains(second)).ok_or_else(invalid)?,
+        None => 0,
+    };
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+
+    let now = now();
+    let mut tm = local_tm(now);
+    tm.tm_hour = hour;
+    tm.tm_min = minute;
+    tm.tm_sec = second;
+    // Let mktime work out whether DST applies on the target day.
+    tm.tm_isdst = -1;
+    let mut due = unsafe { libc::mktime(&mut tm) } as i64;
+    if due <= now {
+        tm.tm_mday += 1;
+        tm.tm_hour = hour;
+        tm.tm_min = minute;
+        tm.tm_sec = second;
+        tm.tm_isdst = -1;
+        due = unsafe { libc::mktime(&mut tm) } as i64;
+    }
+    Ok(due)
+}
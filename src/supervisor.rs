@@ -0,0 +1,447 @@
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use nix::sys::signal::{killpg, Signal};
+use nix::unistd::Pid;
+use serde::Serialize;
+
+use crate::config::{self, RestartPolicy, ServiceConfig, SupervisorConfig};
+use crate::ctrl::{self, Bypass};
+use crate::launch::{describe_exit, LaunchSpec, Launcher};
+
+/// Restart delays of a service that does not set `backoff`.
+const DEFAULT_BACKOFF: [u64; 3] = [1, 5, 30];
+const DEFAULT_HEALTH_INTERVAL: u64 = 30;
+/// Failed health checks in a row before the service is terminated.
+const HEALTH_FAILURES: u32 = 3;
+/// A run at least this long starts the backoff schedule over.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+/// How long a stopped service gets to exit after SIGTERM before it is killed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Parses the launch dialog form of a service,
+/// `name=worker restart=always max_restarts=5 backoff=1s,5s health_check="curl -sf localhost" -- ./worker`.
+///
+/// The keys are those of `[[supervisor.services]]`; everything after `--` is
+/// the run spec.
+impl FromStr for ServiceConfig {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (fields, run) = text.split_once(" -- ").ok_or("Expected KEY=VALUE fields, then -- and the command")?;
+        let mut service = ServiceConfig {
+            name: String::new(),
+            run: run.trim().to_string(),
+            restart: RestartPolicy::default(),
+            backoff: Vec::new(),
+            max_restarts: None,
+            health_check: None,
+            health_interval: None,
+            autostart: true,
+        };
+        let mut rest = fields.trim();
+        while !rest.is_empty() {
+            let (key, after) = rest.split_once('=').ok_or_else(|| format!("Expected KEY=VALUE at {}", rest))?;
+            let (value, after) = match after.strip_prefix('"') {
+                Some(quoted) => quoted.split_once('"').ok_or_else(|| format!("Unclosed quote in {}", key))?,
+                None => after.split_once(char::is_whitespace).unwrap_or((after, "")),
+            };
+            match key.trim() {
+                "name" => service.name = value.to_string(),
+                "restart" => {
+                    service.restart = match value {
+                        "always" => RestartPolicy::Always,
+                        "on-failure" => RestartPolicy::OnFailure,
+                        "never" => RestartPolicy::Never,
+                        _ => return Err(format!("Unknown restart policy {} (use always, on-failure or never)", value)),
+                    }
+                }
+                "backoff" => service.backoff = value.split(',').map(str::to_string).collect(),
+                "max_restarts" => {
+                    service.max_restarts = Some(value.parse().map_err(|_| format!("Invalid max_restarts {}", value))?)
+                }
+                "health_check" => service.health_check = Some(value.to_string()),
+                "health_interval" => service.health_interval = Some(value.to_string()),
+                "autostart" => service.autostart = value != "false",
+                other => return Err(format!("Unknown field {}", other)),
+            }
+            rest = after.trim_start();
+        }
+        if service.name.is_empty() {
+            return Err("A service needs name=...".to_string());
+        }
+        Ok(service)
+    }
+}
+
+enum State {
+    Stopped,
+    Running { pid: i32, since: Instant },
+    /// Sent SIGTERM and waiting for it to exit, with SIGKILL once
+    /// [`STOP_TIMEOUT`] has passed. With `restart` it is started again once
+    /// it is gone, so two instances never run at once.
+    Stopping { pid: i32, since: Instant, killed: bool, restart: bool },
+    /// Waiting to be started again.
+    Backoff(Instant),
+    /// Not restarted: its policy said so, or it hit `max_restarts`.
+    Finished(String),
+}
+
+struct HealthCheck {
+    command: String,
+    interval: Duration,
+    last_run: Instant,
+    running: Option<Child>,
+    failures: u32,
+    last_ok: Option<bool>,
+}
+
+struct Service {
+    config: ServiceConfig,
+    spec: LaunchSpec,
+    backoff: Vec<Duration>,
+    health: Option<HealthCheck>,
+    state: State,
+    restarts: u32,
+    /// Index into `backoff` of the next delay.
+    backoff_step: usize,
+    last_exit: Option<String>,
+}
+
+impl Service {
+    fn new(config: ServiceConfig) -> Result<Self, String> {
+        let context = |err: String| format!("Service \"{}\": {}", config.name, err);
+        let spec: LaunchSpec = config.run.parse().map_err(context)?;
+        let backoff = if config.backoff.is_empty() {
+            DEFAULT_BACKOFF.iter().map(|&seconds| Duration::from_secs(seconds)).collect()
+        } else {
+            config.backoff.iter()
+                .map(|delay| ctrl::parse_age(delay).map(Duration::from_secs))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(context)?
+        };
+        let health = match &config.health_check {
+            Some(command) => Some(HealthCheck {
+                command: command.clone(),
+                interval: Duration::from_secs(match &config.health_interval {
+                    Some(interval) => ctrl::parse_age(interval).map_err(context)?,
+                    None => DEFAULT_HEALTH_INTERVAL,
+                }),
+                last_run: Instant::now(),
+                running: None,
+                failures: 0,
+                last_ok: None,
+            }),
+            None => None,
+        };
+        let state = if config.autostart { State::Backoff(Instant::now()) } else { State::Stopped };
+        Ok(Service { config, spec, backoff, health, state, restarts: 0, backoff_step: 0, last_exit: None })
+    }
+
+    /// Decides what happens after the service ended with `reason`.
+    fn ended(&mut self, failed: bool, reason: String) -> String {
+        let name = &self.config.name;
+        let restart = match self.config.restart {
+            RestartPolicy::Always => true,
+            RestartPolicy::OnFailure => failed,
+            RestartPolicy::Never => false,
+        };
+        self.last_exit = Some(reason.clone());
+        if !restart {
+            self.state = State::Finished(reason.clone());
+            return format!("{} {}, not restarting", name, reason);
+        }
+        if self.config.max_restarts.is_some_and(|max| self.restarts >= max) {
+            self.state = State::Finished(format!("gave up after {} restarts", self.restarts));
+            return format!("{} {}, giving up after {} restarts", name, reason, self.restarts);
+        }
+        let delay = self.backoff[self.backoff_step.min(self.backoff.len() - 1)];
+        self.backoff_step += 1;
+        self.state = State::Backoff(Instant::now() + delay);
+        format!("{} {}, restarting in {}s", name, reason, delay.as_secs())
+    }
+
+    fn tick(&mut self, launcher: &mut Launcher, events: &mut Vec<String>) {
+        match self.state {
+            State::Backoff(at) if Instant::now() >= at => {
+                if self.last_exit.is_some() {
+                    self.restarts += 1;
+                }
+                match launcher.launch(&self.spec) {
+                    Ok(pid) => {
+                        if let Some(health) = &mut self.health {
+                            health.last_run = Instant::now();
+                            health.failures = 0;
+                        }
+                        self.state = State::Running { pid, since: Instant::now() };
+                        events.push(format!("Started {} as PID {}", self.config.name, pid));
+                    }
+                    Err(err) => events.push(self.ended(true, err)),
+                }
+            }
+            State::Running { pid, since } => {
+                let exit = match launcher.get(pid) {
+                    Some(launched) => launched.exit_status().map(|status| (!status.success(), describe_exit(status))),
+                    None => Some((true, "exited".to_string())),
+                };
+                if let Some((failed, reason)) = exit {
+                    if since.elapsed() >= STABLE_AFTER {
+                        self.backoff_step = 0;
+                    }
+                    self.kill_health_check();
+                    events.push(self.ended(failed, reason));
+                    return;
+                }
+                self.check_health(pid, events);
+            }
+            State::Stopping { pid, since, killed, restart } => {
+                if launcher.get(pid).is_none_or(|launched| launched.exit_status().is_some()) {
+                    if restart {
+                        self.reset();
+                    } else {
+                        self.state = State::Stopped;
+                    }
+                } else if !killed && since.elapsed() >= STOP_TIMEOUT {
+                    self.state = State::Stopping { pid, since, killed: true, restart };
+                    events.push(match ctrl::kill_process(pid, Bypass::None) {
+                        Ok(()) => format!("{} did not exit within {}s, killed", self.config.name, STOP_TIMEOUT.as_secs()),
+                        Err(err) => format!("{} did not exit within {}s: {}", self.config.name, STOP_TIMEOUT.as_secs(), err),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Starts the service afresh: due at once, with the restart count and
+    /// backoff schedule cleared.
+    fn reset(&mut self) {
+        self.restarts = 0;
+        self.backoff_step = 0;
+        self.last_exit = None;
+        self.state = State::Backoff(Instant::now());
+    }
+
+    /// Terminates the running process, which [`Service::tick`] then waits
+    /// for, and starts the service again afterwards with `restart`.
+    fn stop(&mut self, restart: bool) -> Result<(), String> {
+        self.kill_health_check();
+        match self.state {
+            State::Running { pid, since } => {
+                self.state = State::Stopping { pid, since: Instant::now(), killed: false, restart };
+                ctrl::terminate_process(pid, Bypass::None).inspect_err(|_| {
+                    self.state = State::Running { pid, since };
+                })
+            }
+            State::Stopping { pid, since, killed, .. } => {
+                self.state = State::Stopping { pid, since, killed, restart };
+                Ok(())
+            }
+            _ if restart => {
+                self.reset();
+                Ok(())
+            }
+            _ => {
+                self.state = State::Stopped;
+                Ok(())
+            }
+        }
+    }
+
+    fn kill_health_check(&mut self) {
+        if let Some(mut check) = self.health.as_mut().and_then(|health| health.running.take()) {
+            kill_check(&mut check);
+        }
+    }
+
+    /// Starts a health check when one is due and collects the result of the
+    /// running one. A check that runs longer than the interval counts as failed.
+    fn check_health(&mut self, pid: i32, events: &mut Vec<String>) {
+        let name = &self.config.name;
+        let Some(health) = &mut self.health else { return };
+        let outcome = match &mut health.running {
+            Some(check) => match check.try_wait() {
+                Ok(Some(status)) => Some(status.success()),
+                Ok(None) if health.last_run.elapsed() < health.interval => None,
+                _ => {
+                    kill_check(check);
+                    Some(false)
+                }
+            },
+            None => {
+                if health.last_run.elapsed() >= health.interval {
+                    health.last_run = Instant::now();
+                    let check = Command::new("sh")
+                        .arg("-c")
+                        .arg(&health.command)
+                        .env("PROCMASTER_PID", pid.to_string())
+                        // Its own group, so whatever the shell started goes with it.
+                        .process_group(0)
+                        .stdin(Stdio::null())
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .spawn();
+                    match check {
+                        Ok(check) => health.running = Some(check),
+                        Err(_) => return events.push(format!("{}: failed to run health check", name)),
+                    }
+                }
+                None
+            }
+        };
+        let Some(ok) = outcome else { return };
+        health.running = None;
+        health.last_ok = Some(ok);
+        if ok {
+            health.failures = 0;
+            return;
+        }
+        health.failures += 1;
+        if health.failures >= HEALTH_FAILURES {
+            health.failures = 0;
+            events.push(match ctrl::terminate_process(pid, Bypass::None) {
+                Ok(()) => format!("{} failed {} health checks, terminated", name, HEALTH_FAILURES),
+                Err(err) => format!("{} failed {} health checks: {}", name, HEALTH_FAILURES, err),
+            });
+        }
+    }
+
+    fn status(&self) -> ServiceStatus {
+        let (state, pid) = match &self.state {
+            State::Stopped => ("stopped".to_string(), None),
+            State::Running { pid, .. } => ("running".to_string(), Some(*pid)),
+            State::Stopping { pid, .. } => ("stopping".to_string(), Some(*pid)),
+            State::Backoff(at) => {
+                let left = at.saturating_duration_since(Instant::now()).as_secs();
+                (if self.last_exit.is_some() { format!("restarting in {}s", left) } else { "starting".to_string() }, None)
+            }
+            State::Finished(reason) => (reason.clone(), None),
+        };
+        let health = self.health.as_ref().map(|health| match (health.last_ok, health.failures) {
+            (None, _) => "pending".to_string(),
+            (Some(true), _) => "ok".to_string(),
+            (Some(false), failures) => format!("failing ({}/{})", failures, HEALTH_FAILURES),
+        });
+        ServiceStatus {
+            name: self.config.name.clone(),
+            run: self.config.run.clone(),
+            restart: self.config.restart,
+            state,
+            pid,
+            restarts: self.restarts,
+            last_exit: self.last_exit.clone(),
+            health,
+        }
+    }
+}
+
+/// Kills a health check with everything it started, and reaps it.
+fn kill_check(check: &mut Child) {
+    let _ = killpg(Pid::from_raw(check.id() as i32), Signal::SIGKILL);
+    let _ = check.wait();
+}
+
+/// What the Supervisor tab shows about one service.
+#[derive(Clone, Debug, Serialize)]
+pub struct ServiceStatus {
+    pub name: String,
+    pub run: String,
+    pub restart: RestartPolicy,
+    /// `running`, `stopping`, `starting`, `restarting in 5s`, `stopped`, or why
+    /// it was not restarted.
+    pub state: String,
+    pub pid: Option<i32>,
+    pub restarts: u32,
+    /// How the last run ended, e.g. `exited 1`.
+    pub last_exit: Option<String>,
+    /// `ok`, `failing (1/3)` or `pending`, if the service has a health check.
+    pub health: Option<String>,
+}
+
+/// Keeps the `[[supervisor.services]]` commands running as their restart
+/// policies say. Supervision only happens while ProcMaster runs; the
+/// processes themselves are started through a [`Launcher`], so their output
+/// is captured like that of any launched command.
+#[derive(Default)]
+pub struct Supervisor {
+    services: Vec<Service>,
+}
+
+impl Supervisor {
+    pub fn new(config: &SupervisorConfig) -> Result<Self, String> {
+        Ok(Supervisor {
+            services: config.services.iter().cloned().map(Service::new).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Builds the supervisor from the config file, which also refreshes
+    /// [`config::current`].
+    pub fn load() -> Result<Self, String> {
+        Supervisor::new(&config::reload()?.supervisor)
+    }
+
+    /// Starts what is due, notices what exited and runs health checks. Returns
+    /// what happened, for the status line.
+    pub fn tick(&mut self, launcher: &mut Launcher) -> Vec<String> {
+        let mut events = Vec::new();
+        for service in &mut self.services {
+            service.tick(launcher, &mut events);
+        }
+        events
+    }
+
+    pub fn status(&self) -> Vec<ServiceStatus> {
+        self.services.iter().map(Service::status).collect()
+    }
+
+    /// Adds a service and saves it to the config file.
+    pub fn add(&mut self, config: ServiceConfig) -> Result<(), String> {
+        if self.services.iter().any(|service| service.config.name == config.name) {
+            return Err(format!("A service named {} already exists", config.name));
+        }
+        let service = Service::new(config)?;
+        config::add_service(&service.config)?;
+        self.services.push(service);
+        Ok(())
+    }
+
+    /// Stops a service and deletes it from the config file.
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        self.stop(name)?;
+        config::remove_service(name)?;
+        self.services.retain(|service| service.config.name != name);
+        Ok(())
+    }
+
+    /// Starts a stopped or finished service, resetting its restart count. One
+    /// still stopping is started once it has exited.
+    pub fn start(&mut self, name: &str) -> Result<(), String> {
+        let service = self.find(name)?;
+        match service.state {
+            State::Running { .. } => Err(format!("{} is already running", name)),
+            State::Stopping { .. } => service.stop(true),
+            _ => {
+                service.reset();
+                Ok(())
+            }
+        }
+    }
+
+    /// Terminates a service and keeps it from being restarted.
+    pub fn stop(&mut self, name: &str) -> Result<(), String> {
+        self.find(name)?.stop(false)
+    }
+
+    /// Terminates a service and starts it again once the old process has exited.
+    pub fn restart(&mut self, name: &str) -> Result<(), String> {
+        self.find(name)?.stop(true)
+    }
+
+    fn find(&mut self, name: &str) -> Result<&mut Service, String> {
+        self.services.iter_mut()
+            .find(|service| service.config.name == name)
+            .ok_or_else(|| format!("No service named {}", name))
+    }
+}
@@ -0,0 +1,115 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::cgroup::CgroupFs;
+use crate::config;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuspendMethod {
+    /// SIGSTOP, undone with SIGCONT.
+    Signal,
+    /// The cgroup v2 freezer, undone by thawing.
+    Freeze,
+    /// Stopped and continued by a CPU limiter, so it is only sometimes in `T`
+    /// state. Recorded in case ProcMaster dies in a stop phase.
+    Limit,
+}
+
+/// A process ProcMaster stopped or froze and has not resumed yet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SuspendedProcess {
+    pub pid: i32,
+    /// Tells the process apart from a later one that reuses its PID.
+    pub starttime: u64,
+    pub command: String,
+    pub method: SuspendMethod,
+    /// RFC 3339, UTC.
+    pub since: String,
+    /// The single-process cgroup a frozen process was moved into, so that it
+    /// can be cleaned up even if the process exits before it is thawed.
+    #[serde(default)]
+    pub cgroup: Option<String>,
+}
+
+/// Kept next to the config so that the list survives a crash of ProcMaster.
+fn registry_path() -> PathBuf {
+    config::config_dir().join("suspended.json")
+}
+
+fn read() -> Vec<SuspendedProcess> {
+    fs::read_to_string(registry_path())
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Best effort, like the audit log. Written to a temporary file first so a
+/// crash halfway through never leaves a truncated registry behind.
+fn write(entries: &[SuspendedProcess]) {
+    let path = registry_path();
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let Ok(text) = serde_json::to_string_pretty(entries) else { return };
+    let temp = path.with_extension("json.tmp");
+    if fs::write(&temp, text).is_ok() {
+        let _ = fs::rename(&temp, &path);
+    }
+}
+
+/// Whether the entry still describes a live process that is stopped or frozen,
+/// or, for a limited one, is still alive.
+fn still_suspended(entry: &SuspendedProcess, cgroups: &CgroupFs) -> bool {
+    let Ok(stat) = procfs::process::Process::new(entry.pid).and_then(|proc| proc.stat()) else {
+        return false;
+    };
+    stat.starttime == entry.starttime
+        && (entry.method == SuspendMethod::Limit
+            || stat.state == 'T'
+            || cgroups.cgroup_of(entry.pid).is_ok_and(|cgroup| cgroups.is_frozen(&cgroup)))
+}
+
+/// Processes ProcMaster suspended that are still suspended. Entries for processes
+/// that exited, or were resumed by something else, are left out.
+pub fn load() -> Vec<SuspendedProcess> {
+    let cgroups = CgroupFs::system();
+    read().into_iter().filter(|entry| still_suspended(entry, &cgroups)).collect()
+}
+
+/// The cgroups recorded for frozen processes, including ones that have since
+/// exited, which [`load`] leaves out.
+pub fn freeze_cgroups() -> Vec<String> {
+    read().into_iter().filter_map(|entry| entry.cgroup).collect()
+}
+
+/// Records that `pid` was just suspended with `method`.
+pub fn add(pid: i32, method: SuspendMethod) {
+    let Ok(stat) = procfs::process::Process::new(pid).and_then(|proc| proc.stat()) else { return };
+    let cgroup = match method {
+        SuspendMethod::Freeze => CgroupFs::system().cgroup_of(pid).ok(),
+        SuspendMethod::Signal | SuspendMethod::Limit => None,
+    };
+    let mut entries = load();
+    entries.retain(|entry| entry.pid != pid);
+    entries.push(SuspendedProcess {
+        pid,
+        starttime: stat.starttime,
+        command: stat.comm,
+        method,
+        since: OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default(),
+        cgroup,
+    });
+    write(&entries);
+}
+
+/// Forgets `pid`, after it was resumed.
+pub fn remove(pid: i32) {
+    let mut entries = load();
+    entries.retain(|entry| entry.pid != pid);
+    write(&entries);
+}
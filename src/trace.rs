@@ -0,0 +1,464 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::os::unix::fs::FileExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use nix::errno::Errno;
+use nix::sys::ptrace::{self, Options};
+use nix::sys::signal::Signal;
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::Pid;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::audit::audited;
+use crate::syscalls;
+
+/// Log lines kept; older ones are dropped.
+const MAX_LOG_LINES: usize = 2000;
+/// Pause between polls when no thread had anything to report. It doubles while
+/// nothing happens, up to `MAX_IDLE_POLL`, so a busy tracee is not slowed down
+/// much and an idle one costs little.
+const IDLE_POLL: Duration = Duration::from_micros(50);
+const MAX_IDLE_POLL: Duration = Duration::from_millis(5);
+/// Longest string or buffer shown in an argument.
+const MAX_STRING: usize = 48;
+
+/// One finished syscall, like a line of strace output.
+#[derive(Clone, Debug, Serialize)]
+pub struct TraceLine {
+    /// Seconds since the trace started.
+    pub time: f64,
+    pub tid: i32,
+    pub text: String,
+    pub error: bool,
+}
+
+/// Totals of one syscall, like `strace -c`.
+#[derive(Clone, Debug, Serialize)]
+pub struct SyscallSummary {
+    pub name: String,
+    pub calls: u64,
+    pub errors: u64,
+    /// Time spent inside the syscall, in seconds.
+    pub seconds: f64,
+}
+
+/// A thread that is inside a syscall right now, e.g. blocked in `futex`.
+#[derive(Clone, Debug, Serialize)]
+pub struct InSyscall {
+    pub tid: i32,
+    pub call: String,
+    pub seconds: f64,
+}
+
+struct Entry {
+    name: String,
+    call: String,
+    start: Instant,
+}
+
+#[derive(Default)]
+struct TraceState {
+    log: VecDeque<TraceLine>,
+    summary: HashMap<String, SyscallSummary>,
+    /// Syscalls entered but not yet returned, by thread.
+    pending: HashMap<i32, Entry>,
+    threads: Vec<i32>,
+    ended: Option<String>,
+}
+
+impl TraceState {
+    fn push(&mut self, line: TraceLine) {
+        if self.log.len() == MAX_LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back(line);
+    }
+}
+
+/// Traces the syscalls of a process with `PTRACE_SEIZE`, from a thread of its
+/// own since ptrace requests have to come from the thread that attached.
+/// Dropping it detaches and leaves the process running as before.
+pub struct Tracer {
+    pub pid: i32,
+    pub command: String,
+    state: Arc<Mutex<TraceState>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Tracer {
+    /// Starts tracing every thread of `pid`, or only that thread if `pid` is
+    /// the TID of a thread other than the main one.
+    pub fn attach(pid: i32) -> Result<Tracer, String> {
+        let process = procfs::process::Process::new(pid).map_err(|err| format!("Failed to read process {}: {}", pid, err))?;
+        let status = process.status().map_err(|err| format!("Failed to read process {}: {}", pid, err))?;
+        let whole_process = status.tgid == pid;
+        let state = Arc::new(Mutex::new(TraceState::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (attached, result) = mpsc::channel();
+        let thread = {
+            let state = Arc::clone(&state);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                let started = Instant::now();
+                let mut threads = HashSet::new();
+                let seized = audited("trace", Some(pid), json!({ "whole_process": whole_process }), || {
+                    threads = seize(pid, whole_process)?;
+                    Ok::<(), String>(())
+                });
+                let ok = seized.is_ok();
+                let _ = attached.send(seized);
+                if ok {
+                    trace(threads, started, &stop, &state);
+                }
+            })
+        };
+        result.recv().map_err(|_| "The tracer thread failed".to_string())??;
+        Ok(Tracer { pid, command: status.name, state, stop, thread: Some(thread) })
+    }
+
+    /// Finished syscalls, oldest first.
+    pub fn log(&self) -> Vec<TraceLine> {
+        self.state.lock().unwrap().log.iter().cloned().collect()
+    }
+
+    /// Per-syscall totals, most time spent first.
+    pub fn summary(&self) -> Vec<SyscallSummary> {
+        let mut summary: Vec<_> = self.state.lock().unwrap().summary.values().cloned().collect();
+        summary.sort_by(|a, b| b.seconds.total_cmp(&a.seconds).then(b.calls.cmp(&a.calls)));
+        summary
+    }
+
+    /// Threads currently inside a syscall, longest first. A thread that stays
+    /// here is stuck, and the call says on what.
+    pub fn in_syscall(&self) -> Vec<InSyscall> {
+        let state = self.state.lock().unwrap();
+        let mut calls: Vec<_> = state.pending.iter()
+            .map(|(&tid, entry)| InSyscall { tid, call: entry.call.clone(), seconds: entry.start.elapsed().as_secs_f64() })
+            .collect();
+        calls.sort_by(|a, b| b.seconds.total_cmp(&a.seconds));
+        calls
+    }
+
+    /// The traced threads, in TID order.
+    pub fn threads(&self) -> Vec<i32> {
+        self.state.lock().unwrap().threads.clone()
+    }
+
+    /// Why tracing stopped on its own, e.g. because the process exited.
+    pub fn ended(&self) -> Option<String> {
+        self.state.lock().unwrap().ended.clone()
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = audited("untrace", Some(self.pid), json!({}), || Ok::<(), String>(()));
+    }
+}
+
+/// Seizes `pid`, or all of its threads with `whole_process`, and interrupts
+/// them so the first `PTRACE_SYSCALL` takes effect right away. The task list is
+/// read again until it stops growing, so threads started meanwhile are not
+/// missed; later ones are picked up through `PTRACE_O_TRACECLONE`.
+fn seize(pid: i32, whole_process: bool) -> Result<HashSet<i32>, String> {
+    let options = Options::PTRACE_O_TRACESYSGOOD | Options::PTRACE_O_TRACECLONE;
+    let mut seized = HashSet::new();
+    // Threads already tried, so one that exits while still listed is not tried forever.
+    let mut tried = HashSet::new();
+    loop {
+        let tids: Vec<i32> = if whole_process {
+            fs::read_dir(format!("/proc/{}/task", pid))
+                .map_err(|err| format!("Failed to list threads of {}: {}", pid, err))?
+                .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                .filter(|&tid| tried.insert(tid))
+                .collect()
+        } else if tried.insert(pid) {
+            vec![pid]
+        } else {
+            Vec::new()
+        };
+        if tids.is_empty() {
+            return Ok(seized);
+        }
+        for tid in tids {
+            match ptrace::seize(Pid::from_raw(tid), options).and_then(|()| ptrace::interrupt(Pid::from_raw(tid))) {
+                Ok(()) => {
+                    seized.insert(tid);
+                }
+                // The thread exited in the meantime.
+                Err(Errno::ESRCH) if tid != pid => {}
+                Err(err) => {
+                    detach(&seized);
+                    return Err(attach_error(pid, err));
+                }
+            }
+        }
+    }
+}
+
+/// Explains a failed attach; EPERM usually comes from `kernel.yama.ptrace_scope`.
+fn attach_error(pid: i32, err: Errno) -> String {
+    if err != Errno::EPERM {
+        return format!("Failed to trace PID {}: {}", pid, err.desc());
+    }
+    let tracer = procfs::process::Process::new(pid).and_then(|process| process.status()).map(|status| status.tracerpid);
+    if let Some(tracer) = tracer.ok().filter(|&tracer| tracer != 0) {
+        return format!("PID {} is already being traced by PID {}", pid, tracer);
+    }
+    let scope = fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope").ok().and_then(|scope| scope.trim().parse::<u8>().ok());
+    match scope {
+        Some(1) => format!("Not permitted to trace PID {}: kernel.yama.ptrace_scope is 1, so without CAP_SYS_PTRACE only descendants can be traced", pid),
+        Some(2) => format!("Not permitted to trace PID {}: kernel.yama.ptrace_scope is 2, so only processes with CAP_SYS_PTRACE can trace", pid),
+        Some(3) => format!("Not permitted to trace PID {}: kernel.yama.ptrace_scope is 3, attaching is disabled until reboot", pid),
+        _ => format!("Not permitted to trace PID {}: it belongs to another user or is setuid", pid),
+    }
+}
+
+/// The tracer thread's loop. Each thread is polled with its own `waitpid` so
+/// the loop never reaps, or steals stops of, children that are not traced.
+fn trace(mut threads: HashSet<i32>, started: Instant, stop: &AtomicBool, state: &Mutex<TraceState>) {
+    let mut pause = IDLE_POLL;
+    while !stop.load(Ordering::Relaxed) {
+        let mut idle = true;
+        for tid in threads.clone() {
+            let signal = match waitpid(Pid::from_raw(tid), Some(WaitPidFlag::__WALL | WaitPidFlag::WNOHANG)) {
+                Ok(WaitStatus::StillAlive) => continue,
+                Ok(WaitStatus::PtraceSyscall(_)) => {
+                    on_syscall(tid, started, state);
+                    None
+                }
+                Ok(WaitStatus::PtraceEvent(_, signal, event)) => {
+                    if event == libc::PTRACE_EVENT_CLONE {
+                        if let Ok(child) = ptrace::getevent(Pid::from_raw(tid)) {
+                            threads.insert(child as i32);
+                        }
+                    } else if event == libc::PTRACE_EVENT_STOP
+                        && matches!(signal, Signal::SIGSTOP | Signal::SIGTSTP | Signal::SIGTTIN | Signal::SIGTTOU)
+                    {
+                        // A real group stop: leave it stopped until SIGCONT.
+                        unsafe { libc::ptrace(libc::PTRACE_LISTEN, tid, 0, 0) };
+                        idle = false;
+                        continue;
+                    }
+                    None
+                }
+                // Signal delivery: pass the signal on.
+                Ok(WaitStatus::Stopped(_, signal)) => Some(signal),
+                Ok(WaitStatus::Exited(..) | WaitStatus::Signaled(..)) | Err(_) => {
+                    threads.remove(&tid);
+                    let mut state = state.lock().unwrap();
+                    // exit and exit_group never return.
+                    if let Some(entry) = state.pending.remove(&tid) {
+                        let text = format!("{} = ?", entry.call);
+                        state.push(TraceLine { time: started.elapsed().as_secs_f64(), tid, text, error: false });
+                    }
+                    continue;
+                }
+                Ok(_) => None,
+            };
+            idle = false;
+            let _ = ptrace::syscall(Pid::from_raw(tid), signal);
+        }
+        {
+            let mut state = state.lock().unwrap();
+            state.threads = threads.iter().copied().collect();
+            state.threads.sort_unstable();
+            if threads.is_empty() {
+                state.ended = Some("The process exited".to_string());
+                return;
+            }
+        }
+        if idle {
+            thread::sleep(pause);
+            pause = (pause * 2).min(MAX_IDLE_POLL);
+        } else {
+            pause = IDLE_POLL;
+        }
+    }
+    detach(&threads);
+}
+
+/// Interrupts every thread and detaches it at that stop, handing back any
+/// signal it was about to receive.
+fn detach(threads: &HashSet<i32>) {
+    for &tid in threads {
+        let _ = ptrace::interrupt(Pid::from_raw(tid));
+    }
+    for &tid in threads {
+        let tid = Pid::from_raw(tid);
+        match waitpid(tid, Some(WaitPidFlag::__WALL)) {
+            Ok(WaitStatus::Stopped(_, signal)) => {
+                let _ = ptrace::detach(tid, signal);
+            }
+            Ok(WaitStatus::Exited(..) | WaitStatus::Signaled(..)) | Err(_) => {}
+            Ok(_) => {
+                let _ = ptrace::detach(tid, None);
+            }
+        }
+    }
+}
+
+fn syscall_info(tid: i32) -> Option<libc::ptrace_syscall_info> {
+    let mut info: libc::ptrace_syscall_info = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::ptrace_syscall_info>();
+    let written = unsafe {
+        libc::ptrace(libc::PTRACE_GET_SYSCALL_INFO, tid, size as *mut libc::c_void, &mut info as *mut _ as *mut libc::c_void)
+    };
+    (written > 0).then_some(info)
+}
+
+/// Records a syscall entry, or turns the matching entry into a log line on exit.
+fn on_syscall(tid: i32, started: Instant, state: &Mutex<TraceState>) {
+    let Some(info) = syscall_info(tid) else { return };
+    match info.op {
+        libc::PTRACE_SYSCALL_INFO_ENTRY => {
+            let entry = unsafe { info.u.entry };
+            let name = syscalls::name(info.arch, entry.nr).map(str::to_string).unwrap_or_else(|| format!("syscall_{}", entry.nr));
+            let call = format_call(tid, &name, &entry.args);
+            state.lock().unwrap().pending.insert(tid, Entry { name, call, start: Instant::now() });
+        }
+        libc::PTRACE_SYSCALL_INFO_EXIT => {
+            let exit = unsafe { info.u.exit };
+            let mut state = state.lock().unwrap();
+            let Some(entry) = state.pending.remove(&tid) else { return };
+            let seconds = entry.start.elapsed().as_secs_f64();
+            let error = exit.is_error != 0;
+            let summary = state.summary.entry(entry.name.clone()).or_insert_with(|| SyscallSummary {
+                name: entry.name.clone(),
+                calls: 0,
+                errors: 0,
+                seconds: 0.0,
+            });
+            summary.calls += 1;
+            summary.errors += error as u64;
+            summary.seconds += seconds;
+            let text = format!("{} = {} <{:.6}>", entry.call, format_result(&entry.name, exit.sval, error), seconds);
+            state.push(TraceLine { time: started.elapsed().as_secs_f64(), tid, text, error });
+        }
+        _ => {}
+    }
+}
+
+fn format_result(name: &str, value: i64, error: bool) -> String {
+    if error {
+        return match -value {
+            512 => "? ERESTARTSYS (To be restarted if SA_RESTART is set)".to_string(),
+            513 => "? ERESTARTNOINTR (To be restarted)".to_string(),
+            514 => "? ERESTARTNOHAND (To be restarted if no handler)".to_string(),
+            516 => "? ERESTART_RESTARTBLOCK (Interrupted by signal)".to_string(),
+            errno => {
+                let errno = Errno::from_raw(errno as i32);
+                format!("-1 {:?} ({})", errno, errno.desc())
+            }
+        };
+    }
+    match name {
+        "mmap" | "mremap" | "brk" | "shmat" => format!("{:#x}", value),
+        _ => value.to_string(),
+    }
+}
+
+fn format_call(tid: i32, name: &str, args: &[u64; 6]) -> String {
+    let Some(kinds) = syscalls::arguments(name) else {
+        return format!("{}({:#x}, {:#x}, {:#x}, ...)", name, args[0], args[1], args[2]);
+    };
+    let formatted: Vec<String> = kinds.chars()
+        .enumerate()
+        .map(|(index, kind)| format_arg(tid, kind, args[index], args.get(index + 1).copied().unwrap_or(0)))
+        .collect();
+    format!("{}({})", name, formatted.join(", "))
+}
+
+/// Formats one argument of the kind letter from [`syscalls::arguments`];
+/// `next` is the argument after it, the length of a buffer.
+fn format_arg(tid: i32, kind: char, value: u64, next: u64) -> String {
+    match kind {
+        'd' => (value as i32).to_string(),
+        'u' => value.to_string(),
+        'f' if value as i32 == libc::AT_FDCWD => "AT_FDCWD".to_string(),
+        'f' => (value as i32).to_string(),
+        's' => match read_memory(tid, value, 256) {
+            Some(bytes) => quote(bytes.split(|&byte| byte == 0).next().unwrap_or_default()),
+            None => format!("{:#x}", value),
+        },
+        'b' => match read_memory(tid, value, (next as usize).min(MAX_STRING + 1)) {
+            Some(bytes) => quote(&bytes),
+            None => format!("{:#x}", value),
+        },
+        'o' => open_flags(value as i32),
+        'm' => format!("{:#o}", value),
+        'F' => futex_op(value as i32),
+        'S' => Signal::try_from(value as i32).map(|signal| signal.as_str().to_string()).unwrap_or_else(|_| value.to_string()),
+        _ => format!("{:#x}", value),
+    }
+}
+
+/// Reads tracee memory through `/proc/TID/mem`, which the tracer may do.
+fn read_memory(tid: i32, address: u64, len: usize) -> Option<Vec<u8>> {
+    let mut buffer = vec![0; len];
+    let read = File::open(format!("/proc/{}/mem", tid)).ok()?.read_at(&mut buffer, address).ok()?;
+    buffer.truncate(read);
+    Some(buffer)
+}
+
+fn quote(bytes: &[u8]) -> String {
+    let shown = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_STRING)]).into_owned();
+    if bytes.len() > MAX_STRING { format!("{:?}...", shown) } else { format!("{:?}", shown) }
+}
+
+fn open_flags(flags: i32) -> String {
+    let mut names = vec![match flags & libc::O_ACCMODE {
+        libc::O_WRONLY => "O_WRONLY".to_string(),
+        libc::O_RDWR => "O_RDWR".to_string(),
+        _ => "O_RDONLY".to_string(),
+    }];
+    let mut rest = flags & !libc::O_ACCMODE;
+    for (flag, name) in [
+        (libc::O_CREAT, "O_CREAT"),
+        (libc::O_EXCL, "O_EXCL"),
+        (libc::O_NOCTTY, "O_NOCTTY"),
+        (libc::O_TRUNC, "O_TRUNC"),
+        (libc::O_APPEND, "O_APPEND"),
+        (libc::O_NONBLOCK, "O_NONBLOCK"),
+        (libc::O_DIRECTORY, "O_DIRECTORY"),
+        (libc::O_NOFOLLOW, "O_NOFOLLOW"),
+        (libc::O_CLOEXEC, "O_CLOEXEC"),
+        (libc::O_PATH, "O_PATH"),
+    ] {
+        if rest & flag == flag {
+            names.push(name.to_string());
+            rest &= !flag;
+        }
+    }
+    if rest != 0 {
+        names.push(format!("{:#x}", rest));
+    }
+    names.join("|")
+}
+
+fn futex_op(op: i32) -> String {
+    const OPERATIONS: [&str; 14] = [
+        "FUTEX_WAIT", "FUTEX_WAKE", "FUTEX_FD", "FUTEX_REQUEUE", "FUTEX_CMP_REQUEUE", "FUTEX_WAKE_OP",
+        "FUTEX_LOCK_PI", "FUTEX_UNLOCK_PI", "FUTEX_TRYLOCK_PI", "FUTEX_WAIT_BITSET", "FUTEX_WAKE_BITSET",
+        "FUTEX_WAIT_REQUEUE_PI", "FUTEX_CMP_REQUEUE_PI", "FUTEX_LOCK_PI2",
+    ];
+    let command = op & libc::FUTEX_CMD_MASK;
+    let mut text = OPERATIONS.get(command as usize).map(|name| name.to_string()).unwrap_or_else(|| command.to_string());
+    if op & libc::FUTEX_PRIVATE_FLAG != 0 {
+        text.push_str("_PRIVATE");
+    }
+    if op & libc::FUTEX_CLOCK_REALTIME != 0 {
+        text.push_str("|FUTEX_CLOCK_REALTIME");
+    }
+    text
+}
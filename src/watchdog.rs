@@ -0,0 +1,572 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use nix::sys::signal::Signal;
+use serde_json::json;
+
+use crate::audit::audited;
+use crate::cgroup::{CgroupFs, CgroupLimit};
+use crate::config::{self, RuleConfig, WatchdogConfig};
+use crate::ctrl::{self, Bypass, IoPriority, PriorityTarget};
+use crate::overview::Process;
+
+/// Cooldown of a rule that does not set one.
+const DEFAULT_COOLDOWN: u64 = 300;
+
+/// A process attribute a condition can test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Pid,
+    Ppid,
+    User,
+    Command,
+    State,
+    /// Bytes.
+    Rss,
+    /// Bytes.
+    Vmem,
+    /// Percent of total memory.
+    Mem,
+    /// Percent of one CPU, as the Processes tab shows it: averaged over the
+    /// process's whole lifetime, not its current usage. A process that has
+    /// idled for hours does not reach `cpu > 90` for a long time.
+    Cpu,
+    Nice,
+    Threads,
+    OomScore,
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(match text {
+            "pid" => Field::Pid,
+            "ppid" => Field::Ppid,
+            "user" => Field::User,
+            "command" | "comm" => Field::Command,
+            "state" => Field::State,
+            "rss" => Field::Rss,
+            "vmem" | "vsize" => Field::Vmem,
+            "mem" => Field::Mem,
+            "cpu" => Field::Cpu,
+            "nice" => Field::Nice,
+            "threads" => Field::Threads,
+            "oom_score" => Field::OomScore,
+            other => return Err(format!("Unknown field {}", other)),
+        })
+    }
+}
+
+impl Field {
+    fn is_text(self) -> bool {
+        matches!(self, Field::User | Field::Command | Field::State)
+    }
+
+    fn number(self, process: &Process) -> f64 {
+        const MIB: f64 = 1024.0 * 1024.0;
+        match self {
+            Field::Pid => process.pid as f64,
+            Field::Ppid => process.ppid as f64,
+            Field::Rss => process.rss_memory * MIB,
+            Field::Vmem => process.v_memory * MIB,
+            Field::Mem => process.memory_uasge,
+            Field::Cpu => process.cpu_usage,
+            Field::Nice => process.nice as f64,
+            Field::Threads => process.threads as f64,
+            Field::OomScore => process.oom_score as f64,
+            Field::User | Field::Command | Field::State => f64::NAN,
+        }
+    }
+
+    fn text(self, process: &Process) -> &str {
+        match self {
+            Field::User => &process.user,
+            Field::Command => &process.command,
+            Field::State => &process.state,
+            _ => "",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    /// Glob match, `~` or `matches`.
+    Matches,
+    /// Glob mismatch, `!~` or `!matches`.
+    NotMatches,
+}
+
+/// Longest first, so `>=` is not read as `>` followed by `=`.
+const OPERATORS: [(&str, Op); 11] = [
+    (" !matches ", Op::NotMatches),
+    (" matches ", Op::Matches),
+    (">=", Op::Ge),
+    ("<=", Op::Le),
+    ("==", Op::Eq),
+    ("!=", Op::Ne),
+    ("!~", Op::NotMatches),
+    (">", Op::Gt),
+    ("<", Op::Lt),
+    ("=", Op::Eq),
+    ("~", Op::Matches),
+];
+
+/// One comparison such as `rss > 4GiB`, `cpu >= 90` or `command ~ 'worker*'`
+/// (`matches` and `!matches` are the same as `~` and `!~`). Sizes take K, M,
+/// G and T suffixes (binary, with or without `iB`). A trailing `for 60s`
+/// makes the comparison count only once it has held that long.
+#[derive(Clone, Debug)]
+pub struct Condition {
+    field: Field,
+    op: Op,
+    number: f64,
+    text: String,
+    pattern: Option<glob::Pattern>,
+    hold: Duration,
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (text, hold) = match text.trim().rsplit_once(" for ").map(|(comparison, hold)| (comparison, ctrl::parse_age(hold))) {
+            Some((comparison, Ok(seconds))) => (comparison, Duration::from_secs(seconds)),
+            _ => (text, Duration::ZERO),
+        };
+        let (position, symbol, op) = OPERATORS.iter()
+            .filter_map(|&(symbol, op)| text.find(symbol).map(|position| (position, symbol, op)))
+            .min_by_key(|&(position, symbol, _)| (position, std::cmp::Reverse(symbol.len())))
+            .ok_or_else(|| format!("No comparison in \"{}\"", text.trim()))?;
+        let field: Field = text[..position].trim().parse()?;
+        let value = text[position + symbol.len()..].trim().trim_matches(|c| c == '\'' || c == '"').to_string();
+
+        match op {
+            Op::Matches | Op::NotMatches => {
+                let pattern = glob::Pattern::new(&value).map_err(|err| format!("Invalid pattern {}: {}", value, err))?;
+                Ok(Condition { field, op, number: f64::NAN, text: value, pattern: Some(pattern), hold })
+            }
+            _ if field.is_text() => {
+                if !matches!(op, Op::Eq | Op::Ne) {
+                    return Err(format!("{} can only be compared with =, != or ~", text[..position].trim()));
+                }
+                Ok(Condition { field, op, number: f64::NAN, text: value, pattern: None, hold })
+            }
+            _ => {
+                let number = parse_number(&value).ok_or_else(|| format!("Invalid number {}", value))?;
+                Ok(Condition { field, op, number, text: value, pattern: None, hold })
+            }
+        }
+    }
+}
+
+impl Condition {
+    fn holds(&self, process: &Process) -> bool {
+        if let Some(pattern) = &self.pattern {
+            return pattern.matches(self.field.text(process)) == (self.op == Op::Matches);
+        }
+        if self.field.is_text() {
+            return (self.field.text(process) == self.text) == (self.op == Op::Eq);
+        }
+        let value = self.field.number(process);
+        match self.op {
+            Op::Gt => value > self.number,
+            Op::Ge => value >= self.number,
+            Op::Lt => value < self.number,
+            Op::Le => value <= self.number,
+            Op::Eq => value == self.number,
+            Op::Ne => value != self.number,
+            Op::Matches | Op::NotMatches => false,
+        }
+    }
+}
+
+/// `4GiB`, `512M`, `90%` or a plain number.
+fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim_end_matches('%').trim_end_matches("iB").trim_end_matches('B');
+    let (number, multiplier) = match text.chars().last()?.to_ascii_uppercase() {
+        'K' => (&text[..text.len() - 1], 1024f64),
+        'M' => (&text[..text.len() - 1], 1024f64.powi(2)),
+        'G' => (&text[..text.len() - 1], 1024f64.powi(3)),
+        'T' => (&text[..text.len() - 1], 1024f64.powi(4)),
+        _ => (text, 1.0),
+    };
+    number.trim().parse::<f64>().ok().map(|number| number * multiplier)
+}
+
+/// What a rule does to each process it fires on.
+#[derive(Clone, Debug)]
+pub enum RuleAction {
+    Signal(Signal),
+    Renice(i32),
+    IoPriority(IoPriority),
+    CgroupLimit(CgroupLimit, String),
+    /// Run through `sh -c` with `PROCMASTER_PID`, `PROCMASTER_COMMAND` and
+    /// `PROCMASTER_RULE` set.
+    Exec(String),
+    Alert,
+}
+
+impl FromStr for RuleAction {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let (verb, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let rest = rest.trim();
+        Ok(match verb {
+            "signal" => RuleAction::Signal(ctrl::parse_signal(rest)?),
+            "renice" => RuleAction::Renice(rest.parse().map_err(|_| format!("Invalid nice value {}", rest))?),
+            "ioprio" => RuleAction::IoPriority(rest.parse()?),
+            "cgroup" => {
+                let (file, value) = rest.split_once(char::is_whitespace).ok_or("cgroup needs a file and a value")?;
+                let limit: CgroupLimit = file.parse()?;
+                limit.validate(value).map_err(|err| err.to_string())?;
+                RuleAction::CgroupLimit(limit, value.trim().to_string())
+            }
+            "exec" if !rest.is_empty() => RuleAction::Exec(rest.to_string()),
+            "exec" => return Err("exec needs a command".to_string()),
+            "alert" => RuleAction::Alert,
+            other => return Err(format!("Unknown action {}", other)),
+        })
+    }
+}
+
+impl fmt::Display for RuleAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleAction::Signal(signal) => write!(f, "signal {}", signal),
+            RuleAction::Renice(nice) => write!(f, "renice {}", nice),
+            RuleAction::IoPriority(priority) => write!(f, "ioprio {}", priority),
+            RuleAction::CgroupLimit(limit, value) => write!(f, "cgroup {} {}", limit, value),
+            RuleAction::Exec(command) => write!(f, "exec {}", command),
+            RuleAction::Alert => write!(f, "alert"),
+        }
+    }
+}
+
+impl RuleAction {
+    /// Runs the action if `process.pid` still belongs to the process in the
+    /// snapshot, which may be a second old.
+    fn run(&self, rule: &str, process: &Process) -> Result<(), String> {
+        let pid = process.pid;
+        let stat = procfs::process::Process::new(pid)
+            .and_then(|proc| proc.stat())
+            .map_err(|_| format!("PID {} ({}) has exited", pid, process.command))?;
+        if stat.starttime != process.starttime {
+            return Err(format!("PID {} now belongs to another process ({}), skipped", pid, stat.comm));
+        }
+        match self {
+            RuleAction::Signal(signal) => ctrl::signal_process(pid, *signal, Bypass::None),
+            RuleAction::Renice(nice) => {
+                ctrl::set_priority(PriorityTarget::Process(pid), *nice).map_err(|err| err.to_string())
+            }
+            RuleAction::IoPriority(priority) => ctrl::set_io_priority(pid, *priority).map_err(|err| err.to_string()),
+            RuleAction::CgroupLimit(limit, value) => {
+                let cgroup = CgroupFs::system().cgroup_of(pid).map_err(|err| err.to_string())?;
+                ctrl::set_cgroup_limit(pid, &cgroup, *limit, value).map_err(|err| err.to_string())
+            }
+            RuleAction::Exec(command) => {
+                let mut child = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .env("PROCMASTER_PID", pid.to_string())
+                    .env("PROCMASTER_COMMAND", &process.command)
+                    .env("PROCMASTER_RULE", rule)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .map_err(|err| format!("Failed to run {}: {}", command, err))?;
+                // Reap it in the background so it does not linger as a zombie.
+                thread::spawn(move || child.wait());
+                Ok(())
+            }
+            RuleAction::Alert => Ok(()),
+        }
+    }
+}
+
+/// Parses `5/1h` into a count and a period in seconds.
+fn parse_rate_limit(text: &str) -> Result<(usize, u64), String> {
+    let (count, period) = text.split_once('/').ok_or_else(|| format!("Invalid rate limit {}, expected COUNT/PERIOD", text))?;
+    let count = count.trim().parse().map_err(|_| format!("Invalid rate limit count {}", count))?;
+    Ok((count, ctrl::parse_age(period)?))
+}
+
+pub struct Rule {
+    pub name: String,
+    conditions: Vec<Condition>,
+    hold: Duration,
+    action: RuleAction,
+    cooldown: Duration,
+    rate_limit: Option<(usize, Duration)>,
+    dry_run: bool,
+    /// When each condition, by PID, start time and index, started holding.
+    holding_since: HashMap<(i32, u64, usize), Instant>,
+    /// When each currently matching process, by PID and start time, started
+    /// matching, so a reused PID starts over.
+    matching_since: HashMap<(i32, u64), Instant>,
+    last_fired: HashMap<(i32, u64), Instant>,
+    /// Recent firings, for the rate limit.
+    fired: VecDeque<Instant>,
+}
+
+impl TryFrom<&RuleConfig> for Rule {
+    type Error = String;
+
+    fn try_from(config: &RuleConfig) -> Result<Self, Self::Error> {
+        let context = |err: String| format!("Rule \"{}\": {}", config.name, err);
+        let conditions = config.when
+            .split(" and ")
+            .map(str::parse)
+            .collect::<Result<Vec<Condition>, String>>()
+            .map_err(context)?;
+        let seconds = |text: &Option<String>, default: u64| {
+            text.as_deref().map_or(Ok(default), ctrl::parse_age).map(Duration::from_secs).map_err(context)
+        };
+        Ok(Rule {
+            name: config.name.clone(),
+            conditions,
+            hold: seconds(&config.hold, 0)?,
+            action: config.action.parse().map_err(context)?,
+            cooldown: seconds(&config.cooldown, DEFAULT_COOLDOWN)?,
+            rate_limit: config.rate_limit
+                .as_deref()
+                .map(parse_rate_limit)
+                .transpose()
+                .map_err(context)?
+                .map(|(count, period)| (count, Duration::from_secs(period))),
+            dry_run: config.dry_run,
+            holding_since: HashMap::new(),
+            matching_since: HashMap::new(),
+            last_fired: HashMap::new(),
+            fired: VecDeque::new(),
+        })
+    }
+}
+
+/// A rule firing on one process.
+#[derive(Clone, Debug)]
+pub struct WatchdogEvent {
+    pub rule: String,
+    pub pid: i32,
+    pub command: String,
+    pub action: String,
+    pub dry_run: bool,
+    pub result: Result<(), String>,
+}
+
+impl fmt::Display for WatchdogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = if self.dry_run { "would " } else { "" };
+        write!(f, "[{}] {}{} on {} ({})", self.rule, prefix, self.action, self.pid, self.command)?;
+        if let Err(err) = &self.result {
+            write!(f, ": {}", err)?;
+        }
+        Ok(())
+    }
+}
+
+/// Evaluates the configured rules against process snapshots and acts on the
+/// processes that match. Every firing goes to the audit log as `watchdog`,
+/// dry runs included.
+#[derive(Default)]
+pub struct Watchdog {
+    rules: Vec<Rule>,
+    dry_run: bool,
+}
+
+impl Watchdog {
+    pub fn new(config: &WatchdogConfig) -> Result<Self, String> {
+        Ok(Watchdog {
+            rules: config.rules.iter().map(Rule::try_from).collect::<Result<_, _>>()?,
+            dry_run: config.dry_run,
+        })
+    }
+
+    /// Builds the watchdog from the config file, which also refreshes
+    /// [`config::current`].
+    pub fn load() -> Result<Self, String> {
+        Watchdog::new(&config::reload()?.watchdog)
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Checks one snapshot and fires the rules whose conditions have held long
+    /// enough, unless a cooldown or rate limit holds them back.
+    pub fn evaluate(&mut self, processes: &[Process]) -> Vec<WatchdogEvent> {
+        let now = Instant::now();
+        let own_pid = std::process::id() as i32;
+        let mut events = Vec::new();
+
+        for rule in &mut self.rules {
+            let alive = |pid: i32, starttime: u64| {
+                processes.iter().any(|process| process.pid == pid && process.starttime == starttime)
+            };
+            rule.matching_since.retain(|&(pid, starttime), _| alive(pid, starttime));
+            rule.holding_since.retain(|&(pid, starttime, _), _| alive(pid, starttime));
+            rule.last_fired.retain(|_, fired| now.duration_since(*fired) < rule.cooldown);
+            if let Some((_, period)) = rule.rate_limit {
+                while rule.fired.front().is_some_and(|fired| now.duration_since(*fired) >= period) {
+                    rule.fired.pop_front();
+                }
+            }
+
+            for process in processes.iter().filter(|process| process.pid != own_pid) {
+                let key = (process.pid, process.starttime);
+                // Every condition is checked, so each one's `for` is timed
+                // from when it started holding, not from when the others did.
+                let mut matching = true;
+                for (index, condition) in rule.conditions.iter().enumerate() {
+                    if condition.holds(process) {
+                        let since = *rule.holding_since.entry((process.pid, process.starttime, index)).or_insert(now);
+                        matching &= now.duration_since(since) >= condition.hold;
+                    } else {
+                        rule.holding_since.remove(&(process.pid, process.starttime, index));
+                        matching = false;
+                    }
+                }
+                if !matching {
+                    rule.matching_since.remove(&key);
+                    continue;
+                }
+                let since = *rule.matching_since.entry(key).or_insert(now);
+                if now.duration_since(since) < rule.hold || rule.last_fired.contains_key(&key) {
+                    continue;
+                }
+                if rule.rate_limit.is_some_and(|(count, _)| rule.fired.len() >= count) {
+                    continue;
+                }
+
+                let dry_run = self.dry_run || rule.dry_run;
+                let args = json!({ "rule": rule.name, "action": rule.action.to_string(), "dry_run": dry_run });
+                let result = audited("watchdog", Some(process.pid), args, || {
+                    if dry_run { Ok(()) } else { rule.action.run(&rule.name, process) }
+                });
+                rule.last_fired.insert(key, now);
+                rule.fired.push_back(now);
+                events.push(WatchdogEvent {
+                    rule: rule.name.clone(),
+                    pid: process.pid,
+                    command: process.command.clone(),
+                    action: rule.action.to_string(),
+                    dry_run,
+                    result,
+                });
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(command: &str, cpu_usage: f64, rss_mib: f64) -> Process {
+        Process {
+            pid: 42,
+            user: "alice".to_string(),
+            command: command.to_string(),
+            v_memory: 0.0,
+            rss_memory: rss_mib,
+            shared_memory: 0.0,
+            memory_uasge: 0.0,
+            cpu_usage,
+            time: String::new(),
+            priority: 20,
+            nice: 0,
+            ppid: 1,
+            state: "S".to_string(),
+            threads: 1,
+            io_priority: String::new(),
+            oom_score: 0,
+            oom_score_adj: 0,
+            frozen: false,
+            suspended_by_procmaster: false,
+            starttime: 1000,
+        }
+    }
+
+    #[test]
+    fn parse_number_takes_binary_size_suffixes() {
+        assert_eq!(parse_number("4GiB"), Some(4.0 * 1024f64.powi(3)));
+        assert_eq!(parse_number("512M"), Some(512.0 * 1024f64.powi(2)));
+        assert_eq!(parse_number("1.5k"), Some(1536.0));
+        assert_eq!(parse_number("90%"), Some(90.0));
+        assert_eq!(parse_number("12"), Some(12.0));
+        assert_eq!(parse_number("lots"), None);
+        assert_eq!(parse_number("G"), None);
+        assert_eq!(parse_number(""), None);
+    }
+
+    #[test]
+    fn condition_with_a_hold_time() {
+        let condition: Condition = "rss > 4GiB for 60s".parse().unwrap();
+        assert_eq!(condition.field, Field::Rss);
+        assert_eq!(condition.op, Op::Gt);
+        assert_eq!(condition.number, 4.0 * 1024f64.powi(3));
+        assert_eq!(condition.hold, Duration::from_secs(60));
+        assert!(condition.holds(&process("worker", 0.0, 5.0 * 1024.0)));
+        assert!(!condition.holds(&process("worker", 0.0, 4.0 * 1024.0)));
+        assert_eq!("rss > 4GiB".parse::<Condition>().unwrap().hold, Duration::ZERO);
+    }
+
+    #[test]
+    fn greater_or_equal_is_not_read_as_greater() {
+        let at_least: Condition = "cpu >= 90".parse().unwrap();
+        let above: Condition = "cpu > 90".parse().unwrap();
+        assert_eq!(at_least.op, Op::Ge);
+        assert_eq!(above.op, Op::Gt);
+        assert!(at_least.holds(&process("worker", 90.0, 0.0)));
+        assert!(!above.holds(&process("worker", 90.0, 0.0)));
+        assert!(above.holds(&process("worker", 90.5, 0.0)));
+    }
+
+    #[test]
+    fn glob_conditions() {
+        let matches: Condition = "command ~ 'worker*'".parse().unwrap();
+        let spelled: Condition = "command matches 'worker*'".parse().unwrap();
+        let not_matches: Condition = "command !matches 'worker*'".parse().unwrap();
+        assert_eq!(not_matches.op, Op::NotMatches);
+        assert_eq!("command !~ worker*".parse::<Condition>().unwrap().op, Op::NotMatches);
+        for (command, matched) in [("worker-3", true), ("postgres", false)] {
+            assert_eq!(matches.holds(&process(command, 0.0, 0.0)), matched, "{}", command);
+            assert_eq!(spelled.holds(&process(command, 0.0, 0.0)), matched, "{}", command);
+            assert_eq!(not_matches.holds(&process(command, 0.0, 0.0)), !matched, "{}", command);
+        }
+    }
+
+    #[test]
+    fn bad_conditions_are_errors() {
+        for text in ["rss 4G", "size > 1", "rss > lots", "command > a", "command ~ '[w'", "> 5"] {
+            assert!(text.parse::<Condition>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn rate_limits() {
+        assert_eq!(parse_rate_limit("5/1h"), Ok((5, 3600)));
+        assert_eq!(parse_rate_limit(" 2 / 30s"), Ok((2, 30)));
+        for text in ["5", "many/1h", "-1/1h", "5/soon"] {
+            assert!(parse_rate_limit(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use nix::sys::signal::Signal;
+use procfs::process::all_processes;
+use serde::Serialize;
+
+use crate::ctrl::{self, Bypass};
+
+/// Shortest time between two scans of `/proc`.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+/// Commands of processes that adopt orphans besides PID 1: systemd user
+/// managers and container inits set themselves up as child subreapers. Matched
+/// against the whole `comm`, which the kernel cuts to 15 bytes, so that
+/// `systemd-journald` and friends do not count.
+const SUBREAPERS: [&str; 5] = ["systemd", "tini", "dumb-init", "catatonit", "containerd-shim"];
+
+/// An exited process whose parent has not collected its exit status yet.
+#[derive(Clone, Debug, Serialize)]
+pub struct Zombie {
+    pub pid: i32,
+    pub command: String,
+    /// How long it has been a zombie, as far as ProcMaster has seen.
+    pub seconds: u64,
+    /// Already a zombie when ProcMaster started looking, so it may be older.
+    pub at_least: bool,
+}
+
+/// A process with zombie children. Only the parent can reap them, by waiting
+/// for them; once it exits they are reparented and its reaper collects them.
+#[derive(Clone, Debug, Serialize)]
+pub struct ZombieParent {
+    pub pid: i32,
+    pub command: String,
+    pub state: char,
+    /// Oldest first.
+    pub zombies: Vec<Zombie>,
+}
+
+impl ZombieParent {
+    /// What to try next for these zombies.
+    pub fn advice(&self) -> &'static str {
+        if self.pid == 1 {
+            "init reaps its own zombies; if these stay, init is stuck"
+        } else if self.state == 'T' {
+            "The parent is stopped and cannot reap them; resume it"
+        } else {
+            "Send SIGCHLD; if they stay, the parent is not reaping, terminate it so its reaper does"
+        }
+    }
+}
+
+/// A process that lost its parent and was adopted by init or a subreaper.
+#[derive(Clone, Debug, Serialize)]
+pub struct Orphan {
+    pub pid: i32,
+    pub command: String,
+    pub reaper: i32,
+    pub reaper_command: String,
+    /// Session it still belongs to, led by a process that is not its reaper.
+    pub session: i32,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ZombieReport {
+    /// Most zombies first.
+    pub parents: Vec<ZombieParent>,
+    pub orphans: Vec<Orphan>,
+}
+
+/// Finds zombies, grouped by the parent that should reap them, and orphans.
+/// Zombie ages come from remembering when each one was first seen, so
+/// [`Zombies::update`] has to be called regularly.
+pub struct Zombies {
+    started: Instant,
+    last_scan: Option<Instant>,
+    /// When each zombie, by PID and start time, was first seen.
+    first_seen: HashMap<(i32, u64), Instant>,
+    report: ZombieReport,
+}
+
+impl Default for Zombies {
+    fn default() -> Self {
+        Zombies { started: Instant::now(), last_scan: None, first_seen: HashMap::new(), report: ZombieReport::default() }
+    }
+}
+
+impl Zombies {
+    /// Rescans `/proc`, unless the last scan was less than a second ago.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        if self.last_scan.is_some_and(|scan| now.duration_since(scan) < SCAN_INTERVAL) {
+            return;
+        }
+        let first_scan = self.last_scan.is_none();
+        self.last_scan = Some(now);
+        let Ok(processes) = all_processes() else { return };
+
+        let stats: HashMap<i32, _> = processes.flatten()
+            .filter_map(|process| process.stat().ok())
+            .map(|stat| (stat.pid, stat))
+            .collect();
+        let command = |pid: i32| stats.get(&pid).map_or_else(|| "?".to_string(), |stat| stat.comm.clone());
+
+        let mut parents: HashMap<i32, Vec<Zombie>> = HashMap::new();
+        let mut first_seen = HashMap::new();
+        let mut orphans = Vec::new();
+        for stat in stats.values() {
+            if stat.state == 'Z' {
+                // Zombies already there on the first scan are dated from when
+                // ProcMaster started, which is a lower bound.
+                let key = (stat.pid, stat.starttime);
+                let since = self.first_seen.get(&key).copied().unwrap_or(if first_scan { self.started } else { now });
+                first_seen.insert(key, since);
+                parents.entry(stat.ppid).or_default().push(Zombie {
+                    pid: stat.pid,
+                    command: stat.comm.clone(),
+                    seconds: now.duration_since(since).as_secs(),
+                    at_least: since == self.started,
+                });
+            } else if is_reaper(stat.ppid, &command(stat.ppid))
+                && stat.session > 0
+                && stat.session != stat.pid
+                && stats.get(&stat.ppid).is_none_or(|parent| parent.session != stat.session)
+            {
+                // Started by something in another session that has since exited.
+                orphans.push(Orphan {
+                    pid: stat.pid,
+                    command: stat.comm.clone(),
+                    reaper: stat.ppid,
+                    reaper_command: command(stat.ppid),
+                    session: stat.session,
+                });
+            }
+        }
+        self.first_seen = first_seen;
+
+        let mut parents: Vec<ZombieParent> = parents.into_iter()
+            .map(|(pid, mut zombies)| {
+                zombies.sort_by(|a, b| b.seconds.cmp(&a.seconds).then(a.pid.cmp(&b.pid)));
+                ZombieParent {
+                    pid,
+                    command: command(pid),
+                    state: stats.get(&pid).map_or('?', |stat| stat.state),
+                    zombies,
+                }
+            })
+            .collect();
+        parents.sort_by(|a, b| b.zombies.len().cmp(&a.zombies.len()).then(a.pid.cmp(&b.pid)));
+        orphans.sort_by_key(|orphan| orphan.pid);
+        self.report = ZombieReport { parents, orphans };
+    }
+
+    pub fn report(&self) -> &ZombieReport {
+        &self.report
+    }
+}
+
+/// Whether `pid` adopts orphans: PID 1, or a known subreaper.
+fn is_reaper(pid: i32, command: &str) -> bool {
+    pid == 1 || SUBREAPERS.contains(&command)
+}
+
+/// Sends SIGCHLD to the parent of zombies, which makes one that reaps in its
+/// SIGCHLD handler but missed a signal collect them. The usual parents are
+/// protected (init, the session's shell), but SIGCHLD is harmless, so it skips
+/// the guard.
+pub fn nudge_parent(parent: i32) -> Result<(), String> {
+    ctrl::send_signal(parent, Signal::SIGCHLD, Bypass::None)
+}